@@ -0,0 +1,71 @@
+//! Lookups of registered types from several threads at once.
+//!
+//! Run with `cargo bench --bench registry`. Each line prints the lookups per
+//! second of all threads together, for `registry::lookup` and for a single
+//! `RwLock` around a map, as the registry was stored before it was sharded.
+//! With enough cores, the registry rate should grow with the thread count.
+
+extern crate typedef;
+
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Instant;
+
+use typedef::{registry, TypeDef};
+
+const LOOKUPS: usize = 1_000_000;
+
+macro_rules! register_arrays {
+    ($($len:expr),+) => {
+        vec![$(registry::register::<[u8; $len]>()),+]
+    };
+}
+
+fn main() {
+    let typedefs: Vec<TypeDef> = register_arrays!(
+        1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30,
+        31, 32
+    );
+    let ids: Arc<Vec<TypeId>> = Arc::new(typedefs.iter().map(TypeDef::id).collect());
+    let baseline: Arc<RwLock<BTreeMap<TypeId, TypeDef>>> =
+        Arc::new(RwLock::new(typedefs.iter().map(|typedef| (typedef.id(), *typedef)).collect()));
+
+    for &threads in &[1, 2, 4, 8] {
+        let sharded = run(threads, &ids, registry::lookup);
+        let baseline = {
+            let baseline = baseline.clone();
+            run(threads, &ids, move |id| baseline.read().unwrap().get(&id).cloned())
+        };
+        println!(
+            "{} threads: registry {:.0} lookups/s, single lock {:.0} lookups/s",
+            threads, sharded, baseline
+        );
+    }
+}
+
+/// Look up all ids over and over from the threads, and get the lookups per
+/// second of all threads together.
+fn run<F>(threads: usize, ids: &Arc<Vec<TypeId>>, lookup: F) -> f64
+where
+    F: Fn(TypeId) -> Option<TypeDef> + Clone + Send + 'static,
+{
+    let start = Instant::now();
+    let handles: Vec<_> = (0..threads)
+        .map(|thread| {
+            let ids = ids.clone();
+            let lookup = lookup.clone();
+            thread::spawn(move || {
+                for index in 0..LOOKUPS {
+                    let id = ids[(index + thread) % ids.len()];
+                    assert!(lookup(id).is_some());
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    (threads * LOOKUPS) as f64 / start.elapsed().as_secs_f64()
+}
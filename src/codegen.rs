@@ -0,0 +1,529 @@
+//! Mapping of type names to identifiers of other languages, for code generators.
+//!
+//! This module is not available if this crate is compiled without the `alloc` feature.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use format::{self, Token, Tokens};
+use typename::{self, GenericArg, Signature, TypeName};
+use PathStyle;
+
+/// Rules used by `render_c_ident`.
+///
+/// ```
+/// use typedef::PathStyle;
+/// use typedef::codegen::{ render_c_ident, CIdentRules };
+///
+/// const RULES: CIdentRules = CIdentRules::new()
+///     .paths(PathStyle::Qualified)
+///     .prefix("rs_");
+///
+/// assert_eq!(render_c_ident("alloc::vec::Vec<u8>", &RULES), "rs_alloc_vec_Vec_u8");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CIdentRules {
+    paths: PathStyle,
+    separator: &'static str,
+    prefix: &'static str,
+    c_primitives: bool,
+}
+
+impl CIdentRules {
+    /// Create the default rules.
+    pub const fn new() -> CIdentRules {
+        CIdentRules {
+            paths: PathStyle::Short,
+            separator: "_",
+            prefix: "",
+            c_primitives: false,
+        }
+    }
+
+    /// Set how paths are mapped. The default is `PathStyle::Short`, which
+    /// keeps only the type names. With `PathStyle::Qualified`, every path
+    /// segment becomes a part of the identifier.
+    pub const fn paths(mut self, paths: PathStyle) -> CIdentRules {
+        self.paths = paths;
+        self
+    }
+
+    /// Set the separator written between parts of the identifier. The
+    /// default is `_`. It should contain only ASCII letters, digits and `_`.
+    pub const fn separator(mut self, separator: &'static str) -> CIdentRules {
+        self.separator = separator;
+        self
+    }
+
+    /// Set the prefix written before the identifier. There is no prefix by
+    /// default. It should contain only ASCII letters, digits and `_`.
+    pub const fn prefix(mut self, prefix: &'static str) -> CIdentRules {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Set whether Rust primitive types are mapped to C type names, as `u8`
+    /// to `uint8_t` and `f64` to `double`. Disabled by default. A name that
+    /// is only a primitive type gives the C type name as it is, without the
+    /// `_` added to other C keywords.
+    pub const fn c_primitives(mut self, c_primitives: bool) -> CIdentRules {
+        self.c_primitives = c_primitives;
+        self
+    }
+}
+
+impl Default for CIdentRules {
+    fn default() -> CIdentRules {
+        CIdentRules::new()
+    }
+}
+
+/// C keywords that are not allowed as identifiers.
+const C_KEYWORDS: &[&str] = &[
+    "auto", "bool", "break", "case", "char", "const", "continue", "default", "do", "double",
+    "else", "enum", "extern", "false", "float", "for", "goto", "if", "inline", "int", "long",
+    "register", "restrict", "return", "short", "signed", "sizeof", "static", "struct", "switch",
+    "true", "typedef", "union", "unsigned", "void", "volatile", "while",
+];
+
+/// Convert a type name into a C identifier.
+///
+/// The identifier is made of parts separated with `_`: the names of types,
+/// generic arguments in order, and words for types without names, which
+/// are `ref` and `ref_mut` for references, `ptr` and `ptr_mut` for raw
+/// pointers, `slice`, `array` followed by the length, `tuple`, `unit`, `fn`
+/// and `ret` before the return type, `variadic` for `...`, and `never`.
+/// Lifetimes are removed.
+/// The result matches `[A-Za-z_][A-Za-z0-9_]*` and is not a C keyword,
+/// unless it is a C type from `CIdentRules::c_primitives`; `_` is added when
+/// needed. The same name always gives the same identifier.
+///
+/// ```
+/// use typedef::codegen::{ render_c_ident, CIdentRules };
+///
+/// let rules = CIdentRules::new();
+///
+/// assert_eq!(render_c_ident("alloc::vec::Vec<u8>", &rules), "Vec_u8");
+/// assert_eq!(render_c_ident("&'static [u8; 4]", &rules), "ref_array_u8_4");
+/// assert_eq!(render_c_ident("(u8, &mut u16)", &rules.c_primitives(true)), "tuple_uint8_t_ref_mut_uint16_t");
+/// ```
+pub fn render_c_ident(name: &str, rules: &CIdentRules) -> String {
+    let mut ident = CIdent {
+        out: String::with_capacity(rules.prefix.len() + name.len()),
+        rules,
+        primitive: false,
+    };
+    let mut tokens = Tokens::new(name);
+    let mut prev = None;
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Ident("dyn") | Token::Ident("mut") | Token::Ident("const") | Token::Ident("for") => {},
+            Token::Ident(first) => {
+                let path = tokens.take_path(first);
+                let strip = match rules.paths {
+                    PathStyle::Qualified => false,
+                    PathStyle::Short => true,
+                    PathStyle::StripStd => matches!(format::strip_disambiguator(first), "std" | "core" | "alloc"),
+                };
+                if strip {
+                    ident.identifier(path.rsplit("::").next().unwrap_or(path));
+                } else {
+                    for segment in path.split("::") {
+                        ident.identifier(segment);
+                    }
+                }
+            },
+            Token::Lifetime(_) | Token::PathSep => {},
+            Token::Punct("&") if tokens.skip_if(&[Token::Ident("mut")]) => ident.word("ref_mut"),
+            Token::Punct("&") => ident.word("ref"),
+            Token::Punct("*") if tokens.peek(&[Token::Ident("mut")]) => ident.word("ptr_mut"),
+            Token::Punct("*") => ident.word("ptr"),
+            Token::Punct("[") if is_array(tokens.clone()) => ident.word("array"),
+            Token::Punct("[") => ident.word("slice"),
+            Token::Punct("(") if tokens.skip_if(&[Token::Punct(")")]) => ident.word("unit"),
+            Token::Punct("(") if !matches!(prev, Some(Token::Ident(_))) => ident.word("tuple"),
+            Token::Punct("->") => ident.word("ret"),
+            Token::Punct("!") => ident.word("never"),
+            Token::Punct(".") if tokens.skip_if(&[Token::Punct("."), Token::Punct(".")]) => ident.word("variadic"),
+            Token::Punct("#") => {
+                tokens.next();
+            },
+            Token::Punct(literal) if literal.starts_with('"') => ident.identifier(literal.trim_matches('"')),
+            Token::Punct(_) => {},
+        }
+        if token != Token::Punct(" ") {
+            prev = Some(token);
+        }
+    }
+
+    ident.finish()
+}
+
+/// Check if the brackets after already consumed `[` are an array type.
+fn is_array(tokens: Tokens) -> bool {
+    let mut depth = 0;
+    for token in tokens {
+        match token {
+            Token::Punct("[") | Token::Punct("(") | Token::Punct("<") => depth += 1,
+            Token::Punct("]") if depth == 0 => return false,
+            Token::Punct("]") | Token::Punct(")") | Token::Punct(">") => depth -= 1,
+            Token::Punct(";") if depth == 0 => return true,
+            _ => {},
+        }
+    }
+    false
+}
+
+struct CIdent<'a> {
+    out: String,
+    rules: &'a CIdentRules,
+    /// The identifier is only a C primitive type, which is kept as it is.
+    primitive: bool,
+}
+
+impl CIdent<'_> {
+    fn word(&mut self, word: &str) {
+        self.primitive = false;
+        if !self.out.is_empty() {
+            self.out.push_str(self.rules.separator);
+        }
+        self.out.push_str(word);
+    }
+
+    /// Write an identifier from the type name, escaping characters that are
+    /// not allowed in C identifiers.
+    fn identifier(&mut self, identifier: &str) {
+        let identifier = format::strip_disambiguator(identifier);
+        if identifier == "{{closure}}" {
+            return self.word("closure");
+        }
+        if self.rules.c_primitives {
+            if let Some(primitive) = c_primitive(identifier) {
+                self.word(primitive);
+                self.primitive = self.out == primitive;
+                return;
+            }
+        }
+        self.word("");
+        for c in identifier.chars() {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                self.out.push(c);
+            } else {
+                let _ = write!(self.out, "u{:x}", c as u32);
+            }
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut ident = self.out;
+        if !ident.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            ident.insert(0, '_');
+        }
+        if !self.primitive && C_KEYWORDS.contains(&ident.as_str()) {
+            ident.push('_');
+        }
+        ident.insert_str(0, self.rules.prefix);
+        ident
+    }
+}
+
+fn c_primitive(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "u8" => "uint8_t",
+        "u16" => "uint16_t",
+        "u32" => "uint32_t",
+        "u64" => "uint64_t",
+        "i8" => "int8_t",
+        "i16" => "int16_t",
+        "i32" => "int32_t",
+        "i64" => "int64_t",
+        "usize" => "size_t",
+        "isize" => "ptrdiff_t",
+        "f32" => "float",
+        "f64" => "double",
+        "bool" => "bool",
+        _ => return None,
+    })
+}
+
+/// Mapping of custom types used by `render_typescript`.
+///
+/// ```
+/// use typedef::codegen::{ render_typescript, TsTypes };
+///
+/// const TYPES: TsTypes = TsTypes::new()
+///     .types(&[("my_crate::Uuid", "string"), ("Timestamp", "Date")]);
+///
+/// assert_eq!(render_typescript("alloc::vec::Vec<my_crate::Uuid>", &TYPES), "string[]");
+/// assert_eq!(render_typescript("core::option::Option<my_crate::time::Timestamp>", &TYPES), "Date | null");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TsTypes {
+    types: &'static [(&'static str, &'static str)],
+}
+
+impl TsTypes {
+    /// Create the default mapping, which only knows types of the standard
+    /// library.
+    pub const fn new() -> TsTypes {
+        TsTypes {
+            types: &[],
+        }
+    }
+
+    /// Set TypeScript types of custom types, as pairs of Rust type path and
+    /// TypeScript type. The Rust path can be the full path without generic
+    /// arguments, as `my_crate::Uuid`, or only the type name, as `Uuid`.
+    /// Custom types take precedence over types of the standard library.
+    pub const fn types(mut self, types: &'static [(&'static str, &'static str)]) -> TsTypes {
+        self.types = types;
+        self
+    }
+}
+
+impl Default for TsTypes {
+    fn default() -> TsTypes {
+        TsTypes::new()
+    }
+}
+
+/// Convert a type name into a TypeScript type.
+///
+/// Types are mapped the way they are usually serialized to JSON:
+///
+/// - numbers to `number`, and `u128` and `i128` to `bigint`;
+/// - `bool` to `boolean`, and `char`, strings and `PathBuf` to `string`;
+/// - `Option<T>` to `T | null`, with nested options collapsed into one
+///   `| null`, and `()` to `null`;
+/// - `Vec<T>`, slices, arrays and sets to `T[]`, and maps to `Record<K, V>`;
+/// - tuples to `[A, B]`, and `Result<T, E>` to `{ Ok: T } | { Err: E }`;
+/// - references, `Box<T>`, `Rc<T>`, `Arc<T>`, `Cow<T>` and cells to `T`;
+/// - functions and `Fn` traits to `(arg0: A) => R`, with `...rest: unknown[]`
+///   for the `...` of variadic functions.
+///
+/// Other types are written with their name and generic arguments, as
+/// `Page<string>`, and types that can not be represented, such as trait
+/// objects and closures, as `unknown`.
+///
+/// ```
+/// use typedef::codegen::{ render_typescript, TsTypes };
+///
+/// let types = TsTypes::new();
+///
+/// assert_eq!(render_typescript("core::option::Option<alloc::string::String>", &types), "string | null");
+/// assert_eq!(render_typescript("alloc::vec::Vec<core::option::Option<u8>>", &types), "(number | null)[]");
+/// assert_eq!(render_typescript("my_crate::Page<(u8, bool)>", &types), "Page<[number, boolean]>");
+/// ```
+pub fn render_typescript(name: &str, types: &TsTypes) -> String {
+    match typename::parse(name) {
+        Some(ty) => ts_type(&ty, types),
+        None => String::from("unknown"),
+    }
+}
+
+fn ts_type(ty: &TypeName, types: &TsTypes) -> String {
+    match *ty {
+        TypeName::Path { path, ref args, ref signature } => {
+            let name = format::strip_disambiguator(path.rsplit("::").next().unwrap_or(path));
+            let custom = types.types.iter().find(|(rust, _)| *rust == path || *rust == name);
+            if let Some((_, ts)) = custom {
+                return String::from(*ts);
+            }
+            if let Some(ref signature) = *signature {
+                return ts_function(signature, types);
+            }
+            let args: Vec<&TypeName> = args.iter().filter_map(|arg| match *arg {
+                GenericArg::Type(ref ty) => Some(ty),
+                _ => None,
+            }).collect();
+            let arg = |i: usize| args.get(i).map_or(String::from("unknown"), |ty| ts_type(ty, types));
+
+            match name {
+                "u8" | "u16" | "u32" | "u64" | "usize" | "i8" | "i16" | "i32" | "i64" | "isize" | "f32" | "f64" => String::from("number"),
+                "u128" | "i128" => String::from("bigint"),
+                "bool" => String::from("boolean"),
+                "char" | "str" | "String" | "OsStr" | "OsString" | "Path" | "PathBuf" => String::from("string"),
+                "Option" => ts_nullable(arg(0)),
+                "Vec" | "VecDeque" | "LinkedList" | "BinaryHeap" | "HashSet" | "BTreeSet" => ts_array(arg(0)),
+                "HashMap" | "BTreeMap" => alloc::format!("Record<{}, {}>", arg(0), arg(1)),
+                "Result" => alloc::format!("{{ Ok: {} }} | {{ Err: {} }}", arg(0), arg(1)),
+                "Box" | "Rc" | "Arc" | "Cow" | "Cell" | "RefCell" | "Mutex" | "RwLock" => arg(0),
+                _ if args.is_empty() => String::from(name),
+                _ => {
+                    let args: Vec<String> = (0..args.len()).map(arg).collect();
+                    alloc::format!("{}<{}>", name, args.join(", "))
+                },
+            }
+        },
+        TypeName::Ref { ref inner, .. } | TypeName::Ptr { ref inner, .. } => ts_type(inner, types),
+        TypeName::Slice(ref inner) | TypeName::Array(ref inner, _) => ts_array(ts_type(inner, types)),
+        TypeName::Tuple(ref items) if items.is_empty() => String::from("null"),
+        TypeName::Tuple(ref items) => {
+            let items: Vec<String> = items.iter().map(|ty| ts_type(ty, types)).collect();
+            alloc::format!("[{}]", items.join(", "))
+        },
+        TypeName::Fn(ref signature) => ts_function(signature, types),
+        TypeName::Dyn(ref bounds) | TypeName::Impl(ref bounds) => match bounds.traits.first() {
+            Some(bound @ &TypeName::Path { signature: Some(_), .. }) => ts_type(bound, types),
+            _ => String::from("unknown"),
+        },
+        TypeName::Never => String::from("never"),
+        TypeName::Other(_) => String::from("unknown"),
+    }
+}
+
+fn ts_array(item: String) -> String {
+    if item.contains(' ') {
+        alloc::format!("({})[]", item)
+    } else {
+        alloc::format!("{}[]", item)
+    }
+}
+
+fn ts_nullable(item: String) -> String {
+    if is_ts_function(&item) {
+        alloc::format!("({}) | null", item)
+    } else if item == "null" || item.ends_with(" | null") {
+        item
+    } else {
+        alloc::format!("{} | null", item)
+    }
+}
+
+/// Check if a TypeScript type is a function type, which has to be wrapped in
+/// parentheses in a union.
+fn is_ts_function(ts: &str) -> bool {
+    let mut depth = 0usize;
+    let mut rest = ts;
+    while let Some(c) = rest.chars().next() {
+        if depth == 0 && rest.starts_with(" => ") {
+            return true;
+        }
+        if rest.starts_with("=>") {
+            rest = &rest[2..];
+            continue;
+        }
+        match c {
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' => depth = depth.saturating_sub(1),
+            _ => {},
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    false
+}
+
+fn ts_function(signature: &Signature, types: &TsTypes) -> String {
+    let mut args: Vec<String> = signature.args.iter().enumerate()
+        .map(|(i, ty)| alloc::format!("arg{}: {}", i, ts_type(ty, types)))
+        .collect();
+    if signature.variadic {
+        args.push(String::from("...rest: unknown[]"));
+    }
+    let ret = match signature.ret {
+        Some(ref ret) if **ret != TypeName::Tuple(Vec::new()) => ts_type(ret, types),
+        _ => String::from("void"),
+    };
+    alloc::format!("({}) => {}", args.join(", "), ret)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{render_c_ident, render_typescript, CIdentRules, TsTypes};
+    use PathStyle;
+
+    fn c_ident(name: &str) -> String {
+        render_c_ident(name, &CIdentRules::new())
+    }
+
+    #[test]
+    fn should_map_generic_types() {
+        assert_eq!(c_ident("alloc::vec::Vec<u8>"), "Vec_u8");
+        assert_eq!(c_ident("std::collections::hash::map::HashMap<alloc::string::String, alloc::vec::Vec<u8>>"), "HashMap_String_Vec_u8");
+        assert_eq!(c_ident("core::option::Option<&'static str>"), "Option_ref_str");
+        assert_eq!(c_ident("my_crate[4f2a]::Foo<'_, u8>"), "Foo_u8");
+    }
+
+    #[test]
+    fn should_map_types_without_names() {
+        assert_eq!(c_ident("&mut [u8]"), "ref_mut_slice_u8");
+        assert_eq!(c_ident("*const [[u8; 2]]"), "ptr_slice_array_u8_2");
+        assert_eq!(c_ident("*mut u8"), "ptr_mut_u8");
+        assert_eq!(c_ident("()"), "unit");
+        assert_eq!(c_ident("((), (u8, i8))"), "tuple_unit_tuple_u8_i8");
+        assert_eq!(c_ident("fn(u8) -> u16"), "fn_u8_ret_u16");
+        assert_eq!(c_ident("unsafe extern \"C\" fn()"), "unsafe_extern_C_fn_unit");
+        assert_eq!(c_ident("unsafe extern \"C\" fn(i32, ...)"), "unsafe_extern_C_fn_i32_variadic");
+        assert_eq!(c_ident("alloc::boxed::Box<dyn core::ops::function::Fn(u8) + core::marker::Send>"), "Box_Fn_u8_Send");
+        assert_eq!(c_ident("my_crate::main::{{closure}}"), "closure");
+        assert_eq!(c_ident("!"), "never");
+    }
+
+    #[test]
+    fn should_produce_valid_c_identifiers() {
+        assert_eq!(c_ident("char"), "char_");
+        assert_eq!(c_ident("my_crate::Größe"), "Gruf6udfe");
+        assert_eq!(c_ident("1"), "_1");
+        assert_eq!(render_c_ident("bool", &CIdentRules::new().c_primitives(true)), "bool");
+        assert_eq!(render_c_ident("f32", &CIdentRules::new().c_primitives(true)), "float");
+        assert_eq!(render_c_ident("f64", &CIdentRules::new().c_primitives(true)), "double");
+        assert_eq!(render_c_ident("my_crate::double", &CIdentRules::new().c_primitives(true)), "double_");
+        assert_eq!(render_c_ident("u8", &CIdentRules::new().prefix("rs_").c_primitives(true)), "rs_uint8_t");
+    }
+
+    #[test]
+    fn should_apply_rules() {
+        let rules = CIdentRules::new().paths(PathStyle::StripStd).separator("__");
+
+        assert_eq!(render_c_ident("alloc::vec::Vec<my_crate::Foo>", &rules), "Vec__my_crate__Foo");
+    }
+
+    fn ts(name: &str) -> String {
+        render_typescript(name, &TsTypes::new())
+    }
+
+    #[test]
+    fn should_map_typescript_types() {
+        assert_eq!(ts("core::option::Option<alloc::string::String>"), "string | null");
+        assert_eq!(ts("alloc::vec::Vec<&str>"), "string[]");
+        assert_eq!(ts("&[core::option::Option<char>]"), "(string | null)[]");
+        assert_eq!(ts("[u128; 4]"), "bigint[]");
+        assert_eq!(ts("std::collections::hash::map::HashMap<alloc::string::String, alloc::vec::Vec<f64>>"), "Record<string, number[]>");
+        assert_eq!(ts("core::result::Result<u8, alloc::string::String>"), "{ Ok: number } | { Err: string }");
+        assert_eq!(ts("alloc::sync::Arc<std::sync::poison::mutex::Mutex<bool>>"), "boolean");
+        assert_eq!(ts("(u8, ())"), "[number, null]");
+        assert_eq!(ts("fn(u8, &str) -> bool"), "(arg0: number, arg1: string) => boolean");
+        assert_eq!(ts("unsafe extern \"C\" fn(i32, ...)"), "(arg0: number, ...rest: unknown[]) => void");
+        assert_eq!(ts("alloc::boxed::Box<dyn core::ops::function::Fn(u8) + core::marker::Send>"), "(arg0: number) => void");
+        assert_eq!(ts("my_crate::Page<my_crate::User>"), "Page<User>");
+    }
+
+    #[test]
+    fn should_map_typescript_options() {
+        assert_eq!(ts("core::option::Option<fn()>"), "(() => void) | null");
+        assert_eq!(ts("core::option::Option<fn() -> core::option::Option<u8>>"), "(() => number | null) | null");
+        assert_eq!(ts("core::option::Option<alloc::vec::Vec<fn(u8)>>"), "((arg0: number) => void)[] | null");
+        assert_eq!(ts("core::option::Option<core::option::Option<u8>>"), "number | null");
+        assert_eq!(ts("core::option::Option<core::option::Option<core::option::Option<u8>>>"), "number | null");
+        assert_eq!(ts("core::option::Option<()>"), "null");
+    }
+
+    #[test]
+    fn should_map_unknown_typescript_types() {
+        assert_eq!(ts("dyn core::any::Any"), "unknown");
+        assert_eq!(ts("my_crate::main::{{closure}}"), "unknown");
+        assert_eq!(ts("Vec<u8"), "unknown");
+        assert_eq!(ts("!"), "never");
+    }
+
+    #[test]
+    fn should_prefer_custom_typescript_types() {
+        const TYPES: TsTypes = TsTypes::new().types(&[("my_crate::Id", "string"), ("Vec", "Array<number>")]);
+
+        assert_eq!(render_typescript("core::option::Option<my_crate::Id>", &TYPES), "string | null");
+        assert_eq!(render_typescript("other::Id", &TYPES), "Id");
+        assert_eq!(render_typescript("alloc::vec::Vec<u8>", &TYPES), "Array<number>");
+    }
+}
@@ -0,0 +1,665 @@
+//! Demangling of symbol names into type names.
+
+use alloc::string::String;
+use core::fmt::Write;
+#[cfg(feature = "std")]
+use registry;
+#[cfg(feature = "std")]
+use TypeDef;
+
+/// Demangle a Rust symbol name, in the legacy or the v0 scheme, into a name
+/// written the way `core::any::type_name` writes names.
+///
+/// Crate disambiguators, the hash of legacy symbols and lifetimes are
+/// removed, closures are written as `{{closure}}` and `Fn` traits with their
+/// signature, as `dyn Fn(u8) -> bool`. Suffixes added to symbols by LLVM and
+/// linkers, as `.llvm.1234` or `.cold`, are ignored. Returns `None` if the
+/// symbol is not a valid Rust symbol.
+///
+/// This is only available if this crate is compiled with `features = ["demangle"]`.
+///
+/// ```
+/// use typedef::demangle;
+///
+/// assert_eq!(demangle("_RINvCs4Jz2Mtfmu5I_1v1fINtNtCslNYArtu3iFV_5alloc3vec3VechEEB2_").unwrap(), "v::f<alloc::vec::Vec<u8>>");
+/// assert_eq!(demangle("_ZN4core3ptr13drop_in_place17h0123456789abcdefE").unwrap(), "core::ptr::drop_in_place");
+/// ```
+pub fn demangle(symbol: &str) -> Option<String> {
+    // Symbols can have an extra `_` prefix on some platforms.
+    let symbol = if symbol.starts_with("__") { &symbol[1..] } else { symbol };
+    if let Some(v0) = symbol.strip_prefix("_R") {
+        // Identifiers of v0 symbols never contain `.`, so it starts a suffix.
+        let v0 = &v0[..v0.find('.').unwrap_or(v0.len())];
+        let mut demangler = V0 {
+            sym: v0.as_bytes(),
+            pos: 0,
+            out: String::new(),
+            depth: 0,
+        };
+        if demangler.peek().is_some_and(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        demangler.path()?;
+        return Some(demangler.out);
+    }
+    let legacy = symbol.strip_prefix("_ZN").or_else(|| symbol.strip_prefix("ZN"))?;
+    demangle_legacy(legacy)
+}
+
+/// Demangle a symbol name with `demangle` and find the registered typedef
+/// of the type it names, with `registry::lookup_name`.
+///
+/// Symbols of functions in impls, as `<my_crate::Order as Clone>::clone`,
+/// find the type of the impl if the whole name is not a registered type.
+/// Returns `None` if the symbol is not valid or no such type is registered.
+///
+/// This is only available if this crate is compiled with `features = ["demangle"]`
+/// and the `std` feature.
+///
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
+/// use typedef::{ demangle_typedef, registry, TypeDef };
+///
+/// registry::register::<u8>();
+///
+/// assert_eq!(demangle_typedef("_RNvXCs4Jz2Mtfmu5I_1vhNtNtCsgEmfK2I1SDS_4core5clone5Clone5clone"), Some(TypeDef::of::<u8>()));
+/// assert_eq!(demangle_typedef("_ZN3foo3BarE"), None);
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub fn demangle_typedef(symbol: &str) -> Option<TypeDef> {
+    let name = demangle(symbol)?;
+    registry::lookup_name(&name).or_else(|| registry::lookup_name(impl_self_type(&name)?))
+}
+
+/// Get the type of the impl in the name of an impl item, as `T` in
+/// `<T as Trait>::item` and `<T>::item`.
+#[cfg(feature = "std")]
+fn impl_self_type(name: &str) -> Option<&str> {
+    let inner = name.strip_prefix('<')?;
+    let mut depth = 0usize;
+    let mut pos = 0;
+    while pos < inner.len() {
+        let rest = &inner[pos..];
+        if depth == 0 && (rest.starts_with(" as ") || rest.starts_with('>')) {
+            return Some(&inner[..pos]);
+        }
+        if rest.starts_with("->") {
+            pos += 2;
+            continue;
+        }
+        match rest.as_bytes()[0] {
+            b'<' | b'(' | b'[' => depth += 1,
+            b'>' | b')' | b']' => depth = depth.saturating_sub(1),
+            _ => {},
+        }
+        pos += rest.chars().next().map_or(1, char::len_utf8);
+    }
+    None
+}
+
+fn demangle_legacy(mut rest: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut first = true;
+    while !rest.starts_with('E') {
+        let len_end = rest.find(|c: char| !c.is_ascii_digit())?;
+        let len: usize = rest[..len_end].parse().ok()?;
+        let segment = rest.get(len_end..len_end + len)?;
+        rest = &rest[len_end + len..];
+        if is_legacy_end(rest) && is_legacy_hash(segment) {
+            break;
+        }
+        if !first {
+            out.push_str("::");
+        }
+        first = false;
+        unescape_legacy(segment, &mut out)?;
+    }
+    if !is_legacy_end(rest) {
+        return None;
+    }
+    Some(out)
+}
+
+/// Check if the rest of a legacy symbol is the final `E`, followed by
+/// nothing or a suffix such as `.llvm.1234`.
+fn is_legacy_end(rest: &str) -> bool {
+    rest == "E" || rest.starts_with("E.")
+}
+
+fn is_legacy_hash(segment: &str) -> bool {
+    segment.len() == 17 && segment.starts_with('h') && segment[1..].bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn unescape_legacy(mut segment: &str, out: &mut String) -> Option<()> {
+    if segment.starts_with("_$") {
+        segment = &segment[1..];
+    }
+    while !segment.is_empty() {
+        if let Some(rest) = segment.strip_prefix("..") {
+            out.push_str("::");
+            segment = rest;
+        } else if let Some(rest) = segment.strip_prefix('$') {
+            let end = rest.find('$')?;
+            let c = match &rest[..end] {
+                "SP" => '@',
+                "BP" => '*',
+                "RF" => '&',
+                "LT" => '<',
+                "GT" => '>',
+                "LP" => '(',
+                "RP" => ')',
+                "C" => ',',
+                code => char::from_u32(u32::from_str_radix(code.strip_prefix('u')?, 16).ok()?)?,
+            };
+            out.push(c);
+            segment = &rest[end + 1..];
+        } else {
+            let end = segment.find(['$', '.']).unwrap_or(segment.len());
+            let end = if end == 0 { 1 } else { end };
+            out.push_str(&segment[..end]);
+            segment = &segment[end..];
+        }
+    }
+    Some(())
+}
+
+/// Demangler of the v0 scheme, writing the name to `out`.
+struct V0<'a> {
+    sym: &'a [u8],
+    pos: usize,
+    out: String,
+    depth: usize,
+}
+
+impl V0<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.sym.get(self.pos).cloned()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn base62(&mut self) -> Option<u64> {
+        if self.eat(b'_') {
+            return Some(0);
+        }
+        let mut value: u64 = 0;
+        loop {
+            let digit = match self.next()? {
+                c @ b'0'..=b'9' => c - b'0',
+                c @ b'a'..=b'z' => c - b'a' + 10,
+                c @ b'A'..=b'Z' => c - b'A' + 36,
+                b'_' => return value.checked_add(1),
+                _ => return None,
+            };
+            value = value.checked_mul(62)?.checked_add(u64::from(digit))?;
+        }
+    }
+
+    fn decimal(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        core::str::from_utf8(&self.sym[start..self.pos]).ok()?.parse().ok()
+    }
+
+    fn disambiguator(&mut self) -> Option<u64> {
+        if self.eat(b's') { self.base62() } else { Some(0) }
+    }
+
+    fn ident(&mut self) -> Option<&str> {
+        if self.eat(b'u') {
+            // Punycode identifiers are not supported.
+            return None;
+        }
+        let len = self.decimal()?;
+        self.eat(b'_');
+        let bytes = self.sym.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        core::str::from_utf8(bytes).ok()
+    }
+
+    /// Demangle the path, type or const that a back reference points to.
+    fn backref(&mut self, f: fn(&mut Self) -> Option<()>) -> Option<()> {
+        let start = self.pos - 1;
+        let target = self.base62()? as usize;
+        if target >= start || self.depth > 100 {
+            return None;
+        }
+        let pos = self.pos;
+        self.pos = target;
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        self.pos = pos;
+        result
+    }
+
+    fn path(&mut self) -> Option<()> {
+        match self.next()? {
+            b'C' => {
+                self.disambiguator()?;
+                let name: String = self.ident()?.into();
+                self.out.push_str(&name);
+            },
+            b'N' => {
+                let namespace = self.next()?;
+                self.path()?;
+                self.disambiguator()?;
+                let name: String = self.ident()?.into();
+                match namespace {
+                    b'C' => self.out.push_str("::{{closure}}"),
+                    b'S' => self.out.push_str("::{{shim}}"),
+                    b'a'..=b'z' => {
+                        self.out.push_str("::");
+                        self.out.push_str(&name);
+                    },
+                    _ => return None,
+                }
+            },
+            b'M' => {
+                self.disambiguator()?;
+                self.skip_path()?;
+                self.out.push('<');
+                self.ty()?;
+                self.out.push('>');
+            },
+            b'X' => {
+                self.disambiguator()?;
+                self.skip_path()?;
+                self.out.push('<');
+                self.ty()?;
+                self.out.push_str(" as ");
+                self.path()?;
+                self.out.push('>');
+            },
+            b'Y' => {
+                self.out.push('<');
+                self.ty()?;
+                self.out.push_str(" as ");
+                self.path()?;
+                self.out.push('>');
+            },
+            b'I' => {
+                self.path()?;
+                self.generic_args()?;
+            },
+            b'B' => self.backref(Self::path)?,
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// Consume the path of an impl, which is not written out.
+    fn skip_path(&mut self) -> Option<()> {
+        let out = self.out.len();
+        self.path()?;
+        self.out.truncate(out);
+        Some(())
+    }
+
+    fn generic_args(&mut self) -> Option<()> {
+        let mut first = true;
+        self.out.push('<');
+        while !self.eat(b'E') {
+            if !first {
+                self.out.push_str(", ");
+            }
+            first = false;
+            if self.eat(b'L') {
+                self.base62()?;
+                self.out.push_str("'_");
+            } else if self.eat(b'K') {
+                self.constant()?;
+            } else {
+                self.ty()?;
+            }
+        }
+        self.out.push('>');
+        Some(())
+    }
+
+    fn ty(&mut self) -> Option<()> {
+        let c = self.peek()?;
+        if let Some(basic) = basic_type(c) {
+            self.pos += 1;
+            self.out.push_str(basic);
+            return Some(());
+        }
+        match c {
+            b'C' | b'N' | b'M' | b'X' | b'Y' | b'I' => return self.path(),
+            _ => self.pos += 1,
+        }
+        match c {
+            b'A' => {
+                self.out.push('[');
+                self.ty()?;
+                self.out.push_str("; ");
+                self.constant()?;
+                self.out.push(']');
+            },
+            b'S' => {
+                self.out.push('[');
+                self.ty()?;
+                self.out.push(']');
+            },
+            b'T' => {
+                self.out.push('(');
+                let mut count = 0;
+                while !self.eat(b'E') {
+                    if count > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.ty()?;
+                    count += 1;
+                }
+                if count == 1 {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            },
+            b'R' | b'Q' => {
+                if self.eat(b'L') {
+                    self.base62()?;
+                }
+                self.out.push_str(if c == b'R' { "&" } else { "&mut " });
+                self.ty()?;
+            },
+            b'P' => {
+                self.out.push_str("*const ");
+                self.ty()?;
+            },
+            b'O' => {
+                self.out.push_str("*mut ");
+                self.ty()?;
+            },
+            b'F' => self.fn_sig()?,
+            b'D' => {
+                self.out.push_str("dyn ");
+                self.dyn_bounds()?;
+                self.eat(b'L').then_some(())?;
+                self.base62()?;
+            },
+            b'B' => self.backref(Self::ty)?,
+            _ => return None,
+        }
+        Some(())
+    }
+
+    fn binder(&mut self) -> Option<()> {
+        if self.eat(b'G') {
+            self.base62()?;
+        }
+        Some(())
+    }
+
+    fn fn_sig(&mut self) -> Option<()> {
+        self.binder()?;
+        if self.eat(b'U') {
+            self.out.push_str("unsafe ");
+        }
+        if self.eat(b'K') {
+            if self.eat(b'C') {
+                self.out.push_str("extern \"C\" ");
+            } else {
+                let abi = self.ident()?.replace('_', "-");
+                let _ = write!(self.out, "extern \"{}\" ", abi);
+            }
+        }
+        self.out.push_str("fn(");
+        let mut first = true;
+        while !self.eat(b'E') {
+            if !first {
+                self.out.push_str(", ");
+            }
+            first = false;
+            self.ty()?;
+        }
+        self.out.push(')');
+        self.ret()
+    }
+
+    /// Write the return type, which is not written if it is `()`.
+    fn ret(&mut self) -> Option<()> {
+        if self.eat(b'u') {
+            return Some(());
+        }
+        self.out.push_str(" -> ");
+        self.ty()
+    }
+
+    fn dyn_bounds(&mut self) -> Option<()> {
+        self.binder()?;
+        let mut first = true;
+        while !self.eat(b'E') {
+            if !first {
+                self.out.push_str(" + ");
+            }
+            first = false;
+            self.dyn_trait()?;
+        }
+        Some(())
+    }
+
+    fn dyn_trait(&mut self) -> Option<()> {
+        let start = self.out.len();
+        self.path()?;
+        let mut bindings = 0;
+        while self.eat(b'p') {
+            let name: String = self.ident()?.into();
+            if bindings > 0 {
+                self.out.push_str(", ");
+            } else if self.out[start..].ends_with('>') {
+                self.out.pop();
+                self.out.push_str(", ");
+            } else {
+                self.out.push('<');
+            }
+            self.out.push_str(&name);
+            self.out.push_str(" = ");
+            self.ty()?;
+            bindings += 1;
+        }
+        if bindings > 0 {
+            self.out.push('>');
+        }
+        fn_trait_sugar(&mut self.out, start);
+        Some(())
+    }
+
+    fn constant(&mut self) -> Option<()> {
+        let c = self.next()?;
+        match c {
+            b'p' => self.out.push('_'),
+            b'B' => self.backref(Self::constant)?,
+            b'h' | b't' | b'm' | b'y' | b'o' | b'j' | b'a' | b's' | b'l' | b'x' | b'n' | b'i' | b'b' | b'c' => {
+                let negative = self.eat(b'n');
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                    self.pos += 1;
+                }
+                let hex = core::str::from_utf8(&self.sym[start..self.pos]).ok()?;
+                let value = if hex.is_empty() { 0 } else { u128::from_str_radix(hex, 16).ok()? };
+                self.eat(b'_').then_some(())?;
+                match c {
+                    b'b' => self.out.push_str(if value == 0 { "false" } else { "true" }),
+                    b'c' => {
+                        let _ = write!(self.out, "{:?}", char::from_u32(value as u32)?);
+                    },
+                    _ => {
+                        if negative {
+                            self.out.push('-');
+                        }
+                        let _ = write!(self.out, "{}", value);
+                    },
+                }
+            },
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+/// Rewrite `Fn<(A, B), Output = R>` written after `start` to `Fn(A, B) -> R`.
+fn fn_trait_sugar(out: &mut String, start: usize) {
+    let written = &out[start..];
+    let open = match written.find("<(") {
+        Some(open) => open,
+        None => return,
+    };
+    let path = &written[..open];
+    let name = path.rsplit("::").next().unwrap_or(path);
+    if !matches!(name, "Fn" | "FnMut" | "FnOnce") || !written.ends_with('>') {
+        return;
+    }
+    let inner = &written[open + 1..written.len() - 1];
+    let (args, output) = match inner.rfind(", Output = ") {
+        Some(pos) => (&inner[..pos], &inner[pos + ", Output = ".len()..]),
+        None => (inner, "()"),
+    };
+    if !args.ends_with(')') {
+        return;
+    }
+
+    let mut sugar = String::from(path);
+    match args.strip_suffix(",)") {
+        Some(arg) => {
+            sugar.push_str(arg);
+            sugar.push(')');
+        },
+        None => sugar.push_str(args),
+    }
+    if output != "()" {
+        sugar.push_str(" -> ");
+        sugar.push_str(output);
+    }
+    out.truncate(start);
+    out.push_str(&sugar);
+}
+
+fn basic_type(c: u8) -> Option<&'static str> {
+    Some(match c {
+        b'a' => "i8",
+        b'b' => "bool",
+        b'c' => "char",
+        b'd' => "f64",
+        b'e' => "str",
+        b'f' => "f32",
+        b'h' => "u8",
+        b'i' => "isize",
+        b'j' => "usize",
+        b'l' => "i32",
+        b'm' => "u32",
+        b'n' => "i128",
+        b'o' => "u128",
+        b'p' => "_",
+        b's' => "i16",
+        b't' => "u16",
+        b'u' => "()",
+        b'v' => "...",
+        b'x' => "i64",
+        b'y' => "u64",
+        b'z' => "!",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::demangle;
+    use mangle;
+
+    #[test]
+    fn should_demangle_v0_symbols() {
+        assert_eq!(demangle("_RINvCs4Jz2Mtfmu5I_1v1fINtNtCslNYArtu3iFV_5alloc3vec3VechEEB2_").unwrap(), "v::f<alloc::vec::Vec<u8>>");
+        assert_eq!(
+            demangle("_RINvCs4Jz2Mtfmu5I_1v1fINtNtCsgEmfK2I1SDS_4core6option6OptionINtNtCslNYArtu3iFV_5alloc3vec3VechEEEB2_").unwrap(),
+            "v::f<core::option::Option<alloc::vec::Vec<u8>>>"
+        );
+        assert_eq!(
+            demangle("_RINvCs4Jz2Mtfmu5I_1v1fDINtNtNtCsgEmfK2I1SDS_4core3ops8function2FnThEEp6OutputbNtNtBs_6marker4SendEL_EB2_").unwrap(),
+            "v::f<dyn core::ops::function::Fn(u8) -> bool + core::marker::Send>"
+        );
+        assert_eq!(
+            demangle("_RINvCs4Jz2Mtfmu5I_1v1fDNtNtNtCsgEmfK2I1SDS_4core6future6future6Futurep6OutputhEL_EB2_").unwrap(),
+            "v::f<dyn core::future::future::Future<Output = u8>>"
+        );
+        assert_eq!(demangle("_RINvCs4Jz2Mtfmu5I_1v1fFUKCEuEB2_").unwrap(), "v::f<unsafe extern \"C\" fn()>");
+        assert_eq!(demangle("_RINvCs4Jz2Mtfmu5I_1v1fPAhj10_EB2_").unwrap(), "v::f<*const [u8; 16]>");
+        assert_eq!(demangle("_RINvCs4Jz2Mtfmu5I_1v1fThbEEB2_").unwrap(), "v::f<(u8, bool)>");
+    }
+
+    #[test]
+    fn should_demangle_impls_and_closures() {
+        assert_eq!(
+            demangle("_RNvMs4_NtCslNYArtu3iFV_5alloc7raw_vecNtB5_11RawVecInner11finish_growB7_").unwrap(),
+            "<alloc::raw_vec::RawVecInner>::finish_grow"
+        );
+        assert_eq!(demangle("_RNCNvCs4Jz2Mtfmu5I_1v4main0B3_").unwrap(), "v::main::{{closure}}");
+        assert_eq!(demangle("_RNvXCs4Jz2Mtfmu5I_1vNtB2_3FooNtNtCsgEmfK2I1SDS_4core5clone5Clone5clone").unwrap(), "<v::Foo as core::clone::Clone>::clone");
+    }
+
+    #[test]
+    fn should_demangle_legacy_symbols() {
+        assert_eq!(demangle("_ZN4core3ptr13drop_in_place17h0123456789abcdefE").unwrap(), "core::ptr::drop_in_place");
+        assert_eq!(
+            demangle("_ZN66_$LT$alloc..vec..Vec$LT$T$GT$$u20$as$u20$core..ops..drop..Drop$GT$4drop17h0123456789abcdefE").unwrap(),
+            "<alloc::vec::Vec<T> as core::ops::drop::Drop>::drop"
+        );
+        assert_eq!(demangle("__ZN3foo3BarE").unwrap(), "foo::Bar");
+    }
+
+    #[test]
+    fn should_ignore_symbol_suffixes() {
+        assert_eq!(demangle("_ZN3foo17h05af221e174051e9E.llvm.1234").unwrap(), "foo");
+        assert_eq!(demangle("_ZN3foo3barE.cold").unwrap(), "foo::bar");
+        assert_eq!(demangle("_RNvC3foo3bar.llvm.1234").unwrap(), "foo::bar");
+        assert_eq!(demangle("_ZN3foo3barE3baz"), None);
+    }
+
+    #[cfg(all(feature = "std", not(feature = "id-only")))]
+    #[test]
+    fn should_find_typedefs_of_symbols() {
+        use super::demangle_typedef;
+        use registry;
+
+        struct Order;
+
+        let typedef = registry::register::<Order>();
+        let ty = mangle::v0(typedef.name()).unwrap();
+
+        assert_eq!(demangle_typedef(&format!("_RNvXC1v{}NtNtC4core5clone5Clone5clone", ty)), Some(typedef));
+        assert_eq!(demangle_typedef(&format!("_RNvMC1v{}3new.llvm.42", ty)), Some(typedef));
+        assert_eq!(demangle_typedef(&format!("_RINvC1v1f{}E", ty)), None);
+        assert_eq!(demangle_typedef("_ZN3foo3BarE"), None);
+    }
+
+    #[test]
+    fn should_invert_mangled_types() {
+        for name in &["alloc::vec::Vec<u8>", "&mut [(u8, char); 3]", "fn(u8) -> u16", "dyn core::ops::function::FnMut(&str)"] {
+            let symbol = format!("_RINvC1v1f{}E", mangle::v0(name).unwrap());
+            assert_eq!(demangle(&symbol).unwrap(), format!("v::f<{}>", name));
+        }
+    }
+
+    #[test]
+    fn should_reject_invalid_symbols() {
+        assert_eq!(demangle("main"), None);
+        assert_eq!(demangle("_RNvC3foo"), None);
+        assert_eq!(demangle("_RB_"), None);
+        assert_eq!(demangle("_ZN3fooE3bar"), None);
+    }
+}
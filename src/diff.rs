@@ -0,0 +1,321 @@
+//! Structural differences between type names.
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+
+use format;
+use typename::{self, Bounds, GenericArg, Signature, TypeName};
+
+/// Write the type name `left` with the parts that differ from `right`
+/// marked, as in `HashMap<String, [-i32-][+u32+]>`. Module paths are removed
+/// if `short` is set.
+pub fn write_diff(left: &str, right: &str, short: bool, f: &mut dyn fmt::Write) -> fmt::Result {
+    match (typename::parse(left), typename::parse(right)) {
+        (Some(left), Some(right)) => diff(&left, &right, short, f),
+        _ if short => write!(f, "[-{}-][+{}+]", format::strip_paths(left), format::strip_paths(right)),
+        _ => write!(f, "[-{}-][+{}+]", left, right),
+    }
+}
+
+fn diff(left: &TypeName, right: &TypeName, short: bool, f: &mut dyn fmt::Write) -> fmt::Result {
+    if left == right {
+        return left.write(short, f);
+    }
+    match (left, right) {
+        (
+            TypeName::Path { path, args: left_args, signature: left_signature },
+            TypeName::Path { path: right_path, args: right_args, signature: right_signature },
+        ) if path == right_path && left_args.len() == right_args.len() && same_signature_shape(left_signature, right_signature) => {
+            if short {
+                f.write_str(path.rsplit("::").next().unwrap_or(path))?;
+            } else {
+                f.write_str(path)?;
+            }
+            if !left_args.is_empty() {
+                f.write_str("<")?;
+                for (i, (left, right)) in left_args.iter().zip(right_args).enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    diff_arg(left, right, short, f)?;
+                }
+                f.write_str(">")?;
+            }
+            if let (Some(left), Some(right)) = (left_signature, right_signature) {
+                diff_signature(left, right, short, f)?;
+            }
+            Ok(())
+        },
+        (TypeName::Ref { mutable, lifetime, inner: left }, TypeName::Ref { mutable: right_mutable, inner: right, .. })
+            if mutable == right_mutable => {
+            f.write_str("&")?;
+            if let Some(lifetime) = lifetime {
+                write!(f, "{} ", lifetime)?;
+            }
+            if *mutable {
+                f.write_str("mut ")?;
+            }
+            diff(left, right, short, f)
+        },
+        (TypeName::Ptr { mutable, inner: left }, TypeName::Ptr { mutable: right_mutable, inner: right }) if mutable == right_mutable => {
+            f.write_str(if *mutable { "*mut " } else { "*const " })?;
+            diff(left, right, short, f)
+        },
+        (TypeName::Slice(left), TypeName::Slice(right)) => {
+            f.write_str("[")?;
+            diff(left, right, short, f)?;
+            f.write_str("]")
+        },
+        (TypeName::Array(left, len), TypeName::Array(right, right_len)) if len == right_len => {
+            f.write_str("[")?;
+            diff(left, right, short, f)?;
+            write!(f, "; {}]", len)
+        },
+        (TypeName::Tuple(left), TypeName::Tuple(right)) if left.len() == right.len() => {
+            f.write_str("(")?;
+            diff_list(left, right, ", ", short, f)?;
+            if left.len() == 1 {
+                f.write_str(",")?;
+            }
+            f.write_str(")")
+        },
+        (TypeName::Fn(left), TypeName::Fn(right))
+            if left.unsafety == right.unsafety && left.abi == right.abi && left.variadic == right.variadic && left.args.len() == right.args.len() => {
+            if left.unsafety {
+                f.write_str("unsafe ")?;
+            }
+            if let Some(abi) = left.abi {
+                write!(f, "extern \"{}\" ", abi)?;
+            }
+            f.write_str("fn")?;
+            diff_signature(left, right, short, f)
+        },
+        (TypeName::Dyn(left), TypeName::Dyn(right)) if same_bounds_shape(left, right) => {
+            f.write_str("dyn ")?;
+            diff_bounds(left, right, short, f)
+        },
+        (TypeName::Impl(left), TypeName::Impl(right)) if same_bounds_shape(left, right) => {
+            f.write_str("impl ")?;
+            diff_bounds(left, right, short, f)
+        },
+        _ => {
+            f.write_str("[-")?;
+            left.write(short, f)?;
+            f.write_str("-][+")?;
+            right.write(short, f)?;
+            f.write_str("+]")
+        },
+    }
+}
+
+fn diff_arg(left: &GenericArg, right: &GenericArg, short: bool, f: &mut dyn fmt::Write) -> fmt::Result {
+    match (left, right) {
+        (GenericArg::Type(left), GenericArg::Type(right)) => diff(left, right, short, f),
+        (GenericArg::Binding(name, left), GenericArg::Binding(right_name, right)) if name == right_name => {
+            write!(f, "{} = ", name)?;
+            diff(left, right, short, f)
+        },
+        (GenericArg::Lifetime(lifetime), GenericArg::Lifetime(_)) => f.write_str(lifetime),
+        _ => {
+            f.write_str("[-")?;
+            left.write(short, f)?;
+            f.write_str("-][+")?;
+            right.write(short, f)?;
+            f.write_str("+]")
+        },
+    }
+}
+
+fn diff_list(left: &[TypeName], right: &[TypeName], separator: &str, short: bool, f: &mut dyn fmt::Write) -> fmt::Result {
+    for (i, (left, right)) in left.iter().zip(right).enumerate() {
+        if i > 0 {
+            f.write_str(separator)?;
+        }
+        diff(left, right, short, f)?;
+    }
+    Ok(())
+}
+
+/// Bounds are compared one by one if both have as many traits and lifetimes.
+/// The lifetimes themselves are not compared.
+fn same_bounds_shape(left: &Bounds, right: &Bounds) -> bool {
+    left.traits.len() == right.traits.len() && left.lifetimes.len() == right.lifetimes.len()
+}
+
+fn diff_bounds(left: &Bounds, right: &Bounds, short: bool, f: &mut dyn fmt::Write) -> fmt::Result {
+    diff_list(&left.traits, &right.traits, " + ", short, f)?;
+    for lifetime in &left.lifetimes {
+        write!(f, " + {}", lifetime)?;
+    }
+    Ok(())
+}
+
+fn same_signature_shape(left: &Option<Signature>, right: &Option<Signature>) -> bool {
+    match (left, right) {
+        (Some(left), Some(right)) => left.args.len() == right.args.len(),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Write the arguments and the return type, where a missing return type is
+/// `()`.
+fn diff_signature(left: &Signature, right: &Signature, short: bool, f: &mut dyn fmt::Write) -> fmt::Result {
+    f.write_str("(")?;
+    diff_list(&left.args, &right.args, ", ", short, f)?;
+    if left.variadic {
+        f.write_str(if left.args.is_empty() { "..." } else { ", ..." })?;
+    }
+    f.write_str(")")?;
+    let unit = TypeName::Tuple(alloc::vec::Vec::new());
+    match (&left.ret, &right.ret) {
+        (None, None) => Ok(()),
+        (left, right) => {
+            f.write_str(" -> ")?;
+            diff(left.as_ref().map_or(&unit, |ret| &**ret), right.as_ref().map_or(&unit, |ret| &**ret), short, f)
+        },
+    }
+}
+
+/// Similarity of two type names from `0.0` for entirely different names to
+/// `1.0` for names that are the same after `canonicalize`.
+///
+/// This is one minus the edit distance of the canonical names, counted in
+/// characters, divided by the length of the longer canonical name.
+pub fn similarity(left: &str, right: &str) -> f32 {
+    canonical_similarity(&format::canonicalize(left), &format::canonicalize(right))
+}
+
+/// Find the candidates that are most similar to a type name, as suggestions
+/// when a lookup by name fails.
+///
+/// Candidates are scored by the `similarity` of the whole names, or of the
+/// names without module paths if that is higher, so that a name found in a
+/// different module, or given without its path, is still suggested. Returns
+/// the candidates with a score of at least `0.7`, the most similar first.
+/// Candidates that are equally similar keep their order.
+///
+/// This is not available if this crate is compiled without the `alloc` feature.
+///
+/// ```
+/// use typedef::closest_matches;
+///
+/// let registered = ["my_crate::Point", "my_crate::Polygon", "my_crate::Circle"];
+///
+/// assert_eq!(closest_matches("my_crate::Pointt", registered.iter().cloned()), ["my_crate::Point", "my_crate::Polygon"]);
+/// assert_eq!(closest_matches("Circle", registered.iter().cloned()), ["my_crate::Circle"]);
+/// assert!(closest_matches("alloc::string::String", registered.iter().cloned()).is_empty());
+/// ```
+pub fn closest_matches<'a, I: IntoIterator<Item = &'a str>>(name: &str, candidates: I) -> Vec<&'a str> {
+    let name = format::canonicalize(name);
+    let short = format::strip_paths(&name).to_string();
+    let mut matches: Vec<(f32, &'a str)> = candidates
+        .into_iter()
+        .map(|candidate| {
+            let canonical = format::canonicalize(candidate);
+            let similarity = canonical_similarity(&name, &canonical)
+                .max(canonical_similarity(&short, &format::strip_paths(&canonical).to_string()));
+            (similarity, candidate)
+        })
+        .filter(|&(similarity, _)| similarity >= 0.7)
+        .collect();
+    matches.sort_by(|left, right| right.0.total_cmp(&left.0));
+    matches.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+fn canonical_similarity(left: &str, right: &str) -> f32 {
+    let longest = left.chars().count().max(right.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(left, right) as f32 / longest as f32
+}
+
+/// Count the characters that have to be inserted, removed or replaced to
+/// turn `left` into `right`.
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut row: Vec<usize> = (0..=right.len()).collect();
+    for (i, left) in left.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &right) in right.iter().enumerate() {
+            let replaced = diagonal + if left == right { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = replaced.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[right.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::string::String;
+
+    use super::{ closest_matches, edit_distance, similarity, write_diff };
+
+    fn diff(left: &str, right: &str, short: bool) -> String {
+        let mut out = String::new();
+        write_diff(left, right, short, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn should_mark_different_arguments() {
+        let left = "std::collections::hash::map::HashMap<alloc::string::String, i32>";
+        let right = "std::collections::hash::map::HashMap<alloc::string::String, u32>";
+
+        assert_eq!(diff(left, right, true), "HashMap<String, [-i32-][+u32+]>");
+        assert_eq!(diff(left, right, false), "std::collections::hash::map::HashMap<alloc::string::String, [-i32-][+u32+]>");
+        assert_eq!(diff(left, left, true), "HashMap<String, i32>");
+    }
+
+    #[test]
+    fn should_mark_nested_differences() {
+        assert_eq!(diff("alloc::vec::Vec<(u8, &str)>", "alloc::vec::Vec<(u8, &mut str)>", true), "Vec<(u8, [-&str-][+&mut str+])>");
+        assert_eq!(diff("[u8; 4]", "[u16; 4]", true), "[[-u8-][+u16+]; 4]");
+        assert_eq!(diff("[u8; 4]", "[u8; 8]", true), "[-[u8; 4]-][+[u8; 8]+]");
+        assert_eq!(diff("fn(u8) -> u8", "fn(u8)", true), "fn(u8) -> [-u8-][+()+]");
+        assert_eq!(
+            diff("dyn core::ops::function::Fn(u8) + core::marker::Send", "dyn core::ops::function::Fn(u16) + core::marker::Send", true),
+            "dyn Fn([-u8-][+u16+]) + Send"
+        );
+        assert_eq!(diff("core::option::Option<u8>", "core::result::Result<u8, u8>", true), "[-Option<u8>-][+Result<u8, u8>+]");
+    }
+
+    #[test]
+    fn should_score_similarity_of_canonical_names() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(similarity("std::sync::mutex::Mutex<&'static str>", "std::sync::Mutex<&str>"), 1.0);
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("u8", "i8"), 0.5);
+        assert_eq!(similarity("u8", "bool"), 0.0);
+    }
+
+    #[test]
+    fn should_order_closest_matches() {
+        let candidates = ["my_crate::Orders", "alloc::string::String", "my_crate::Order"];
+
+        assert_eq!(
+            closest_matches("my_crate::Ordr", candidates.iter().cloned()),
+            ["my_crate::Order", "my_crate::Orders"]
+        );
+        assert!(closest_matches("u8", candidates.iter().cloned()).is_empty());
+    }
+
+    #[test]
+    fn should_match_names_in_other_modules() {
+        let candidates = ["my_crate::Point", "my_crate::Order", "other::shop::Orders"];
+
+        assert_eq!(closest_matches("Order", candidates.iter().cloned()), ["my_crate::Order", "other::shop::Orders"]);
+        assert_eq!(closest_matches("old_crate::shop::Order", candidates.iter().cloned()), ["my_crate::Order", "other::shop::Orders"]);
+        assert_eq!(closest_matches("alloc::vec::Vec<Order>", ["alloc::vec::Vec<my_crate::Order>"].iter().cloned()), ["alloc::vec::Vec<my_crate::Order>"]);
+    }
+
+    #[test]
+    fn should_mark_whole_names_that_do_not_parse() {
+        assert_eq!(diff("Vec<u8", "alloc::vec::Vec<u8>", false), "[-Vec<u8-][+alloc::vec::Vec<u8>+]");
+    }
+}
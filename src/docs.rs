@@ -0,0 +1,216 @@
+//! Links to rustdoc documentation of types.
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use format;
+
+/// Where documentation of crates is published, used by `TypeDef::docs_url_with`.
+///
+/// Types of `std`, `core` and `alloc` link to the standard library
+/// documentation. Other crates link to the base URL configured with `crates`,
+/// or to the latest version on docs.rs if they are not configured.
+///
+/// ```
+/// # #[cfg(all(feature = "alloc", not(feature = "id-only")))] {
+/// use typedef::{ TypeDef, DocsUrls };
+///
+/// const DOCS: DocsUrls = DocsUrls::new()
+///     .std_base("https://doc.rust-lang.org/nightly/std/")
+///     .crates(&[("my_crate", "https://example.com/docs/")]);
+///
+/// assert_eq!(
+///     TypeDef::of::<Vec<u8>>().docs_url_with(&DOCS).unwrap(),
+///     "https://doc.rust-lang.org/nightly/std/vec/struct.Vec.html"
+/// );
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DocsUrls {
+    std_base: &'static str,
+    crates: &'static [(&'static str, &'static str)],
+    docs_rs: bool,
+}
+
+impl DocsUrls {
+    /// Create the default mapping.
+    pub const fn new() -> DocsUrls {
+        DocsUrls {
+            std_base: "https://doc.rust-lang.org/std/",
+            crates: &[],
+            docs_rs: true,
+        }
+    }
+
+    /// Set the base URL of the standard library documentation. The default
+    /// is `https://doc.rust-lang.org/std/`.
+    pub const fn std_base(mut self, base: &'static str) -> DocsUrls {
+        self.std_base = base;
+        self
+    }
+
+    /// Set base URLs of crates, as pairs of crate name and the URL of the
+    /// crate root documentation directory, ending with `/`.
+    pub const fn crates(mut self, crates: &'static [(&'static str, &'static str)]) -> DocsUrls {
+        self.crates = crates;
+        self
+    }
+
+    /// Set whether crates that are not configured link to docs.rs. When
+    /// disabled, there is no link for their types. Enabled by default.
+    pub const fn docs_rs(mut self, docs_rs: bool) -> DocsUrls {
+        self.docs_rs = docs_rs;
+        self
+    }
+}
+
+impl Default for DocsUrls {
+    fn default() -> DocsUrls {
+        DocsUrls::new()
+    }
+}
+
+/// Enums of the standard library that are commonly used in type names.
+#[cfg(feature = "alloc")]
+const STD_ENUMS: &[&str] = &[
+    "Bound", "ControlFlow", "Cow", "Entry", "ErrorKind", "FpCategory", "Infallible", "IpAddr",
+    "Option", "Ordering", "Poll", "RecvTimeoutError", "Result", "SeekFrom", "SocketAddr",
+    "TryRecvError", "TrySendError", "VarError",
+];
+
+#[cfg(feature = "alloc")]
+pub const PRIMITIVES: &[&str] = &[
+    "bool", "char", "str", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize",
+    "u8", "u16", "u32", "u64", "u128", "usize",
+];
+
+/// Build the URL of the rustdoc page for the outer type of a type name.
+#[cfg(feature = "alloc")]
+pub fn docs_url(name: &str, urls: &DocsUrls) -> Option<String> {
+    let primitive = match name.as_bytes().first()? {
+        b'&' => Some("reference"),
+        b'*' => Some("pointer"),
+        b'!' => Some("never"),
+        b'(' if name == "()" => Some("unit"),
+        b'(' => Some("tuple"),
+        b'[' if name.ends_with("]") && is_array(name) => Some("array"),
+        b'[' => Some("slice"),
+        _ if name.starts_with("fn(") || name.starts_with("unsafe ") || name.starts_with("extern ") => Some("fn"),
+        _ => PRIMITIVES.iter().find(|primitive| **primitive == name).cloned(),
+    };
+    if let Some(primitive) = primitive {
+        return Some(alloc::format!("{}primitive.{}.html", urls.std_base, primitive));
+    }
+
+    let (name, is_trait) = match name.strip_prefix("dyn ") {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+    let path = &name[..name.find(['<', ' ']).unwrap_or(name.len())];
+    if path.contains("{{") || name[path.len()..].starts_with('<') && name.contains(">::") {
+        return None;
+    }
+
+    let mut segments = path.split("::").map(format::strip_disambiguator);
+    let krate = segments.next()?;
+    let modules: alloc::vec::Vec<&str> = segments.collect();
+    let (item, modules) = modules.split_last()?;
+    let is_std = matches!(krate, "std" | "core" | "alloc");
+
+    let mut url = String::new();
+    if is_std {
+        url.push_str(urls.std_base);
+        let modules = modules.join("::");
+        for module in format::std_public_modules(&modules, item).split("::").filter(|module| !module.is_empty()) {
+            url.push_str(module);
+            url.push('/');
+        }
+    } else {
+        match urls.crates.iter().find(|(name, _)| *name == krate) {
+            Some((_, base)) => url.push_str(base),
+            None if urls.docs_rs => url.push_str(&alloc::format!("https://docs.rs/{}/latest/", krate)),
+            None => return None,
+        }
+        url.push_str(krate);
+        url.push('/');
+        for module in modules {
+            url.push_str(module);
+            url.push('/');
+        }
+    }
+
+    let kind = if is_trait {
+        "trait"
+    } else if is_std && STD_ENUMS.contains(item) {
+        "enum"
+    } else {
+        "struct"
+    };
+    url.push_str(kind);
+    url.push('.');
+    url.push_str(item);
+    url.push_str(".html");
+    Some(url)
+}
+
+/// Check if a name that starts with `[` is an array, as in `[u8; 4]`.
+#[cfg(feature = "alloc")]
+fn is_array(name: &str) -> bool {
+    let mut depth = 0;
+    for c in name.chars() {
+        match c {
+            '[' | '(' | '<' => depth += 1,
+            ']' | ')' | '>' => depth -= 1,
+            ';' if depth == 1 => return true,
+            _ => {},
+        }
+    }
+    false
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use super::{docs_url, DocsUrls};
+
+    fn url(name: &str) -> Option<String> {
+        docs_url(name, &DocsUrls::new())
+    }
+
+    #[test]
+    fn should_link_std_types_to_public_modules() {
+        assert_eq!(url("alloc::vec::Vec<u8>").unwrap(), "https://doc.rust-lang.org/std/vec/struct.Vec.html");
+        assert_eq!(url("std::collections::hash::map::HashMap<u8, u8>").unwrap(), "https://doc.rust-lang.org/std/collections/struct.HashMap.html");
+        assert_eq!(url("std::sync::poison::mutex::Mutex<u8>").unwrap(), "https://doc.rust-lang.org/std/sync/struct.Mutex.html");
+        assert_eq!(url("core::sync::atomic::AtomicU8").unwrap(), "https://doc.rust-lang.org/std/sync/atomic/struct.AtomicU8.html");
+        assert_eq!(url("core::option::Option<u8>").unwrap(), "https://doc.rust-lang.org/std/option/enum.Option.html");
+        assert_eq!(url("dyn core::fmt::Debug + core::marker::Send").unwrap(), "https://doc.rust-lang.org/std/fmt/trait.Debug.html");
+    }
+
+    #[test]
+    fn should_link_primitives() {
+        assert_eq!(url("u8").unwrap(), "https://doc.rust-lang.org/std/primitive.u8.html");
+        assert_eq!(url("&str").unwrap(), "https://doc.rust-lang.org/std/primitive.reference.html");
+        assert_eq!(url("[u8]").unwrap(), "https://doc.rust-lang.org/std/primitive.slice.html");
+        assert_eq!(url("[[u8; 2]]").unwrap(), "https://doc.rust-lang.org/std/primitive.slice.html");
+        assert_eq!(url("[u8; 4]").unwrap(), "https://doc.rust-lang.org/std/primitive.array.html");
+        assert_eq!(url("()").unwrap(), "https://doc.rust-lang.org/std/primitive.unit.html");
+        assert_eq!(url("(u8, u8)").unwrap(), "https://doc.rust-lang.org/std/primitive.tuple.html");
+        assert_eq!(url("fn(u8) -> u8").unwrap(), "https://doc.rust-lang.org/std/primitive.fn.html");
+    }
+
+    #[test]
+    fn should_link_other_crates() {
+        const DOCS: DocsUrls = DocsUrls::new().crates(&[("my_crate", "https://example.com/")]);
+
+        assert_eq!(url("serde_json::value::Value").unwrap(), "https://docs.rs/serde_json/latest/serde_json/value/struct.Value.html");
+        assert_eq!(docs_url("my_crate[4f2a]::a::Foo<u8>", &DOCS).unwrap(), "https://example.com/my_crate/a/struct.Foo.html");
+        assert_eq!(docs_url("other::Foo", &DOCS.docs_rs(false)), None);
+    }
+
+    #[test]
+    fn should_not_link_generated_types() {
+        assert_eq!(url("my_crate::main::{{closure}}"), None);
+        assert_eq!(url("my_crate::f<u8>::{{closure}}"), None);
+        assert_eq!(url("Foo"), None);
+    }
+}
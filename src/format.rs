@@ -0,0 +1,1606 @@
+//! Rendering of type names.
+
+use core::cmp;
+use core::fmt::{self, Write};
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+
+/// Remove module paths from every path in a type name, keeping only the last
+/// segment of each.
+///
+/// For example, `std::vec::Vec<alloc::string::String>` becomes `Vec<String>`.
+pub fn strip_paths(name: &str) -> Rendered<'_> {
+    render(name, NameFormat::new().paths(PathStyle::Short))
+}
+
+/// Remove module paths only from paths into the standard library crates,
+/// `std`, `core` and `alloc`, leaving paths into other crates intact.
+///
+/// For example, `alloc::vec::Vec<my_crate::Foo>` becomes `Vec<my_crate::Foo>`.
+#[cfg(any(feature = "alloc", test))]
+pub fn strip_std_paths(name: &str) -> Rendered<'_> {
+    render(name, NameFormat::new().paths(PathStyle::StripStd))
+}
+
+/// Render a type name with the given options.
+pub fn render(name: &str, format: NameFormat) -> Rendered<'_> {
+    render_tagged(name, None, format)
+}
+
+/// Render a type name followed by the name of its tag, separated by ` @ `.
+pub fn render_tagged<'a>(name: &'a str, tag: Option<&'a str>, format: NameFormat) -> Rendered<'a> {
+    Rendered {
+        name,
+        tag,
+        format,
+    }
+}
+
+/// How paths in type names are rendered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PathStyle {
+    /// Keep all paths, as `alloc::vec::Vec<my_crate::Foo>`.
+    Qualified,
+    /// Keep only the last segment of every path, as `Vec<Foo>`.
+    Short,
+    /// Keep only the last segment of paths into `std`, `core` and `alloc`,
+    /// as `Vec<my_crate::Foo>`.
+    StripStd,
+}
+
+/// Options for rendering type names, used by `TypeDef::format_with`.
+///
+/// The options are set with builder methods, starting from `NameFormat::new`,
+/// which renders names as they are returned by `TypeDef::name`, only without
+/// crate disambiguators. The
+/// methods are `const`, so a format can be shared as a constant.
+///
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
+/// use typedef::{ TypeDef, NameFormat, PathStyle };
+///
+/// const LOG_FORMAT: NameFormat = NameFormat::new()
+///     .paths(PathStyle::Short)
+///     .max_depth(1);
+///
+/// let typedef = TypeDef::of::<Result<Vec<Option<u8>>, String>>();
+///
+/// assert_eq!(typedef.format_with(&LOG_FORMAT).to_string(), "Result<Vec<…>, String>");
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NameFormat {
+    paths: PathStyle,
+    max_depth: Option<usize>,
+    max_len: Option<usize>,
+    ellipsis: &'static str,
+    path_separator: &'static str,
+    arg_separator: &'static str,
+    lifetimes: bool,
+    anonymous_lifetimes: bool,
+    disambiguators: bool,
+    closures: bool,
+    multiline: bool,
+    std_public_paths: bool,
+}
+
+impl NameFormat {
+    /// Create options that render type names as they are.
+    pub const fn new() -> NameFormat {
+        NameFormat {
+            paths: PathStyle::Qualified,
+            max_depth: None,
+            max_len: None,
+            ellipsis: "…",
+            path_separator: "::",
+            arg_separator: ", ",
+            lifetimes: true,
+            anonymous_lifetimes: false,
+            disambiguators: false,
+            closures: false,
+            multiline: false,
+            std_public_paths: false,
+        }
+    }
+
+    /// Create options used by `canonicalize`.
+    #[cfg(feature = "alloc")]
+    const fn canonical() -> NameFormat {
+        let mut format = NameFormat::new().lifetimes(false).arg_separator(",");
+        format.std_public_paths = true;
+        format
+    }
+
+    /// Set how paths are rendered. The default is `PathStyle::Qualified`.
+    pub const fn paths(mut self, paths: PathStyle) -> NameFormat {
+        self.paths = paths;
+        self
+    }
+
+    /// Limit how deeply nested generic arguments are rendered.
+    ///
+    /// Generic arguments nested deeper than `depth` are replaced with `…`, so
+    /// with depth `1`, `Result<Vec<u8>, E>` is rendered as `Result<Vec<…>, E>`.
+    /// There is no limit by default.
+    pub const fn max_depth(mut self, depth: usize) -> NameFormat {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Limit the length of rendered names to `len` characters.
+    ///
+    /// Longer names are cut in the middle and the removed part is replaced
+    /// with `…`, so that both the start of the path and the end of the type
+    /// remain visible. With length `30`, `alloc::collections::btree::map::BTreeMap<u8, u8>`
+    /// is rendered as `alloc::collecti…reeMap<u8, u8>`. The ellipsis is counted
+    /// in the length, and is itself cut if it is longer than `len`. There is
+    /// no limit by default.
+    pub const fn max_len(mut self, len: usize) -> NameFormat {
+        self.max_len = Some(len);
+        self
+    }
+
+    /// Set the text that replaces generic arguments nested deeper than
+    /// `max_depth` and the middle of names longer than `max_len`. The default
+    /// is `…`, use `...` for ASCII-only output.
+    pub const fn ellipsis(mut self, ellipsis: &'static str) -> NameFormat {
+        self.ellipsis = ellipsis;
+        self
+    }
+
+    /// Set the separator written between path segments. The default is `::`.
+    pub const fn path_separator(mut self, separator: &'static str) -> NameFormat {
+        self.path_separator = separator;
+        self
+    }
+
+    /// Set the separator written between generic arguments and tuple
+    /// elements. The default is `, `.
+    pub const fn arg_separator(mut self, separator: &'static str) -> NameFormat {
+        self.arg_separator = separator;
+        self
+    }
+
+    /// Set whether lifetimes are rendered. They are rendered by default.
+    ///
+    /// When disabled, lifetime arguments, lifetimes of references, lifetime
+    /// bounds of trait objects and `for<'a>` binders are removed, so
+    /// `Foo<'_, &'static str>` is rendered as `Foo<&str>`.
+    pub const fn lifetimes(mut self, lifetimes: bool) -> NameFormat {
+        self.lifetimes = lifetimes;
+        self
+    }
+
+    /// Set whether lifetimes are rendered as `'_`.
+    ///
+    /// When enabled, lifetimes are canonicalized, so that names which differ
+    /// only in lifetime names render the same: lifetimes of references and
+    /// `for<'a>` binders are removed and all other lifetimes, including
+    /// `'static`, are rendered as `'_`. `Foo<'a, &'static str>` is rendered
+    /// as `Foo<'_, &str>`. Disabled by default. Has no effect if lifetimes
+    /// are not rendered.
+    pub const fn anonymous_lifetimes(mut self, anonymous: bool) -> NameFormat {
+        self.anonymous_lifetimes = anonymous;
+        self
+    }
+
+    /// Set whether crate disambiguators are rendered.
+    ///
+    /// Some toolchains include a hash after crate names to tell apart
+    /// different versions of the same crate, as in `my_crate[4f2a]::Foo`.
+    /// The hashes are removed by default.
+    pub const fn disambiguators(mut self, disambiguators: bool) -> NameFormat {
+        self.disambiguators = disambiguators;
+        self
+    }
+
+    /// Set whether closure types are rendered in a readable way.
+    ///
+    /// When enabled, closures are rendered as `closure@` followed by the path
+    /// of the function that defines them, so `my_crate::main::{{closure}}` is
+    /// rendered as `closure@my_crate::main`. They are rendered as they are by
+    /// default.
+    pub const fn closures(mut self, closures: bool) -> NameFormat {
+        self.closures = closures;
+        self
+    }
+
+    /// Set whether generic arguments are written on separate lines.
+    ///
+    /// When enabled, generic argument lists that contain other generic
+    /// arguments are written like rustfmt would write them, with one argument
+    /// per line, indented by four spaces and followed by a comma. Lists
+    /// without nested generic arguments stay on one line. Names are written
+    /// on one line by default.
+    pub const fn multiline(mut self, multiline: bool) -> NameFormat {
+        self.multiline = multiline;
+        self
+    }
+}
+
+impl Default for NameFormat {
+    fn default() -> NameFormat {
+        NameFormat::new()
+    }
+}
+
+/// Type name rendered with `NameFormat` options, created by `render`.
+///
+/// It is written out without allocation.
+pub struct Rendered<'a> {
+    name: &'a str,
+    tag: Option<&'a str>,
+    format: NameFormat,
+}
+
+impl fmt::Display for Rendered<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let max_len = match self.format.max_len {
+            Some(max_len) => max_len,
+            None => return self.write(f),
+        };
+
+        let mut count = CountChars(0);
+        self.write(&mut count)?;
+        if count.0 <= max_len {
+            return self.write(f);
+        }
+
+        let ellipsis = self.format.ellipsis;
+        if let Some((end, _)) = ellipsis.char_indices().nth(max_len) {
+            return f.write_str(&ellipsis[..end]);
+        }
+        let keep = max_len - ellipsis.chars().count();
+        let tail = keep / 2;
+        self.write(&mut ElideMiddle {
+            out: f,
+            ellipsis: self.format.ellipsis,
+            head: keep - tail,
+            tail_start: count.0 - tail,
+            pos: 0,
+        })
+    }
+}
+
+impl Rendered<'_> {
+    fn write(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        self.write_name(self.name, out)?;
+        if let Some(tag) = self.tag {
+            out.write_str(" @ ")?;
+            self.write_name(tag, out)?;
+        }
+        Ok(())
+    }
+
+    fn write_name(&self, name: &str, f: &mut dyn fmt::Write) -> fmt::Result {
+        let format = &self.format;
+        let mut tokens = Tokens::new(name);
+        let mut prev = None;
+        let mut depth = 0;
+        // One bit per open bracket, set for lists written on multiple lines.
+        let mut open = 0u64;
+        let mut nesting = 0;
+
+        while let Some(token) = tokens.next() {
+            match token {
+                Token::Ident("for") if (!format.lifetimes || format.anonymous_lifetimes) && tokens.peek(&[Token::Punct("<")]) => {
+                    tokens.next();
+                    tokens.skip_generic_args();
+                    tokens.skip_if(&[Token::Punct(" ")]);
+                    continue;
+                },
+                Token::Ident(_) => tokens.write_path(token, format, f)?,
+                Token::PathSep if format.closures && tokens.peek(&[Token::Ident("{{closure}}")]) => {
+                    tokens.next();
+                    while tokens.skip_if(&[Token::PathSep, Token::Ident("{{closure}}")]) {}
+                    tokens.skip_closure_index();
+                },
+                Token::PathSep => f.write_str(format.path_separator)?,
+                Token::Lifetime(_) if (!format.lifetimes || format.anonymous_lifetimes) && prev == Some(Token::Punct("&")) => {
+                    tokens.skip_if(&[Token::Punct(" ")]);
+                },
+                Token::Lifetime(_) if format.anonymous_lifetimes => f.write_str("'_")?,
+                Token::Punct("<") => {
+                    if !format.lifetimes {
+                        while tokens.skip_if(&[Token::Lifetime(""), Token::Punct(","), Token::Punct(" ")]) {}
+                        if tokens.skip_if(&[Token::Lifetime(""), Token::Punct(">")]) {
+                            continue;
+                        }
+                    }
+                    if format.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+                        tokens.skip_generic_args();
+                        f.write_str("<")?;
+                        f.write_str(format.ellipsis)?;
+                        f.write_str(">")?;
+                    } else {
+                        depth += 1;
+                        f.write_str("<")?;
+                        let multiline = format.multiline && tokens.generic_args_nest();
+                        push_bracket(&mut open, &mut nesting, multiline);
+                        if multiline {
+                            write_line_break(f, open)?;
+                        }
+                    }
+                },
+                Token::Punct(">") => {
+                    depth = depth.saturating_sub(1);
+                    if pop_bracket(&mut open, &mut nesting) {
+                        f.write_str(",")?;
+                        write_line_break(f, open)?;
+                    }
+                    f.write_str(">")?;
+                },
+                Token::Punct(text @ "(") | Token::Punct(text @ "[") => {
+                    push_bracket(&mut open, &mut nesting, false);
+                    f.write_str(text)?;
+                },
+                Token::Punct(text @ ")") | Token::Punct(text @ "]") => {
+                    pop_bracket(&mut open, &mut nesting);
+                    f.write_str(text)?;
+                },
+                Token::Punct(",") => {
+                    if !format.lifetimes && tokens.skip_if(&[Token::Punct(" "), Token::Lifetime("")]) {
+                        continue;
+                    }
+                    if open & 1 == 1 {
+                        tokens.skip_if(&[Token::Punct(" ")]);
+                        f.write_str(",")?;
+                        write_line_break(f, open)?;
+                    } else if tokens.skip_if(&[Token::Punct(" ")]) {
+                        f.write_str(format.arg_separator)?;
+                    } else {
+                        f.write_str(",")?;
+                    }
+                },
+                Token::Punct(" ") if !format.lifetimes
+                    && tokens.skip_if(&[Token::Punct("+"), Token::Punct(" "), Token::Lifetime("")]) => {
+                    continue;
+                },
+                Token::Lifetime(text) | Token::Punct(text) => f.write_str(text)?,
+            }
+            prev = Some(token);
+        }
+
+        Ok(())
+    }
+}
+
+/// Type name highlighted with ANSI escape codes.
+///
+/// Module paths are dim, the outer type is bold, generic brackets are cyan
+/// and lifetimes are italic and magenta. With `short`, module paths are
+/// removed instead.
+#[cfg(feature = "color")]
+pub struct Highlighted<'a> {
+    pub name: &'a str,
+    pub tag: Option<&'a str>,
+    pub short: bool,
+}
+
+#[cfg(feature = "color")]
+impl fmt::Display for Highlighted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        highlight(self.name, self.short, f)?;
+        if let Some(tag) = self.tag {
+            f.write_str(" @ ")?;
+            highlight(tag, self.short, f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "color")]
+fn highlight(name: &str, short: bool, f: &mut fmt::Formatter) -> fmt::Result {
+    const DIM: &str = "\x1b[2m";
+    const BOLD: &str = "\x1b[1m";
+    const BRACKET: &str = "\x1b[36m";
+    const LIFETIME: &str = "\x1b[3;35m";
+    const RESET: &str = "\x1b[0m";
+
+    let mut tokens = Tokens::new(name);
+    let mut depth = 0usize;
+    let mut outer_written = false;
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Ident(ident) if is_keyword(ident) => f.write_str(ident)?,
+            Token::Ident(first) => {
+                let path = tokens.take_path(first);
+                let (prefix, last) = match path.rfind("::") {
+                    Some(pos) => (&path[..pos + 2], &path[pos + 2..]),
+                    None => ("", path),
+                };
+                if !short && !prefix.is_empty() {
+                    f.write_str(DIM)?;
+                    for segment in prefix.split_terminator("::") {
+                        write!(f, "{}::", strip_disambiguator(segment))?;
+                    }
+                    f.write_str(RESET)?;
+                }
+                if depth == 0 && !outer_written {
+                    write!(f, "{}{}{}", BOLD, strip_disambiguator(last), RESET)?;
+                    outer_written = true;
+                } else {
+                    f.write_str(strip_disambiguator(last))?;
+                }
+            },
+            Token::Lifetime(lifetime) => write!(f, "{}{}{}", LIFETIME, lifetime, RESET)?,
+            Token::PathSep if short => {},
+            Token::PathSep => write!(f, "{}::{}", DIM, RESET)?,
+            Token::Punct("<") => {
+                depth += 1;
+                write!(f, "{}<{}", BRACKET, RESET)?;
+            },
+            Token::Punct(">") => {
+                depth = depth.saturating_sub(1);
+                write!(f, "{}>{}", BRACKET, RESET)?;
+            },
+            Token::Punct(punct) => f.write_str(punct)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "color")]
+fn is_keyword(ident: &str) -> bool {
+    matches!(ident, "dyn" | "mut" | "const" | "fn" | "impl" | "unsafe" | "extern" | "for" | "as")
+}
+
+fn push_bracket(open: &mut u64, nesting: &mut usize, multiline: bool) {
+    if *nesting < 64 {
+        *open = *open << 1 | u64::from(multiline);
+    }
+    *nesting += 1;
+}
+
+/// Close the innermost bracket and return whether its list was written on
+/// multiple lines.
+fn pop_bracket(open: &mut u64, nesting: &mut usize) -> bool {
+    *nesting = nesting.saturating_sub(1);
+    if *nesting >= 64 {
+        return false;
+    }
+    let multiline = *open & 1 == 1;
+    *open >>= 1;
+    multiline
+}
+
+/// Start a new line indented by the number of open multi-line lists.
+fn write_line_break(f: &mut dyn fmt::Write, open: u64) -> fmt::Result {
+    f.write_str("\n")?;
+    for _ in 0..open.count_ones() {
+        f.write_str("    ")?;
+    }
+    Ok(())
+}
+
+/// Token of a type name.
+///
+/// Lifetimes in patterns passed to `Tokens::peek` and `Tokens::skip_if` match
+/// any lifetime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// Identifier, keyword, number or path segment.
+    Ident(&'a str),
+    /// Lifetime, including the leading `'`.
+    Lifetime(&'a str),
+    /// Path separator, `::`.
+    PathSep,
+    /// Any other character, `->` or a string literal such as `"C"`.
+    Punct(&'a str),
+}
+
+impl Token<'_> {
+    fn matches(&self, pattern: &Token) -> bool {
+        match (self, pattern) {
+            (Token::Lifetime(_), Token::Lifetime(_)) => true,
+            _ => self == pattern,
+        }
+    }
+}
+
+/// Iterator over tokens of a type name.
+#[derive(Clone)]
+pub struct Tokens<'a> {
+    name: &'a str,
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(name: &'a str) -> Tokens<'a> {
+        Tokens {
+            name,
+            pos: 0,
+        }
+    }
+
+    /// Check if the next tokens match `pattern`.
+    pub fn peek(&self, pattern: &[Token]) -> bool {
+        let mut tokens = self.clone();
+        pattern.iter().all(|expected| tokens.next().is_some_and(|token| token.matches(expected)))
+    }
+
+    /// Consume the next tokens if they match `pattern`.
+    pub fn skip_if(&mut self, pattern: &[Token]) -> bool {
+        if !self.peek(pattern) {
+            return false;
+        }
+        for _ in pattern {
+            self.next();
+        }
+        true
+    }
+
+    /// Consume tokens up to and including the `>` that closes already
+    /// consumed `<`.
+    fn skip_generic_args(&mut self) {
+        let mut depth = 1;
+        for token in self.by_ref() {
+            match token {
+                Token::Punct("<") => depth += 1,
+                Token::Punct(">") if depth == 1 => return,
+                Token::Punct(">") => depth -= 1,
+                _ => {},
+            }
+        }
+    }
+
+    /// Check if generic arguments after already consumed `<` contain other
+    /// generic arguments.
+    fn generic_args_nest(&self) -> bool {
+        for token in self.clone() {
+            match token {
+                Token::Punct("<") => return true,
+                Token::Punct(">") => return false,
+                _ => {},
+            }
+        }
+        false
+    }
+
+    /// Consume the rest of the path that starts with `first` and write it out.
+    fn write_path(&mut self, first: Token<'a>, format: &NameFormat, f: &mut dyn fmt::Write) -> fmt::Result {
+        let krate = match first {
+            Token::Ident(ident) => ident,
+            _ => return Ok(()),
+        };
+        let start = self.pos - krate.len();
+        let mut path = self.take_path(krate);
+
+        if format.closures {
+            // The path continues after the generic arguments of a function,
+            // where `closure@` was already written.
+            let continued = self.name[..start].ends_with("::");
+            if path.ends_with("::{{closure}}") {
+                while let Some(parent) = path.strip_suffix("::{{closure}}") {
+                    path = parent;
+                }
+                self.skip_closure_index();
+                if !continued {
+                    f.write_str("closure@")?;
+                }
+            } else if self.closure_follows() {
+                f.write_str("closure@")?;
+            }
+        }
+        let last = path.rsplit("::").next().unwrap_or(path);
+
+        let strip = match format.paths {
+            PathStyle::Qualified => false,
+            PathStyle::Short => true,
+            PathStyle::StripStd => matches!(strip_disambiguator(krate), "std" | "core" | "alloc"),
+        };
+        if strip {
+            return f.write_str(strip_disambiguator(last));
+        }
+        if format.std_public_paths && matches!(strip_disambiguator(krate), "std" | "core" | "alloc") {
+            f.write_str("std")?;
+            if path.len() > krate.len() + last.len() + 2 {
+                let modules = &path[krate.len() + 2..path.len() - last.len() - 2];
+                let public = std_public_modules(modules, strip_disambiguator(last));
+                for module in public.split("::").filter(|module| !module.is_empty()) {
+                    f.write_str(format.path_separator)?;
+                    f.write_str(strip_disambiguator(module))?;
+                }
+            }
+            if path.len() > krate.len() {
+                f.write_str(format.path_separator)?;
+                f.write_str(strip_disambiguator(last))?;
+            }
+            return Ok(());
+        }
+        for (i, segment) in path.split("::").enumerate() {
+            if i > 0 {
+                f.write_str(format.path_separator)?;
+            }
+            if format.disambiguators {
+                f.write_str(segment)?;
+            } else {
+                f.write_str(strip_disambiguator(segment))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check if the path continues after generic arguments and ends with a
+    /// closure, as in `my_crate::f<u8>::{{closure}}`.
+    fn closure_follows(&self) -> bool {
+        let mut tokens = self.clone();
+        loop {
+            match tokens.next() {
+                Some(Token::Punct("<")) => tokens.skip_generic_args(),
+                Some(Token::PathSep) => match tokens.next() {
+                    Some(Token::Ident("{{closure}}")) => return true,
+                    Some(Token::Ident(_)) => {},
+                    _ => return false,
+                },
+                _ => return false,
+            }
+        }
+    }
+
+    /// Consume the index that follows a closure on some toolchains, as in
+    /// `my_crate::main::{{closure}}#0`.
+    fn skip_closure_index(&mut self) {
+        if self.peek(&[Token::Punct("#")]) {
+            self.next();
+            self.next();
+        }
+    }
+
+    /// Get the position of the next token in the name.
+    #[cfg(feature = "alloc")]
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Consume the rest of the path that starts with the already consumed
+    /// identifier `first` and return the whole path.
+    pub fn take_path(&mut self, first: &'a str) -> &'a str {
+        let start = self.pos - first.len();
+        while self.peek_path_segment() {
+            self.next();
+            self.next();
+        }
+        &self.name[start..self.pos]
+    }
+
+    fn peek_path_segment(&self) -> bool {
+        let mut tokens = self.clone();
+        tokens.next() == Some(Token::PathSep) && matches!(tokens.next(), Some(Token::Ident(_)))
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let rest = &self.name[self.pos..];
+        let c = rest.chars().next()?;
+
+        let len = if rest.starts_with("::") || rest.starts_with("->") {
+            2
+        } else if c == '\'' {
+            1 + rest[1..].find(|c| !is_path_char(c)).unwrap_or(rest.len() - 1)
+        } else if c == '"' {
+            rest[1..].find('"').map_or(rest.len(), |end| end + 2)
+        } else if is_path_char(c) {
+            let len = rest.find(|c| !is_path_char(c)).unwrap_or(rest.len());
+            len + disambiguator_len(&rest[len..])
+        } else {
+            c.len_utf8()
+        };
+        let text = &rest[..len];
+        self.pos += len;
+
+        Some(if text == "::" {
+            Token::PathSep
+        } else if c == '\'' {
+            Token::Lifetime(text)
+        } else if is_path_char(c) {
+            Token::Ident(text)
+        } else {
+            Token::Punct(text)
+        })
+    }
+}
+
+/// Public modules of the standard library that are nested in other modules.
+const STD_NESTED_MODULES: &[&str] = &["sync::atomic", "sync::mpsc", "sync::mpmc"];
+
+/// Private modules of the standard library collections, with the public
+/// module that re-exports their items, and the collection type that is also
+/// re-exported from `std::collections`.
+const STD_COLLECTION_MODULES: &[(&str, &str, &str)] = &[
+    ("collections::hash::map", "collections::hash_map", "HashMap"),
+    ("collections::hash::set", "collections::hash_set", "HashSet"),
+    ("collections::btree::map", "collections::btree_map", "BTreeMap"),
+    ("collections::btree::set", "collections::btree_set", "BTreeSet"),
+    ("collections::vec_deque", "collections::vec_deque", "VecDeque"),
+    ("collections::binary_heap", "collections::binary_heap", "BinaryHeap"),
+    ("collections::linked_list", "collections::linked_list", "LinkedList"),
+];
+
+/// Get the modules of the public path of an `item` in the standard library,
+/// given the `modules` of its path without the crate name and the item name.
+///
+/// Types are usually defined in private modules and re-exported from the
+/// first module, as `std::sync::poison::mutex::Mutex` is `std::sync::Mutex`.
+/// Items of the collections are re-exported from their own modules instead,
+/// as `std::collections::hash::map::Entry` is `std::collections::hash_map::Entry`,
+/// so that the entries of different maps keep different paths.
+pub fn std_public_modules<'a>(modules: &'a str, item: &str) -> &'a str {
+    let within = |module: &str| {
+        modules.strip_prefix(module).is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+    };
+    if let Some(&(_, public, collection)) = STD_COLLECTION_MODULES.iter().find(|&&(module, public, _)| within(module) || within(public)) {
+        return if item == collection { "collections" } else { public };
+    }
+    if let Some(nested) = STD_NESTED_MODULES.iter().find(|nested| within(nested)) {
+        return nested;
+    }
+    &modules[..modules.find("::").unwrap_or(modules.len())]
+}
+
+/// Rewrite a type name to a canonical form, so that names of the same type
+/// printed by different compiler versions compare equal.
+///
+/// The rules are:
+///
+/// - Lifetimes are removed, as with `NameFormat::lifetimes(false)`.
+/// - Crate disambiguators are removed.
+/// - Paths into `std`, `core` and `alloc` are rewritten to the public path in
+///   `std`, usually keeping the first module, as `std::sync::Mutex`, and the
+///   module of the collection for items of collections, as
+///   `std::collections::hash_map::Entry`.
+/// - Whitespace is normalized: there is one space after `,` and `;`, around
+///   `->`, `+` and `=`, and where the name had whitespace between words, as in
+///   `dyn Send` or `&mut [u8]`, and no whitespace elsewhere. A `,` before a
+///   closing bracket, as in `(u8,)`, is not followed by a space.
+///
+/// Canonical names are not changed by `canonicalize`. The rules may be
+/// extended when compilers change how names are printed.
+///
+/// This is not available if this crate is compiled without the `alloc` feature.
+///
+/// ```
+/// use typedef::canonicalize;
+///
+/// assert_eq!(canonicalize("std::sync::poison::mutex::Mutex<&'static str>"), "std::sync::Mutex<&str>");
+/// assert_eq!(canonicalize("std::sync::mutex::Mutex<&str>"), "std::sync::Mutex<&str>");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn canonicalize(name: &str) -> String {
+    let mut canonical = String::with_capacity(name.len());
+    let mut out = CanonicalSpaces {
+        out: &mut canonical,
+        prev: None,
+        space: false,
+        force_space: false,
+        dash: false,
+    };
+    let _ = write!(out, "{}", render(name, NameFormat::canonical()));
+    let _ = out.finish();
+    canonical
+}
+
+/// Writer that normalizes whitespace for `canonicalize`.
+#[cfg(feature = "alloc")]
+struct CanonicalSpaces<'a, W: 'a + ?Sized> {
+    out: &'a mut W,
+    prev: Option<char>,
+    /// Whitespace was skipped after `prev`.
+    space: bool,
+    /// A space is written before the next character.
+    force_space: bool,
+    /// A `-` that may start `->` is not written yet.
+    dash: bool,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, W: 'a + ?Sized + fmt::Write> CanonicalSpaces<'a, W> {
+    fn push(&mut self, c: char) -> fmt::Result {
+        let separate = match self.prev {
+            None => false,
+            Some(prev) => (self.force_space && !matches!(c, ')' | ']' | '>'))
+                || matches!(c, '+' | '=')
+                || (self.space && !matches!(prev, '<' | '(' | '[' | '&' | '*') && !matches!(c, ',' | ';' | '>' | ')' | ']')),
+        };
+        if separate {
+            self.out.write_char(' ')?;
+        }
+        self.out.write_char(c)?;
+        self.prev = Some(c);
+        self.space = false;
+        self.force_space = matches!(c, ',' | ';' | '+' | '=');
+        Ok(())
+    }
+
+    fn finish(&mut self) -> fmt::Result {
+        if self.dash {
+            self.dash = false;
+            self.push('-')?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, W: 'a + ?Sized + fmt::Write> fmt::Write for CanonicalSpaces<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if c.is_whitespace() {
+                self.space = true;
+                continue;
+            }
+            if self.dash {
+                self.dash = false;
+                if c == '>' {
+                    if self.prev.is_some() {
+                        self.out.write_char(' ')?;
+                    }
+                    self.out.write_str("->")?;
+                    self.prev = Some('>');
+                    self.space = false;
+                    self.force_space = true;
+                    continue;
+                }
+                self.push('-')?;
+            }
+            if c == '-' {
+                self.dash = true;
+            } else {
+                self.push(c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Convert a type name into an identifier that matches `[A-Za-z_][A-Za-z0-9_]*`.
+///
+/// Module paths are removed, and every run of other characters is replaced
+/// with a single `_`, so `HashMap<String, i32>` becomes `HashMap_String_i32`.
+/// With `snake_case`, words of camel case names are separated with `_` and
+/// converted to lowercase, so it becomes `hash_map_string_i32`.
+#[cfg(feature = "alloc")]
+pub fn to_ident(name: &str, snake_case: bool) -> String {
+    let short = strip_paths(name).to_string();
+    let mut ident = String::with_capacity(short.len());
+    let mut separate = false;
+    let mut prev: Option<char> = None;
+    let mut chars = short.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if !c.is_ascii_alphanumeric() && c != '_' {
+            separate = true;
+            prev = None;
+            continue;
+        }
+        if snake_case && c.is_ascii_uppercase() {
+            let next_lowercase = chars.peek().is_some_and(|next| next.is_ascii_lowercase());
+            separate |= match prev {
+                Some(prev) => prev.is_ascii_lowercase() || prev.is_ascii_digit() || (prev.is_ascii_uppercase() && next_lowercase),
+                None => false,
+            };
+        }
+        if separate && !ident.is_empty() && !ident.ends_with('_') {
+            ident.push('_');
+        }
+        separate = false;
+        prev = Some(c);
+        ident.push(if snake_case { c.to_ascii_lowercase() } else { c });
+    }
+
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+/// Encode a type name so it can be used as a file name on any platform.
+///
+/// ASCII letters, digits, `_` and `-` are kept, and every other byte is
+/// percent-encoded, so `Vec<u8>` becomes `Vec%3Cu8%3E`. Percent-decoding
+/// the result gives back the original name.
+#[cfg(feature = "alloc")]
+pub fn to_filename(name: &str) -> String {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut filename = String::with_capacity(name.len());
+    for &byte in name.as_bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-' {
+            filename.push(byte as char);
+        } else {
+            filename.push('%');
+            filename.push(HEX[(byte >> 4) as usize] as char);
+            filename.push(HEX[(byte & 0xf) as usize] as char);
+        }
+    }
+    filename
+}
+
+/// Check if a type name is the name of a closure type, such as
+/// `my_crate::main::{{closure}}`.
+#[cfg(any(not(feature = "id-only"), feature = "alloc", test))]
+pub fn is_closure(name: &str) -> bool {
+    generated_origin(name).is_some() && name.rsplit("::").next().is_some_and(|last| last.starts_with("{{closure}}"))
+}
+
+/// Get the path of the item that defines a compiler-generated type, or `None`
+/// if the type is not compiler-generated.
+///
+/// Compiler-generated types are named after the item that defines them,
+/// followed by segments in double braces, so the origin of
+/// `my_crate::fetch<u8>::{{closure}}` is `my_crate::fetch<u8>`.
+#[cfg(any(not(feature = "id-only"), feature = "alloc", test))]
+pub fn generated_origin(name: &str) -> Option<&str> {
+    let mut tokens = Tokens::new(name);
+    if !matches!(tokens.next(), Some(Token::Ident(_))) {
+        return None;
+    }
+
+    let mut origin_end = None;
+    loop {
+        let pos = tokens.pos;
+        match tokens.next() {
+            None => return origin_end.map(|end| &name[..end]),
+            Some(Token::PathSep) => match tokens.next() {
+                Some(Token::Ident(ident)) if ident.starts_with("{{") && ident.ends_with("}}") => {
+                    origin_end.get_or_insert(pos);
+                },
+                Some(Token::Ident(_)) => origin_end = None,
+                _ => return None,
+            },
+            Some(Token::Punct("<")) if origin_end.is_none() => tokens.skip_generic_args(),
+            Some(Token::Punct("#")) if origin_end.is_some() => {
+                tokens.next();
+                return match tokens.next() {
+                    None => origin_end.map(|end| &name[..end]),
+                    Some(_) => None,
+                };
+            },
+            Some(_) => return None,
+        }
+    }
+}
+
+/// Check if a type name contains crate disambiguators, such as `[4f2a]` in
+/// `my_crate[4f2a]::Foo`.
+pub fn has_disambiguator(name: &str) -> bool {
+    name.contains("]::") && Tokens::new(name).any(|token| match token {
+        Token::Ident(ident) => ident.contains('['),
+        _ => false,
+    })
+}
+
+/// Length of a crate disambiguator at the start of `rest`, or `0` if there
+/// is none.
+///
+/// A disambiguator is a hexadecimal hash in brackets that directly follows a
+/// path segment and is followed by `::`.
+fn disambiguator_len(rest: &str) -> usize {
+    let end = match rest.strip_prefix('[').and_then(|inner| inner.find(']')) {
+        Some(end) => end + 2,
+        None => return 0,
+    };
+    let hash = &rest[1..end - 1];
+    if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) || !rest[end..].starts_with("::") {
+        return 0;
+    }
+    end
+}
+
+pub fn strip_disambiguator(segment: &str) -> &str {
+    match segment.find('[') {
+        Some(pos) => &segment[..pos],
+        None => segment,
+    }
+}
+
+/// Write the output of `write` to the formatter, applying width, fill,
+/// alignment and precision to it as a whole, the same way `Formatter::pad`
+/// does for a single string.
+pub fn pad<F>(f: &mut fmt::Formatter, write: F) -> fmt::Result
+    where F: Fn(&mut dyn fmt::Write) -> fmt::Result
+{
+    if f.width().is_none() && f.precision().is_none() {
+        return write(f);
+    }
+
+    let mut count = CountChars(0);
+    write(&mut count)?;
+    let len = match f.precision() {
+        Some(precision) => cmp::min(precision, count.0),
+        None => count.0,
+    };
+    let padding = f.width().map_or(0, |width| width.saturating_sub(len));
+    let (before, after) = match f.align() {
+        Some(fmt::Alignment::Right) => (padding, 0),
+        Some(fmt::Alignment::Center) => (padding / 2, padding.div_ceil(2)),
+        _ => (0, padding),
+    };
+
+    let fill = f.fill();
+    for _ in 0..before {
+        f.write_char(fill)?;
+    }
+    write(&mut Truncate { out: f, remaining: len })?;
+    for _ in 0..after {
+        f.write_char(fill)?;
+    }
+    Ok(())
+}
+
+/// Writer that escapes characters which have special meaning in HTML.
+pub struct HtmlEscape<'a, W: 'a + ?Sized>(pub &'a mut W);
+
+impl<'a, W: 'a + ?Sized + fmt::Write> fmt::Write for HtmlEscape<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut rest = s;
+        while let Some(pos) = rest.find(['&', '<', '>', '"', '\'']) {
+            self.0.write_str(&rest[..pos])?;
+            self.0.write_str(match rest.as_bytes()[pos] {
+                b'&' => "&amp;",
+                b'<' => "&lt;",
+                b'>' => "&gt;",
+                b'"' => "&quot;",
+                _ => "&#39;",
+            })?;
+            rest = &rest[pos + 1..];
+        }
+        self.0.write_str(rest)
+    }
+}
+
+/// Writer that only counts the written characters.
+struct CountChars(usize);
+
+impl fmt::Write for CountChars {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.chars().count();
+        Ok(())
+    }
+}
+
+/// Writer that passes through at most `remaining` characters.
+struct Truncate<'a, W: 'a + ?Sized> {
+    out: &'a mut W,
+    remaining: usize,
+}
+
+impl<'a, W: 'a + ?Sized + fmt::Write> fmt::Write for Truncate<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match s.char_indices().nth(self.remaining) {
+            Some((end, _)) => {
+                self.remaining = 0;
+                self.out.write_str(&s[..end])
+            },
+            None => {
+                self.remaining -= s.chars().count();
+                self.out.write_str(s)
+            },
+        }
+    }
+}
+
+/// Writer that replaces the characters from `head` up to `tail_start` with
+/// `ellipsis`.
+struct ElideMiddle<'a, W: 'a + ?Sized> {
+    out: &'a mut W,
+    ellipsis: &'a str,
+    head: usize,
+    tail_start: usize,
+    pos: usize,
+}
+
+impl<'a, W: 'a + ?Sized + fmt::Write> fmt::Write for ElideMiddle<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if self.pos == self.head {
+                self.out.write_str(self.ellipsis)?;
+            }
+            if self.pos < self.head || self.pos >= self.tail_start {
+                self.out.write_char(c)?;
+            }
+            self.pos += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Decimal representation of a `u64`, formatted without allocation.
+pub struct Decimal {
+    buf: [u8; 20],
+    start: usize,
+}
+
+impl Decimal {
+    pub fn new(mut value: u64) -> Decimal {
+        let mut buf = [0; 20];
+        let mut start = buf.len();
+        loop {
+            start -= 1;
+            buf[start] = b'0' + (value % 10) as u8;
+            value /= 10;
+            if value == 0 {
+                break;
+            }
+        }
+        Decimal { buf, start }
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Only ASCII digits are written to the buffer.
+        core::str::from_utf8(&self.buf[self.start..]).unwrap_or("")
+    }
+}
+
+/// Parts of the outermost path in a type name.
+pub struct PathParts<'a> {
+    pub krate: Option<&'a str>,
+    pub module: Option<&'a str>,
+    pub ident: &'a str,
+}
+
+/// Split the outermost path of a type name into crate, module and identifier.
+///
+/// Returns `None` if the type name does not start with a path, as is the case
+/// for references, tuples, slices and other built-in type syntax.
+pub fn split_path(name: &str) -> Option<PathParts<'_>> {
+    let end = name.find('<').unwrap_or(name.len());
+    let path = &name[..end];
+    if path.is_empty() || !path.chars().all(|c| is_path_char(c) || c == ':') {
+        return None;
+    }
+
+    let (prefix, ident) = match path.rfind("::") {
+        Some(pos) => (Some(&path[..pos]), &path[pos + 2..]),
+        None => (None, path),
+    };
+    let (krate, module) = match prefix {
+        Some(prefix) => match prefix.find("::") {
+            Some(pos) => (Some(&prefix[..pos]), Some(&prefix[pos + 2..])),
+            None => (Some(prefix), None),
+        },
+        None => (None, None),
+    };
+
+    Some(PathParts {
+        krate,
+        module,
+        ident,
+    })
+}
+
+fn is_path_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '{' || c == '}'
+}
+
+#[cfg(test)]
+mod test {
+    use core::fmt;
+    use super::{strip_paths, strip_std_paths, render, render_tagged, split_path, pad, has_disambiguator, is_closure, generated_origin, Decimal, NameFormat, PathStyle};
+
+    #[test]
+    fn should_strip_paths() {
+        assert_eq!(strip_paths("i64").to_string(), "i64");
+        assert_eq!(strip_paths("alloc::string::String").to_string(), "String");
+        assert_eq!(strip_paths("&alloc::string::String").to_string(), "&String");
+    }
+
+    #[test]
+    fn should_strip_paths_in_generic_arguments() {
+        assert_eq!(
+            strip_paths("std::collections::hash::map::HashMap<alloc::string::String, alloc::vec::Vec<core::option::Option<i32>>>").to_string(),
+            "HashMap<String, Vec<Option<i32>>>"
+        );
+        assert_eq!(strip_paths("(alloc::string::String, [core::option::Option<u8>; 4])").to_string(), "(String, [Option<u8>; 4])");
+        assert_eq!(strip_paths("alloc::boxed::Box<dyn core::error::Error + core::marker::Send>").to_string(), "Box<dyn Error + Send>");
+    }
+
+    #[test]
+    fn should_strip_std_paths_only() {
+        assert_eq!(strip_std_paths("i64").to_string(), "i64");
+        assert_eq!(strip_std_paths("alloc::vec::Vec<my_crate::foo::Foo>").to_string(), "Vec<my_crate::foo::Foo>");
+        assert_eq!(
+            strip_std_paths("my_crate::Wrapper<std::collections::hash::map::HashMap<alloc::string::String, other::Bar>>").to_string(),
+            "my_crate::Wrapper<HashMap<String, other::Bar>>"
+        );
+        assert_eq!(strip_std_paths("&dyn core::any::Any").to_string(), "&dyn Any");
+        assert_eq!(strip_std_paths("stdx::Foo<core_foo::Bar>").to_string(), "stdx::Foo<core_foo::Bar>");
+        assert_eq!(strip_std_paths("my_crate::std::Foo").to_string(), "my_crate::std::Foo");
+    }
+
+    #[test]
+    fn should_render_unchanged_by_default() {
+        for name in [
+            "alloc::boxed::Box<dyn core::ops::function::Fn(&'_ str) -> &'_ str>",
+            "(i32, &mut [u8], *const u8, *mut i8)",
+            "unsafe extern \"C\" fn(i32) -> i32",
+            "<alloc::vec::Vec<u8> as core::iter::IntoIterator>::IntoIter",
+            "my_crate::f::G<3>",
+        ] {
+            assert_eq!(render(name, NameFormat::new()).to_string(), name);
+        }
+    }
+
+    #[test]
+    fn should_render_with_separators() {
+        let format = NameFormat::new()
+            .path_separator(".")
+            .arg_separator(",");
+
+        assert_eq!(
+            render("std::collections::hash::map::HashMap<alloc::string::String, (u8, i8)>", format).to_string(),
+            "std.collections.hash.map.HashMap<alloc.string.String,(u8,i8)>"
+        );
+        assert_eq!(render("[u8; 16]", format).to_string(), "[u8; 16]");
+    }
+
+    #[test]
+    fn should_rewrite_every_path_separator() {
+        let format = NameFormat::new().path_separator("/");
+
+        assert_eq!(
+            render("<alloc::vec::Vec<my_crate::Foo> as core::iter::IntoIterator>::IntoIter", format).to_string(),
+            "<alloc/vec/Vec<my_crate/Foo> as core/iter/IntoIterator>/IntoIter"
+        );
+        assert_eq!(
+            render("alloc::boxed::Box<dyn core::ops::function::Fn(my_crate::A) -> (my_crate::B, [my_crate::C; 2])>", format).to_string(),
+            "alloc/boxed/Box<dyn core/ops/function/Fn(my_crate/A) -> (my_crate/B, [my_crate/C; 2])>"
+        );
+        assert_eq!(render("my_crate::main::{{closure}}", format).to_string(), "my_crate/main/{{closure}}");
+        assert_eq!(
+            render("alloc::vec::Vec<my_crate::foo::Foo>", format.paths(PathStyle::StripStd)).to_string(),
+            "Vec<my_crate/foo/Foo>"
+        );
+    }
+
+    #[test]
+    fn should_remove_disambiguators() {
+        let name = "alloc[9f3c]::vec::Vec<my_crate[4f2a]::foo::Foo, [u8; 4]>";
+
+        assert!(has_disambiguator(name));
+        assert!(!has_disambiguator("alloc::vec::Vec<[u8; 4]>"));
+        assert!(!has_disambiguator("my_crate::Foo<[my_crate::Bar; 2]>"));
+        assert_eq!(render(name, NameFormat::new()).to_string(), "alloc::vec::Vec<my_crate::foo::Foo, [u8; 4]>");
+        assert_eq!(render(name, NameFormat::new().disambiguators(true)).to_string(), name);
+        assert_eq!(strip_paths(name).to_string(), "Vec<Foo, [u8; 4]>");
+        assert_eq!(strip_std_paths(name).to_string(), "Vec<my_crate::foo::Foo, [u8; 4]>");
+        assert_eq!(render("my_crate[xyz]::Foo", NameFormat::new()).to_string(), "my_crate[xyz]::Foo");
+    }
+
+    #[test]
+    fn should_render_closures() {
+        let format = NameFormat::new().closures(true);
+
+        assert_eq!(render("my_crate::main::{{closure}}", format).to_string(), "closure@my_crate::main");
+        assert_eq!(render("my_crate::main::{{closure}}#0", format).to_string(), "closure@my_crate::main");
+        assert_eq!(render("my_crate::main::{{closure}}::{{closure}}", format).to_string(), "closure@my_crate::main");
+        assert_eq!(render("my_crate::main::{{closure}}", format.paths(PathStyle::Short)).to_string(), "closure@main");
+        assert_eq!(
+            render("core::iter::adapters::map::Map<alloc::vec::into_iter::IntoIter<u8>, my_crate::f::{{closure}}>", format.paths(PathStyle::Short)).to_string(),
+            "Map<IntoIter<u8>, closure@f>"
+        );
+        assert_eq!(render("my_crate::main::{{closure}}", NameFormat::new()).to_string(), "my_crate::main::{{closure}}");
+        assert_eq!(render("my_crate::f<u8, i8>::{{closure}}", format).to_string(), "closure@my_crate::f<u8, i8>");
+        assert_eq!(render("my_crate::S<u8>::m::{{closure}}#2", format).to_string(), "closure@my_crate::S<u8>::m");
+        assert_eq!(
+            render("my_crate::Wrap<my_crate::f<u8>::{{closure}}, u8>", format.paths(PathStyle::Short)).to_string(),
+            "Wrap<closure@f<u8>, u8>"
+        );
+    }
+
+    #[test]
+    fn should_detect_closures() {
+        assert!(is_closure("my_crate::main::{{closure}}"));
+        assert!(is_closure("my_crate::main::{{closure}}#1"));
+        assert!(!is_closure("my_crate::main"));
+        assert!(!is_closure("&my_crate::main::{{closure}}"));
+        assert!(!is_closure("alloc::vec::Vec<my_crate::main::{{closure}}>"));
+        assert!(is_closure("my_crate::f<u8>::{{closure}}"));
+        assert!(!is_closure("my_crate::f::{{constant}}"));
+    }
+
+    #[test]
+    fn should_find_origin_of_generated_types() {
+        assert_eq!(generated_origin("my_crate::fetch::{{closure}}"), Some("my_crate::fetch"));
+        assert_eq!(generated_origin("my_crate::fetch<u8>::{{closure}}::{{closure}}#1"), Some("my_crate::fetch<u8>"));
+        assert_eq!(generated_origin("my_crate::main::{{opaque}}"), Some("my_crate::main"));
+        assert_eq!(generated_origin("my_crate::main::{{closure}}::Local"), None);
+        assert_eq!(generated_origin("my_crate::Foo"), None);
+        assert_eq!(generated_origin("{{closure}}"), None);
+        assert_eq!(generated_origin("&my_crate::main::{{closure}}"), None);
+        assert_eq!(generated_origin("fn() -> my_crate::main::{{closure}}"), None);
+        assert_eq!(generated_origin("alloc::vec::Vec<my_crate::main::{{closure}}>"), None);
+    }
+
+    #[test]
+    fn should_limit_generic_depth() {
+        let name = "core::result::Result<alloc::vec::Vec<std::collections::hash::map::HashMap<u8, alloc::sync::Arc<u8>>>, my_crate::Error>";
+        let format = NameFormat::new().paths(PathStyle::Short);
+
+        assert_eq!(render(name, format.max_depth(0)).to_string(), "Result<…>");
+        assert_eq!(render(name, format.max_depth(1)).to_string(), "Result<Vec<…>, Error>");
+        assert_eq!(render(name, format.max_depth(2)).to_string(), "Result<Vec<HashMap<…>>, Error>");
+        assert_eq!(render(name, format.max_depth(5)).to_string(), "Result<Vec<HashMap<u8, Arc<u8>>>, Error>");
+        assert_eq!(render(name, format.max_depth(1).ellipsis("...")).to_string(), "Result<Vec<...>, Error>");
+        assert_eq!(
+            render("alloc::boxed::Box<dyn core::ops::function::Fn(u8) -> alloc::vec::Vec<u8>>", format.max_depth(1)).to_string(),
+            "Box<dyn Fn(u8) -> Vec<…>>"
+        );
+    }
+
+    #[test]
+    fn should_cut_long_names_in_the_middle() {
+        let name = "alloc::collections::btree::map::BTreeMap<u8, u8>";
+
+        assert_eq!(render(name, NameFormat::new().max_len(48)).to_string(), name);
+        assert_eq!(render(name, NameFormat::new().max_len(30)).to_string(), "alloc::collecti…reeMap<u8, u8>");
+        assert_eq!(render(name, NameFormat::new().max_len(30)).to_string().chars().count(), 30);
+        assert_eq!(render(name, NameFormat::new().max_len(10).ellipsis("...")).to_string(), "allo...u8>");
+        assert_eq!(render(name, NameFormat::new().max_len(1)).to_string(), "…");
+        assert_eq!(render(name, NameFormat::new().max_len(0)).to_string(), "");
+        assert_eq!(render(name, NameFormat::new().max_len(2).ellipsis("...")).to_string(), "..");
+        assert_eq!(render_tagged("my_crate::Id", Some("my_crate::UserTag"), NameFormat::new().max_len(15)).to_string(), "my_crat…UserTag");
+    }
+
+    #[test]
+    fn should_remove_lifetimes() {
+        let format = NameFormat::new().paths(PathStyle::Short).lifetimes(false);
+
+        assert_eq!(render("my_crate::Foo<'_>", format).to_string(), "Foo");
+        assert_eq!(render("my_crate::Foo<'_, 'static, u8>", format).to_string(), "Foo<u8>");
+        assert_eq!(render("my_crate::Foo<u8, 'a, i8, 'b>", format).to_string(), "Foo<u8, i8>");
+        assert_eq!(render("&'static str", format).to_string(), "&str");
+        assert_eq!(render("&'a mut [u8]", format).to_string(), "&mut [u8]");
+        assert_eq!(
+            render("alloc::boxed::Box<dyn core::ops::function::Fn(&'_ str) -> &'_ str>", format).to_string(),
+            "Box<dyn Fn(&str) -> &str>"
+        );
+        assert_eq!(render("alloc::boxed::Box<dyn my_crate::Tr<'_> + '_>", format).to_string(), "Box<dyn Tr>");
+        assert_eq!(render("for<'a> fn(&'a u8) -> &'a u8", format).to_string(), "fn(&u8) -> &u8");
+        assert_eq!(render("my_crate::Foo<'_>", format.max_depth(0)).to_string(), "Foo");
+        assert_eq!(render("my_crate::Foo<'a, &'a str>", format.anonymous_lifetimes(true)).to_string(), "Foo<&str>");
+    }
+
+    #[test]
+    fn should_canonicalize_lifetimes() {
+        let format = NameFormat::new().paths(PathStyle::Short).anonymous_lifetimes(true);
+
+        assert_eq!(render("my_crate::Foo<'a, &'static str>", format).to_string(), "Foo<'_, &str>");
+        assert_eq!(render("my_crate::Foo<'_, &str>", format).to_string(), "Foo<'_, &str>");
+        assert_eq!(render("&'a mut [u8]", format).to_string(), "&mut [u8]");
+        assert_eq!(render("alloc::boxed::Box<dyn my_crate::Tr<'a> + 'static>", format).to_string(), "Box<dyn Tr<'_> + '_>");
+        assert_eq!(render("for<'a> fn(&'a u8) -> &'a u8", format).to_string(), "fn(&u8) -> &u8");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn should_convert_to_ident() {
+        use super::to_ident;
+
+        let name = "std::collections::hash::map::HashMap<alloc::string::String, i32>";
+
+        assert_eq!(to_ident(name, false), "HashMap_String_i32");
+        assert_eq!(to_ident(name, true), "hash_map_string_i32");
+        assert_eq!(to_ident("&mut [u8]", false), "mut_u8");
+        assert_eq!(to_ident("(u8, my_crate::my_type)", true), "u8_my_type");
+        assert_eq!(to_ident("my_crate::HTTPServer<my_crate::IOError2D>", true), "http_server_io_error2_d");
+        assert_eq!(to_ident("my_crate::main::{{closure}}", false), "closure");
+        assert_eq!(to_ident("[u8; 16]", false), "u8_16");
+        assert_eq!(to_ident("()", false), "_");
+        assert_eq!(to_ident("1234", false), "_1234");
+        assert_eq!(to_ident("my_crate::Größe", true), "gr_e");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn should_encode_filename_reversibly() {
+        use super::to_filename;
+
+        fn decode(filename: &str) -> String {
+            let bytes = filename.as_bytes();
+            let mut decoded = Vec::new();
+            let mut i = 0;
+            while i < bytes.len() {
+                if bytes[i] == b'%' {
+                    decoded.push(u8::from_str_radix(&filename[i + 1..i + 3], 16).unwrap());
+                    i += 3;
+                } else {
+                    decoded.push(bytes[i]);
+                    i += 1;
+                }
+            }
+            String::from_utf8(decoded).unwrap()
+        }
+
+        assert_eq!(to_filename("alloc::vec::Vec<u8>"), "alloc%3A%3Avec%3A%3AVec%3Cu8%3E");
+        assert_eq!(to_filename("my-crate_2::Größe"), "my-crate_2%3A%3AGr%C3%B6%C3%9Fe");
+        assert_eq!(to_filename("../etc/passwd"), "%2E%2E%2Fetc%2Fpasswd");
+
+        for name in &["alloc::vec::Vec<u8>", "&'static [u8; 4]", "(u8, %20)", "my_crate::Größe", ""] {
+            let filename = to_filename(name);
+            assert!(filename.bytes().all(|b| b.is_ascii_alphanumeric() || b"_-%".contains(&b)));
+            assert_eq!(decode(&filename), *name);
+        }
+    }
+
+    #[test]
+    fn should_escape_html() {
+        use super::HtmlEscape;
+        use std::fmt::Write;
+
+        let mut html = String::new();
+        HtmlEscape(&mut html).write_str("fn(&'a str) -> Result<u8, \"e\">").unwrap();
+
+        assert_eq!(html, "fn(&amp;&#39;a str) -&gt; Result&lt;u8, &quot;e&quot;&gt;");
+    }
+
+    #[cfg(feature = "color")]
+    #[test]
+    fn should_highlight_names() {
+        use super::Highlighted;
+
+        let highlighted = Highlighted {
+            name: "core::option::Option<&'a alloc::string::String>",
+            tag: None,
+            short: false,
+        };
+
+        assert_eq!(
+            highlighted.to_string(),
+            "\x1b[2mcore::option::\x1b[0m\x1b[1mOption\x1b[0m\x1b[36m<\x1b[0m&\x1b[3;35m'a\x1b[0m \x1b[2malloc::string::\x1b[0mString\x1b[36m>\x1b[0m"
+        );
+
+        let highlighted = Highlighted {
+            name: "&dyn core::any::Any",
+            tag: Some("my_crate::Order"),
+            short: true,
+        };
+
+        assert_eq!(highlighted.to_string(), "&dyn \x1b[1mAny\x1b[0m @ \x1b[1mOrder\x1b[0m");
+    }
+
+    #[test]
+    fn should_render_multiline() {
+        let format = NameFormat::new().paths(PathStyle::Short).multiline(true);
+        let name = "core::result::Result<alloc::vec::Vec<(u8, core::option::Option<u8>)>, alloc::string::String>";
+
+        assert_eq!(
+            render(name, format).to_string(),
+            "Result<\n    Vec<\n        (u8, Option<u8>),\n    >,\n    String,\n>"
+        );
+        assert_eq!(render("alloc::vec::Vec<u8>", format).to_string(), "Vec<u8>");
+        assert_eq!(
+            render("my_crate::Service<my_crate::Layer<'_, u8>, [my_crate::Id<u8>; 2]>", format.lifetimes(false)).to_string(),
+            "Service<\n    Layer<u8>,\n    [Id<u8>; 2],\n>"
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn should_canonicalize_names() {
+        use super::canonicalize;
+
+        let names = [
+            ("std::sync::poison::mutex::Mutex<u8>", "std::sync::Mutex<u8>"),
+            ("std::sync::mutex::Mutex<u8>", "std::sync::Mutex<u8>"),
+            ("core::sync::atomic::AtomicU8", "std::sync::atomic::AtomicU8"),
+            ("alloc::collections::btree::map::BTreeMap<u8,u8>", "std::collections::BTreeMap<u8, u8>"),
+            ("alloc::collections::btree::map::entry::Entry<u8, u8>", "std::collections::btree_map::Entry<u8, u8>"),
+            ("std::collections::hash::map::Entry<u8, u8>", "std::collections::hash_map::Entry<u8, u8>"),
+            ("alloc::collections::vec_deque::iter::Iter<u8>", "std::collections::vec_deque::Iter<u8>"),
+            ("core::option::Option<&'static str>", "std::option::Option<&str>"),
+            ("my_crate[4f2a]::Foo<'_,  u8 >", "my_crate::Foo<u8>"),
+            ("&'a  mut [u8;4]", "&mut [u8; 4]"),
+            ("alloc::boxed::Box<dyn core::ops::function::Fn(u8)->u8+core::marker::Send + 'static>",
+             "std::boxed::Box<dyn std::ops::Fn(u8) -> u8 + std::marker::Send>"),
+            ("core::pin::Pin<alloc::boxed::Box<dyn core::future::future::Future<Output=u8>>>",
+             "std::pin::Pin<std::boxed::Box<dyn std::future::Future<Output = u8>>>"),
+            ("<my_crate::Foo as my_crate::Tr>::Out", "<my_crate::Foo as my_crate::Tr>::Out"),
+            ("my_crate::Foo<-1>", "my_crate::Foo<-1>"),
+            ("std::Foo", "std::Foo"),
+            ("(u8,)", "(u8,)"),
+            ("my_crate::Foo<u8,>", "my_crate::Foo<u8,>"),
+        ];
+
+        for &(name, canonical) in &names {
+            assert_eq!(canonicalize(name), canonical);
+            assert_eq!(canonicalize(canonical), canonical);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn should_keep_distinct_std_types_apart() {
+        use std::any::type_name;
+        use std::collections::{binary_heap, btree_map, btree_set, hash_map, hash_set, linked_list, vec_deque};
+        use std::collections::BTreeSet;
+        use super::canonicalize;
+
+        let names = [
+            type_name::<hash_map::Entry<u8, u8>>(),
+            type_name::<btree_map::Entry<u8, u8>>(),
+            type_name::<hash_map::Iter<u8, u8>>(),
+            type_name::<btree_map::Iter<u8, u8>>(),
+            type_name::<hash_set::Iter<u8>>(),
+            type_name::<btree_set::Iter<u8>>(),
+            type_name::<vec_deque::Iter<u8>>(),
+            type_name::<binary_heap::Iter<u8>>(),
+            type_name::<linked_list::Iter<u8>>(),
+        ];
+        let canonical: BTreeSet<_> = names.iter().map(|name| canonicalize(name)).collect();
+
+        assert_eq!(canonical.len(), names.len());
+        assert!(canonical.contains("std::collections::hash_map::Entry<u8, u8>"));
+        assert!(canonical.contains("std::collections::btree_map::Entry<u8, u8>"));
+        assert_eq!(canonicalize(type_name::<hash_map::HashMap<u8, u8>>()), "std::collections::HashMap<u8, u8>");
+    }
+
+    #[test]
+    fn should_split_path() {
+        let parts = split_path("std::collections::hash::map::HashMap<alloc::string::String, i32>").unwrap();
+        assert_eq!(parts.krate, Some("std"));
+        assert_eq!(parts.module, Some("collections::hash::map"));
+        assert_eq!(parts.ident, "HashMap");
+
+        let parts = split_path("my_crate::Foo").unwrap();
+        assert_eq!(parts.krate, Some("my_crate"));
+        assert_eq!(parts.module, None);
+        assert_eq!(parts.ident, "Foo");
+
+        let parts = split_path("i64").unwrap();
+        assert_eq!(parts.krate, None);
+        assert_eq!(parts.module, None);
+        assert_eq!(parts.ident, "i64");
+    }
+
+    #[test]
+    fn should_not_split_non_path_types() {
+        assert!(split_path("&str").is_none());
+        assert!(split_path("(i32, u8)").is_none());
+        assert!(split_path("[u8; 4]").is_none());
+        assert!(split_path("dyn core::any::Any").is_none());
+        assert!(split_path("").is_none());
+    }
+
+    #[test]
+    fn should_keep_closure_segment() {
+        assert_eq!(strip_paths("my_crate::main::{{closure}}").to_string(), "{{closure}}");
+    }
+
+    #[test]
+    fn should_keep_qualified_paths() {
+        assert_eq!(strip_paths("<alloc::vec::Vec<u8> as core::iter::IntoIterator>::IntoIter").to_string(), "<Vec<u8> as IntoIterator>::IntoIter");
+    }
+
+    #[test]
+    fn should_format_decimal() {
+        assert_eq!(Decimal::new(0).as_str(), "0");
+        assert_eq!(Decimal::new(42).as_str(), "42");
+        assert_eq!(Decimal::new(u64::MAX).as_str(), u64::MAX.to_string());
+    }
+
+    struct Tagged(&'static str, &'static str);
+
+    impl fmt::Display for Tagged {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            pad(f, |out| write!(out, "{} @ {}", self.0, self.1))
+        }
+    }
+
+    #[test]
+    fn should_pad_like_str() {
+        let tagged = Tagged("u64", "Ü");
+        assert_eq!(format!("[{}]", tagged), "[u64 @ Ü]");
+        assert_eq!(format!("[{:10}]", tagged), format!("[{:10}]", "u64 @ Ü"));
+        assert_eq!(format!("[{:>10}]", tagged), format!("[{:>10}]", "u64 @ Ü"));
+        assert_eq!(format!("[{:^10}]", tagged), format!("[{:^10}]", "u64 @ Ü"));
+        assert_eq!(format!("[{:-^11}]", tagged), format!("[{:-^11}]", "u64 @ Ü"));
+        assert_eq!(format!("[{:.5}]", tagged), format!("[{:.5}]", "u64 @ Ü"));
+        assert_eq!(format!("[{:>8.5}]", tagged), format!("[{:>8.5}]", "u64 @ Ü"));
+        assert_eq!(format!("[{:3}]", tagged), format!("[{:3}]", "u64 @ Ü"));
+    }
+}
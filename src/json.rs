@@ -0,0 +1,323 @@
+//! Minimal JSON reader for registry snapshots.
+
+use std::string::String;
+use std::vec::Vec;
+
+/// Parsed JSON value. Numbers are kept as they are written.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Item>),
+    Object(Vec<(String, Item)>),
+}
+
+/// Value of an array or an object, with the byte offset where it starts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item {
+    pub offset: usize,
+    pub value: Value,
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Item> {
+        match *self {
+            Value::Object(ref fields) => fields.iter().find(|field| field.0 == key).map(|field| &field.1),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Item]> {
+        match *self {
+            Value::Array(ref items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Error with the byte offset where the input stopped being valid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Error {
+    pub message: &'static str,
+    pub offset: usize,
+}
+
+/// Parse a whole document.
+pub fn parse(input: &str) -> Result<Value, Error> {
+    let mut parser = Parser { input, pos: 0 };
+    let value = parser.value(0)?;
+    parser.whitespace();
+    if parser.pos < input.len() {
+        return Err(parser.error("expected end of input"));
+    }
+    Ok(value)
+}
+
+/// Write a string literal, escaping quotes, backslashes and control
+/// characters.
+pub fn push_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Nesting deeper than this is rejected, so that malicious input can not
+/// overflow the stack.
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &'static str) -> Error {
+        Error { message, offset: self.pos }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nesting is too deep"));
+        }
+        self.whitespace();
+        match self.rest().chars().next() {
+            Some('{') => self.object(depth),
+            Some('[') => self.array(depth),
+            Some('"') => self.string().map(Value::String),
+            Some('-') | Some('0'..='9') => Ok(self.number()),
+            _ if self.eat("null") => Ok(Value::Null),
+            _ if self.eat("true") => Ok(Value::Bool(true)),
+            _ if self.eat("false") => Ok(Value::Bool(false)),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn item(&mut self, depth: usize) -> Result<Item, Error> {
+        self.whitespace();
+        let offset = self.pos;
+        let value = self.value(depth)?;
+        Ok(Item { offset, value })
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Value, Error> {
+        self.pos += 1;
+        let mut fields = Vec::new();
+        self.whitespace();
+        if self.eat("}") {
+            return Ok(Value::Object(fields));
+        }
+        loop {
+            self.whitespace();
+            if !self.rest().starts_with('"') {
+                return Err(self.error("expected a key"));
+            }
+            let key = self.string()?;
+            self.whitespace();
+            if !self.eat(":") {
+                return Err(self.error("expected `:`"));
+            }
+            let item = self.item(depth + 1)?;
+            fields.push((key, item));
+            self.whitespace();
+            if self.eat("}") {
+                return Ok(Value::Object(fields));
+            }
+            if !self.eat(",") {
+                return Err(self.error("expected `,` or `}`"));
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Value, Error> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.whitespace();
+        if self.eat("]") {
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.item(depth + 1)?);
+            self.whitespace();
+            if self.eat("]") {
+                return Ok(Value::Array(items));
+            }
+            if !self.eat(",") {
+                return Err(self.error("expected `,` or `]`"));
+            }
+        }
+    }
+
+    fn number(&mut self) -> Value {
+        let rest = self.rest();
+        let len = rest.find(|c: char| !(c.is_ascii_digit() || "+-.eE".contains(c))).unwrap_or(rest.len());
+        self.pos += len;
+        Value::Number(rest[..len].into())
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let c = match self.rest().chars().next() {
+                Some(c) => c,
+                None => return Err(self.error("unterminated string")),
+            };
+            match c {
+                '"' => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                '\\' => {
+                    self.pos += 1;
+                    out.push(self.escape()?);
+                }
+                c if (c as u32) < 0x20 => return Err(self.error("control character in string")),
+                c => {
+                    self.pos += c.len_utf8();
+                    out.push(c);
+                }
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, Error> {
+        let c = match self.rest().chars().next() {
+            Some(c) => c,
+            None => return Err(self.error("unterminated string")),
+        };
+        self.pos += 1;
+        Ok(match c {
+            '"' => '"',
+            '\\' => '\\',
+            '/' => '/',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let high = self.hex4()?;
+                if (0xd800..0xdc00).contains(&high) {
+                    if !self.eat("\\u") {
+                        return Err(self.error("expected a low surrogate"));
+                    }
+                    let low = self.hex4()?;
+                    if !(0xdc00..0xe000).contains(&low) {
+                        return Err(self.error("expected a low surrogate"));
+                    }
+                    let code = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+                    core::char::from_u32(code).ok_or_else(|| self.error("invalid character"))?
+                } else {
+                    core::char::from_u32(high).ok_or_else(|| self.error("invalid character"))?
+                }
+            }
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("invalid escape"));
+            }
+        })
+    }
+
+    fn hex4(&mut self) -> Result<u32, Error> {
+        let digits = self.rest().get(..4).ok_or_else(|| self.error("expected 4 hexadecimal digits"))?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(self.error("expected 4 hexadecimal digits"));
+        }
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::string::String;
+    use super::{parse, push_string, Error, Item, Value};
+
+    #[test]
+    fn should_parse_values() {
+        let value = parse(" {\"a\": [1, -2.5e3, true, false, null], \"b\": {}, \"c\": []} ").unwrap();
+        let values = |items: &[Item]| items.iter().map(|item| item.value.clone()).collect::<Vec<_>>();
+
+        assert_eq!(values(value.get("a").and_then(|item| item.value.as_array()).unwrap()), [
+            Value::Number("1".into()),
+            Value::Number("-2.5e3".into()),
+            Value::Bool(true),
+            Value::Bool(false),
+            Value::Null,
+        ]);
+        assert_eq!(value.get("b").map(|item| &item.value), Some(&Value::Object(vec![])));
+        assert_eq!(value.get("c").map(|item| &item.value), Some(&Value::Array(vec![])));
+        assert_eq!(value.get("d"), None);
+    }
+
+    #[test]
+    fn should_record_value_offsets() {
+        let value = parse("{\"a\": [1,  \"x\"], \"b\":null}").unwrap();
+        let a = value.get("a").unwrap();
+
+        assert_eq!(a.offset, 6);
+        assert_eq!(a.value.as_array().unwrap().iter().map(|item| item.offset).collect::<Vec<_>>(), [7, 11]);
+        assert_eq!(value.get("b").unwrap().offset, 21);
+    }
+
+    #[test]
+    fn should_parse_escapes() {
+        let value = parse(r#""a\"b\\c\/\n\u00e9\ud83d\ude00""#).unwrap();
+
+        assert_eq!(value.as_str(), Some("a\"b\\c/\né\u{1f600}"));
+    }
+
+    #[test]
+    fn should_round_trip_strings() {
+        let mut out = String::new();
+        push_string(&mut out, "a\"b\\c\nd\u{1}é");
+
+        assert_eq!(out, "\"a\\\"b\\\\c\\nd\\u0001é\"");
+        assert_eq!(parse(&out).unwrap().as_str(), Some("a\"b\\c\nd\u{1}é"));
+    }
+
+    #[test]
+    fn should_report_error_offsets() {
+        assert_eq!(parse("{\"a\" 1}"), Err(Error { message: "expected `:`", offset: 5 }));
+        assert_eq!(parse("[1, 2"), Err(Error { message: "expected `,` or `]`", offset: 5 }));
+        assert_eq!(parse("\"abc"), Err(Error { message: "unterminated string", offset: 4 }));
+        assert_eq!(parse("\"\\x\""), Err(Error { message: "invalid escape", offset: 2 }));
+        assert_eq!(parse("[] x"), Err(Error { message: "expected end of input", offset: 3 }));
+        assert_eq!(parse(&"[".repeat(100)).unwrap_err().message, "nesting is too deep");
+    }
+}
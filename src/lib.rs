@@ -3,32 +3,70 @@
 //! If you do not need readable type name, you should use `TypeId`. This
 //! wrapper re-implements `TypeId`.
 //!
-//! Since Rust 1.0, this library can only work on nightly Rust. To activate the nice names instead
-//! of gobbledygook, include this library with `features = ["nightly"]` configuration parameter.
-//! On stable rust, it falls back to gobbledygook (type identifier) instead of a nice name.
+//! Type names are obtained with `std::any::type_name`, which works on stable Rust.
+//! If type names are not wanted, for example to keep them out of the binary, include
+//! this library with `features = ["id-only"]` configuration parameter. In that case it
+//! falls back to gobbledygook (type identifier) instead of a nice name.
+//!
+//! With `features = ["nightly"]`, typedefs can be created and compared in `const`
+//! context. This requires nightly Rust.
+//!
+//! The library does not need the standard library. To use it on `no_std` targets,
+//! include it with `default-features = false, features = ["alloc"]`. Without the
+//! `alloc` feature it never allocates: functions that return owned names are not
+//! available, and names can be written out with `TypeDef::write_name` instead.
+//!
+//! With `features = ["color"]`, `TypeDef::colored` highlights names for terminals.
+//! With `features = ["demangle"]`, `demangle` turns symbol names from object files
+//! and backtraces into names written like type names.
+//! With `features = ["regex"]`, `TypeRegex` selects types by matching their
+//! canonical names with regular expressions.
+//! With `features = ["ctor"]`, `register_type!` adds types to the `registry`
+//! before `main` starts, and `features = ["derive"]` adds `#[derive(RegisterType)]`
+//! that does the same for a struct or an enum.
+//! With `features = ["link"]`, `register_type!` places types in a section that
+//! the linker collects into the list of the `link` module, and with the `std`
+//! feature adds them to the `registry` before `main` starts.
 //!
 //! To get a name of a type:
 //!
-//! ``` ignore
+//! ```
+//! # #[cfg(all(feature = "alloc", not(feature = "id-only")))] {
 //! use typedef::{ TypeDef };
 //!
 //! assert_eq!(TypeDef::name_of::<i64>(), "i64");
+//! # }
 //! ```
 //!
 //! Type can also serve as type identifier and name container:
 //!
-//! ``` ignore
+//! ```
+//! # #[cfg(not(feature = "id-only"))] {
 //! use typedef::{ TypeDef };
 //!
 //! let typedef = TypeDef::of::<i64>();
 //!
 //! assert!(typedef.is::<i64>());
-//! assert_eq!(typedef.get_str(), "i64");
+//! assert_eq!(typedef.name(), "i64");
+//! # }
+//! ```
+//!
+//! Names can be rendered differently with `TypeDef::format_with`, for example
+//! with `.` as the path separator:
+//!
+//! ```
+//! # #[cfg(not(feature = "id-only"))] {
+//! use typedef::{ TypeDef, NameFormat };
+//!
+//! let format = NameFormat::new().path_separator(".");
+//!
+//! assert_eq!(TypeDef::of::<Vec<String>>().format_with(&format).to_string(), "alloc.vec.Vec<alloc.string.String>");
+//! # }
 //! ```
 //!
 //! More common usage would be in a generic method:
 //!
-//! ``` ignore
+//! ```
 //! use std::any::Any;
 //! use std::fmt;
 //! use typedef::TypeDef;
@@ -41,226 +79,3013 @@
 //!     )
 //! }
 //!
+//! # #[cfg(not(feature = "id-only"))]
 //! fn main() {
 //!     assert_eq!(foo(15), "the value of i32 type is 15");
 //! }
+//! # #[cfg(feature = "id-only")]
+//! # fn main() {}
 //! ```
 
-#![cfg_attr(feature = "nightly", feature(core_intrinsics))]
+#![cfg_attr(feature = "nightly", feature(const_trait_impl, const_cmp))]
+#![cfg_attr(all(feature = "nightly", not(feature = "id-only")), feature(const_type_name))]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(any(feature = "std", test))]
+extern crate core;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "derive")]
+extern crate typedef_derive;
+
+use core::any::{Any, TypeId};
+use core::fmt;
+use core::hash;
+use core::cmp;
+#[cfg(feature = "alloc")]
+use alloc::borrow::Cow;
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+#[cfg(all(feature = "alloc", not(feature = "id-only")))]
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+#[cfg(not(feature = "id-only"))]
+use core::future::Future;
+use core::panic::Location;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use core::str::FromStr;
+
+/// Declare a function that is `const` if this crate is compiled with
+/// `features = ["nightly"]`.
+macro_rules! nightly_const {
+    ($(#[$attr:meta])* $vis:vis fn $($rest:tt)*) => {
+        $(#[$attr])*
+        #[cfg(feature = "nightly")]
+        $vis const fn $($rest)*
+
+        $(#[$attr])*
+        #[cfg(not(feature = "nightly"))]
+        $vis fn $($rest)*
+    };
+}
+
+mod format;
+mod docs;
+#[cfg(feature = "alloc")]
+mod mangle;
+#[cfg(feature = "alloc")]
+mod diff;
+#[cfg(feature = "alloc")]
+mod pattern;
+#[cfg(feature = "demangle")]
+mod demangle;
+#[cfg(feature = "regex")]
+mod regex;
+#[cfg(feature = "alloc")]
+pub mod typename;
+#[cfg(feature = "alloc")]
+pub mod codegen;
+#[cfg(feature = "alloc")]
+pub mod typemap;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "link")]
+#[macro_use]
+pub mod link;
+#[cfg(feature = "std")]
+pub mod registry;
+
+pub use format::{ NameFormat, PathStyle };
+pub use docs::DocsUrls;
+#[cfg(feature = "alloc")]
+pub use format::canonicalize;
+#[cfg(feature = "alloc")]
+pub use diff::closest_matches;
+#[cfg(feature = "alloc")]
+pub use typename::TypeKind;
+#[cfg(feature = "alloc")]
+pub use pattern::TypePattern;
+#[cfg(feature = "demangle")]
+pub use demangle::demangle;
+#[cfg(all(feature = "demangle", feature = "std"))]
+pub use demangle::demangle_typedef;
+#[cfg(feature = "regex")]
+pub use regex::{ RegexError, TypeRegex };
+/// Register a struct, enum or union at startup, as `register_type!` does.
+///
+/// With `#[typedef(rename = "...")]` the type implements `NamedType` with
+/// the name, and it is registered with `TypeDef::of_named`. Generic types are
+/// not supported, and are registered with `register_type!` for each concrete
+/// type instead.
+///
+/// This is only available if this crate is compiled with
+/// `features = ["derive"]`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate typedef;
+///
+/// use typedef::TypeDef;
+///
+/// #[derive(RegisterType)]
+/// #[typedef(rename = "shop::Order")]
+/// struct Order;
+///
+/// #[derive(RegisterType)]
+/// enum Status {
+///     Open,
+///     Closed,
+/// }
+///
+/// fn main() {
+///     let typedef = TypeDef::from_name("shop::Order").unwrap();
+///
+///     assert!(typedef.is::<Order>());
+///     assert_eq!(typedef.name(), "shop::Order");
+///     assert!(TypeDef::from_id(TypeDef::id_of::<Status>()).is_some());
+/// }
+/// ```
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate typedef;
+///
+/// #[derive(RegisterType)]
+/// struct Wrapper<T>(T);
+///
+/// fn main() {}
+/// ```
+#[cfg(feature = "derive")]
+pub use typedef_derive::RegisterType;
 
-use std::any::{Any, TypeId};
-use std::fmt;
-use std::hash;
-use std::cmp;
-use std::borrow::Cow;
+/// Re-exports the commonly used items in one import.
+///
+/// ```
+/// use typedef::prelude::*;
+///
+/// assert!(TypeDef::of::<i64>().is::<i64>());
+/// ```
+pub mod prelude {
+    pub use super::{ TypeDef, NamedType };
+    #[cfg(not(feature = "id-only"))]
+    pub use super::{ NameDef, FutureName };
+}
 
 /// Create a TypeDef structure to identify a type and to print its name.
 ///
-/// ``` ignore
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
 /// use typedef::{ TypeDef };
 ///
 /// let typedef = TypeDef::of::<i64>();
 ///
 /// assert!(typedef.is::<i64>());
-/// assert!(typedef.get_str() == "i64");
+/// assert!(typedef.name() == "i64");
+/// # }
 /// ```
-#[derive(Clone, Copy, Eq, Ord, Debug)]
-#[cfg(feature = "nightly")]
+#[derive(Clone, Copy, Debug)]
 pub struct TypeDef {
     id: TypeId,
+    #[cfg(not(feature = "id-only"))]
     name: &'static str,
-}
-
-#[derive(Clone, Copy, Eq, Ord, Debug)]
-#[cfg(not(feature = "nightly"))]
-pub struct TypeDef {
-    id: TypeId,
+    custom_name: Option<&'static str>,
+    location: Option<&'static Location<'static>>,
+    #[cfg(not(feature = "id-only"))]
+    tag: Option<&'static str>,
 }
 
 impl TypeDef {
-    /// Create a TypeDef structure from a type parameter.
+    nightly_const! {
+        /// Create a TypeDef structure from a type parameter.
+        ///
+        /// The type does not need to be `Sized`, so slices, `str` and trait objects
+        /// are supported too.
+        ///
+        /// ```
+        /// use typedef::{ TypeDef };
+        ///
+        /// let _typedef = TypeDef::of::<i64>();
+        /// let _typedef = TypeDef::of::<[u8]>();
+        /// ```
+        ///
+        /// With the `nightly` feature this is a `const fn`, so typedefs can be
+        /// placed in statics:
+        ///
+        /// ``` ignore
+        /// use typedef::{ TypeDef };
+        ///
+        /// static I64_TYPE: TypeDef = TypeDef::of::<i64>();
+        ///
+        /// assert!(I64_TYPE.is::<i64>());
+        /// ```
+        pub fn of<T: ?Sized + Any>() -> TypeDef {
+            TypeDef {
+                id: TypeId::of::<T>(),
+                #[cfg(not(feature = "id-only"))]
+                name: ::core::any::type_name::<T>(),
+                custom_name: None,
+                location: None,
+                #[cfg(not(feature = "id-only"))]
+                tag: None,
+            }
+        }
+    }
+
+    nightly_const! {
+        /// Create a TypeDef structure for a type that has a custom name.
+        ///
+        /// The name from `NamedType::NAME` is returned by `name` and used in
+        /// `Display`. The identity of the typedef is not affected, so it is still
+        /// equal to `TypeDef::of::<T>()`.
+        ///
+        /// ```
+        /// use typedef::{ TypeDef, NamedType };
+        ///
+        /// struct Order;
+        ///
+        /// impl NamedType for Order {
+        ///     const NAME: &'static str = "Order";
+        /// }
+        ///
+        /// let typedef = TypeDef::of_named::<Order>();
+        ///
+        /// assert_eq!(typedef.name(), "Order");
+        /// assert_eq!(typedef, TypeDef::of::<Order>());
+        /// ```
+        pub fn of_named<T: ?Sized + NamedType>() -> TypeDef {
+            TypeDef {
+                id: TypeId::of::<T>(),
+                #[cfg(not(feature = "id-only"))]
+                name: ::core::any::type_name::<T>(),
+                custom_name: Some(T::NAME),
+                location: None,
+                #[cfg(not(feature = "id-only"))]
+                tag: None,
+            }
+        }
+    }
+
+    nightly_const! {
+        /// Create a TypeDef structure for a type distinguished by a tag type.
+        ///
+        /// The identity combines both types, so typedefs of the same type with
+        /// different tags are not equal, and neither of them `is::<T>()`. The
+        /// tag is displayed after the type name, separated by ` @ `.
+        ///
+        /// ```
+        /// # #[cfg(not(feature = "id-only"))] {
+        /// use typedef::{ TypeDef };
+        ///
+        /// struct UserId;
+        /// struct OrderId;
+        ///
+        /// let user_id = TypeDef::of_tagged::<u64, UserId>();
+        ///
+        /// assert_eq!(user_id, TypeDef::of_tagged::<u64, UserId>());
+        /// assert!(user_id != TypeDef::of_tagged::<u64, OrderId>());
+        /// assert!(user_id != TypeDef::of::<u64>());
+        /// assert_eq!(format!("{:#}", user_id), "u64 @ UserId");
+        /// # }
+        /// ```
+        pub fn of_tagged<T: ?Sized + Any, Tag: ?Sized + Any>() -> TypeDef {
+            TypeDef {
+                id: TypeId::of::<Tagged<T, Tag>>(),
+                #[cfg(not(feature = "id-only"))]
+                name: ::core::any::type_name::<T>(),
+                custom_name: None,
+                location: None,
+                #[cfg(not(feature = "id-only"))]
+                tag: Some(::core::any::type_name::<Tag>()),
+            }
+        }
+    }
+
+    /// Create a TypeDef structure that records where it was created.
+    ///
+    /// The location of the caller is available through `location` and is
+    /// included in `Debug` output, which helps finding out where a typedef
+    /// came from. It does not affect the identity of the typedef.
     ///
     /// ```
     /// use typedef::{ TypeDef };
     ///
-    /// let _typedef = TypeDef::of::<i64>();
+    /// let typedef = TypeDef::of_traced::<i64>();
+    ///
+    /// assert_eq!(typedef.location().unwrap().file(), file!());
+    /// assert_eq!(typedef, TypeDef::of::<i64>());
     /// ```
-    #[cfg(feature = "nightly")]
-    pub fn of<T: Any>() -> TypeDef {
-        use std::intrinsics::type_name;
-        TypeDef {
-            id: TypeId::of::<T>(),
-            name: unsafe { type_name::<T>() },
+    #[track_caller]
+    pub fn of_traced<T: ?Sized + Any>() -> TypeDef {
+        let mut typedef = TypeDef::of::<T>();
+        typedef.location = Some(Location::caller());
+        typedef
+    }
+
+    nightly_const! {
+        /// Create a TypeDef structure from the type of a value.
+        ///
+        /// ```
+        /// use typedef::{ TypeDef };
+        ///
+        /// let value = 15i64;
+        ///
+        /// assert!(TypeDef::of_val(&value).is::<i64>());
+        /// ```
+        pub fn of_val<T: ?Sized + Any>(_val: &T) -> TypeDef {
+            TypeDef::of::<T>()
+        }
+    }
+
+    nightly_const! {
+        /// Get `TypeId` for specified type directly.
+        ///
+        /// ```
+        /// use std::any::{ TypeId };
+        /// use typedef::{ TypeDef };
+        ///
+        /// assert!(TypeDef::id_of::<i64>() == TypeId::of::<i64>());
+        /// ```
+        pub fn id_of<T: ?Sized + Any>() -> TypeId {
+            TypeId::of::<T>()
         }
     }
 
-    /// Create a TypeDef structure from a type parameter.
+    /// Find the typedef of a type registered with `registry::register`, or
+    /// `None` if the type is not registered.
+    ///
+    /// This is not available if this crate is compiled without the `std`
+    /// feature.
     ///
     /// ```
-    /// use typedef::{ TypeDef };
+    /// use std::any::{ TypeId };
+    /// use typedef::{ registry, TypeDef };
+    ///
+    /// registry::register::<Vec<u8>>();
     ///
-    /// let _typedef = TypeDef::of::<i64>();
+    /// assert_eq!(TypeDef::from_id(TypeId::of::<Vec<u8>>()), Some(TypeDef::of::<Vec<u8>>()));
+    /// assert_eq!(TypeDef::from_id(TypeId::of::<Vec<i8>>()), None);
     /// ```
-    #[cfg(not(feature = "nightly"))]
-    pub fn of<T: Any>() -> TypeDef {
-        TypeDef {
-            id: TypeId::of::<T>(),
+    #[cfg(feature = "std")]
+    pub fn from_id(id: TypeId) -> Option<TypeDef> {
+        registry::lookup(id)
+    }
+
+    /// Find the typedef of a type registered with `registry::register` by the
+    /// name of the type, or `None` if no registered type has this name.
+    ///
+    /// Names are compared in the canonical form, as returned by
+    /// `canonicalize`. The same lookup is done by `str::parse` and
+    /// `TypeDef::try_from`, which return `registry::UnknownTypeName` if the
+    /// type is not found.
+    ///
+    /// This is not available if this crate is compiled without the `std`
+    /// feature.
+    ///
+    /// ```
+    /// use typedef::{ registry, TypeDef };
+    ///
+    /// mod my_crate {
+    ///     pub struct Foo;
+    /// }
+    ///
+    /// registry::register::<my_crate::Foo>();
+    ///
+    /// let name = TypeDef::name_of::<my_crate::Foo>();
+    ///
+    /// assert!(TypeDef::from_name(&name).unwrap().is::<my_crate::Foo>());
+    /// assert!(TypeDef::from_name("my_crate::Bar").is_none());
+    /// assert_eq!(
+    ///     "my_crate::Bar".parse::<TypeDef>().unwrap_err().to_string(),
+    ///     "type `my_crate::Bar` is not registered"
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_name(name: &str) -> Option<TypeDef> {
+        registry::lookup_name(name)
+    }
+
+    /// Check if the type was recorded with `registry::register_impl` to
+    /// implement the trait, given as the trait object type, as
+    /// `implements::<dyn Plugin>()`.
+    ///
+    /// This is not available if this crate is compiled without the `std`
+    /// feature.
+    #[cfg(feature = "std")]
+    pub fn implements<Trait: ?Sized + Any>(&self) -> bool {
+        registry::implements::<Trait>(self)
+    }
+
+    nightly_const! {
+        /// Get type name for specified type directly.
+        ///
+        /// This is not available if this crate is compiled without the `alloc` feature.
+        ///
+        /// ```
+        /// use typedef::{ TypeDef };
+        ///
+        /// assert_eq!(TypeDef::name_of::<i64>(), "i64");
+        /// ```
+        #[cfg(all(feature = "alloc", not(feature = "id-only")))]
+        pub fn name_of<T: ?Sized + Any>() -> Cow<'static, str> {
+            Cow::Borrowed(::core::any::type_name::<T>())
         }
     }
 
-    /// Get `TypeId` for specified type directly.
+    /// Get type name for specified type directly.
+    ///
+    /// This crate is compiled with `features = ["id-only"]`, so the type
+    /// identifier is formatted instead.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
     ///
     /// ```
-    /// use std::any::{ TypeId };
     /// use typedef::{ TypeDef };
     ///
-    /// assert!(TypeDef::id_of::<i64>() == TypeId::of::<i64>());
+    /// assert_eq!(TypeDef::name_of::<i64>(), TypeDef::of::<i64>().raw_id().to_string());
     /// ```
-    pub fn id_of<T: Any>() -> TypeId {
-        TypeId::of::<T>()
+    #[cfg(all(feature = "alloc", feature = "id-only"))]
+    pub fn name_of<T: ?Sized + Any>() -> Cow<'static, str> {
+        Cow::Owned(alloc::format!("{}", raw_id(&TypeId::of::<T>())))
     }
 
-    /// Get type name for specified type directly.
+    /// Get type name for the type of a value directly.
     ///
-    /// This only works if this crate is compiled with `features = ["nightly"]`
+    /// This is not available if this crate is compiled without the `alloc` feature.
     ///
-    /// ``` ignore
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
     /// use typedef::{ TypeDef };
     ///
-    /// assert_eq!(TypeDef::name_of::<i64>(), "i64");
+    /// assert_eq!(TypeDef::name_of_val(&15i64), "i64");
+    /// # }
     /// ```
-    #[cfg(feature = "nightly")]
-    pub fn name_of<T: Any>() -> Cow<'static, str> {
-        use std::intrinsics::type_name;
-        Cow::Borrowed(unsafe { type_name::<T>() })
+    #[cfg(feature = "alloc")]
+    pub fn name_of_val<T: ?Sized + Any>(_val: &T) -> Cow<'static, str> {
+        TypeDef::name_of::<T>()
     }
 
-    /// Get type name for specified type directly.
+    nightly_const! {
+        /// Get type name for specified type directly, without requiring the type
+        /// to be `'static`.
+        ///
+        /// This makes it possible to name borrowed types such as `Foo<'a>`. There
+        /// is no `TypeId` for such types, use `NameDef` to store the name alone.
+        ///
+        /// This is not available if this crate is compiled with `features = ["id-only"]`
+        ///
+        /// ```
+        /// use typedef::{ TypeDef };
+        ///
+        /// fn name_of_ref<'a>(_value: &'a i64) -> &'static str {
+        ///     TypeDef::name_of_any_lifetime::<&'a i64>()
+        /// }
+        ///
+        /// assert_eq!(name_of_ref(&15), "&i64");
+        /// ```
+        #[cfg(not(feature = "id-only"))]
+        pub fn name_of_any_lifetime<T: ?Sized>() -> &'static str {
+            ::core::any::type_name::<T>()
+        }
+    }
+
+    nightly_const! {
+        /// Create a lifetime-erased handle for a type that is not necessarily
+        /// `'static`.
+        ///
+        /// The compiler erases lifetimes in type names, so `Foo<'a>` and
+        /// `Foo<'static>` produce equal handles. The returned `NameDef` has
+        /// name-based identity only: it can be compared with other `NameDef` and
+        /// `TypeDef` values by name, but two different types that share the same
+        /// name are indistinguishable.
+        ///
+        /// This is not available if this crate is compiled with `features = ["id-only"]`
+        ///
+        /// ```
+        /// use typedef::{ TypeDef };
+        ///
+        /// struct Foo<'a>(&'a str);
+        ///
+        /// fn erased<'a>(_foo: &Foo<'a>) -> typedef::NameDef {
+        ///     TypeDef::of_erased::<Foo<'a>>()
+        /// }
+        ///
+        /// assert_eq!(erased(&Foo("bar")), TypeDef::of_erased::<Foo<'static>>());
+        /// assert!(erased(&Foo("bar")) == TypeDef::of::<Foo<'static>>());
+        /// ```
+        #[cfg(not(feature = "id-only"))]
+        pub fn of_erased<T: ?Sized>() -> NameDef {
+            NameDef::of::<T>()
+        }
+    }
+
+    nightly_const! {
+        /// Check if typedef instance matches type.
+        ///
+        /// ```
+        /// use typedef::{ TypeDef };
+        ///
+        /// let typedef = TypeDef::of::<i64>();
+        ///
+        /// assert!(typedef.is::<i64>());
+        /// ```
+        pub fn is<T: ?Sized + Any>(&self) -> bool {
+            self.id == TypeId::of::<T>()
+        }
+    }
+
+    nightly_const! {
+        /// Get the `TypeId` of the type this typedef was created for.
+        ///
+        /// ```
+        /// use std::any::{ TypeId };
+        /// use typedef::{ TypeDef };
+        ///
+        /// let typedef = TypeDef::of::<i64>();
+        ///
+        /// assert!(typedef.id() == TypeId::of::<i64>());
+        /// ```
+        pub fn id(&self) -> TypeId {
+            self.id
+        }
+    }
+
+    /// Get an opaque numeric identifier of the type.
     ///
-    /// This only works if this crate is compiled with `features = ["nightly"]`
+    /// The value is derived by hashing the `TypeId` with FNV-1a, so it is
+    /// the same for the same type within a single binary, but may change
+    /// between compilations. It is used in place of the type name when names
+    /// are not available.
     ///
-    /// ``` ignore
+    /// `TypeId` is 128 bits wide and its representation is not specified, so
+    /// it is never reinterpreted as a number directly.
+    ///
+    /// ```
     /// use typedef::{ TypeDef };
     ///
-    /// assert_eq!(TypeDef::name_of::<i64>(), "i64");
+    /// assert_eq!(TypeDef::of::<i64>().raw_id(), TypeDef::of::<i64>().raw_id());
+    /// assert!(TypeDef::of::<i64>().raw_id() != TypeDef::of::<i32>().raw_id());
     /// ```
-    #[cfg(not(feature = "nightly"))]
-    pub fn name_of<T: Any>() -> Cow<'static, str> {
-        Cow::Owned(format!("{}", unsafe { ::std::mem::transmute_copy::<TypeId, u64>(&TypeId::of::<T>()) }))
+    pub fn raw_id(&self) -> u64 {
+        raw_id(&self.id)
     }
 
-    /// Check if typedef instance matches type.
+    /// Make `Display` print the type identifier instead of the type name,
+    /// for all typedefs.
+    ///
+    /// The identifier is the decimal `raw_id`, the same value that is used in
+    /// place of the name with `features = ["id-only"]`. Custom names, tags
+    /// and the alternate flag are ignored. This is useful to compare output
+    /// with fixtures that were recorded without type names. Only `Display` is
+    /// affected, `name` still returns the type name.
     ///
     /// ```
     /// use typedef::{ TypeDef };
     ///
-    /// let typedef = TypeDef::of::<i64>();
+    /// TypeDef::force_id_display(true);
+    /// assert_eq!(TypeDef::of::<i64>().to_string(), TypeDef::of::<i64>().raw_id().to_string());
     ///
-    /// assert!(typedef.is::<i64>());
+    /// TypeDef::force_id_display(false);
+    /// assert_eq!(TypeDef::of::<i64>().to_string(), TypeDef::of::<i64>().name());
     /// ```
-    pub fn is<T: Any>(&self) -> bool {
-        self.id == TypeId::of::<T>()
+    pub fn force_id_display(force: bool) {
+        FORCE_ID_DISPLAY.store(force, Ordering::Relaxed);
     }
 
-    /// Get the static `&str` for typedef instance.
+    /// Get a 64-bit hash of the type name that is stable between compilations.
+    ///
+    /// Unlike `TypeId` and `raw_id`, the value only depends on the type name,
+    /// so it can be persisted, for example in on-disk caches. It is computed
+    /// with 64-bit FNV-1a over the UTF-8 bytes of the compiler-generated type
+    /// name, followed by ` @ ` and the tag name for typedefs created with
+    /// `of_tagged`. Custom names from `of_named` are not used.
+    ///
+    /// The hash changes if the type is renamed or moved to another module, and
+    /// the compiler does not guarantee that type names are the same between
+    /// compiler versions.
+    ///
+    /// This is not available if this crate is compiled with `features = ["id-only"]`
+    ///
+    /// ```
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert_eq!(TypeDef::of::<i64>().stable_hash64(), 0x2ae1af192b331746);
+    /// ```
+    #[cfg(not(feature = "id-only"))]
+    pub fn stable_hash64(&self) -> u64 {
+        let mut hash = fnv1a64(FNV_OFFSET_BASIS, self.name.as_bytes());
+        if let Some(tag) = self.tag {
+            hash = fnv1a64(hash, b" @ ");
+            hash = fnv1a64(hash, tag.as_bytes());
+        }
+        hash
+    }
+
+    nightly_const! {
+        /// Get the type name for typedef instance.
+        ///
+        /// The name is a `&'static str` that can be stored without allocation.
+        /// If the typedef was created with `of_named`, the custom name is
+        /// returned.
+        ///
+        /// ```
+        /// use typedef::{ TypeDef };
+        ///
+        /// let typedef = TypeDef::of::<i64>();
+        ///
+        /// assert!(typedef.name() == "i64");
+        /// ```
+        #[cfg(not(feature = "id-only"))]
+        pub fn name(&self) -> &'static str {
+            match self.custom_name {
+                Some(name) => name,
+                None => self.name,
+            }
+        }
+    }
+
+    /// Get the type name for typedef instance.
+    ///
+    /// This crate is compiled with `features = ["id-only"]`, so the type
+    /// identifier is formatted instead. If the typedef was created with
+    /// `of_named`, the custom name is returned.
     ///
-    /// ``` ignore
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
     /// use typedef::{ TypeDef };
     ///
     /// let typedef = TypeDef::of::<i64>();
     ///
-    /// assert!(typedef.get_str() == "i64");
+    /// assert!(typedef.name() == typedef.raw_id().to_string());
     /// ```
-    #[cfg(feature = "nightly")]
-    pub fn get_str(&self) -> Cow<'static, str> {
-        Cow::Borrowed(self.name)
+    #[cfg(all(feature = "alloc", feature = "id-only"))]
+    pub fn name(&self) -> Cow<'static, str> {
+        if let Some(name) = self.custom_name {
+            return Cow::Borrowed(name);
+        }
+        Cow::Owned(alloc::format!("{}", self.raw_id()))
     }
 
-    /// Get the static `&str` for typedef instance.
+    /// Write the type name for typedef instance, without allocating.
     ///
-    /// This only works if this crate is compiled with `features = ["nightly"]`
+    /// This writes the same name that is returned by `name`, and is available
+    /// in every configuration, including the one without the `alloc` feature.
     ///
-    /// ``` ignore
+    /// ```
+    /// use std::fmt::Write;
     /// use typedef::{ TypeDef };
     ///
-    /// let typedef = TypeDef::of::<i64>();
+    /// let mut out = String::new();
+    /// TypeDef::of::<i64>().write_name(&mut out).unwrap();
     ///
-    /// assert!(typedef.get_str() == "i64");
+    /// assert_eq!(out, TypeDef::of::<i64>().name());
     /// ```
-    #[cfg(not(feature = "nightly"))]
-    pub fn get_str(&self) -> Cow<'static, str> {
-        Cow::Owned(format!("{}", unsafe { ::std::mem::transmute_copy::<TypeId, u64>(&self.id) }))
+    pub fn write_name<W: fmt::Write + ?Sized>(&self, out: &mut W) -> fmt::Result {
+        self.with_name(|name| out.write_str(name))
     }
-}
 
-impl PartialOrd for TypeDef {
-    #[inline(always)]
-    fn partial_cmp(&self, other: &TypeDef) -> Option<cmp::Ordering> {
-        self.id.partial_cmp(&other.id)
+    /// Get the type name with module paths removed.
+    ///
+    /// Paths are removed from the type and from all of its generic arguments,
+    /// the same way as in the alternate `Display` form (`{:#}`), but without
+    /// the tag. A name set with `registry::set_display_name` is returned as
+    /// it is.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// let typedef = TypeDef::of::<HashMap<String, i32>>();
+    ///
+    /// assert_eq!(typedef.short_name(), "HashMap<String, i32>");
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn short_name(&self) -> String {
+        #[cfg(feature = "std")]
+        {
+            if let Some(name) = registry::display_name(self.id) {
+                return name.to_string();
+            }
+        }
+        self.with_name(|name| format::strip_paths(name).to_string())
     }
-}
 
-impl hash::Hash for TypeDef {
-    #[inline(always)]
-    fn hash<H: hash::Hasher>(&self, state: &mut H) {
-        self.id.hash(state)
+    /// Convert the type name into an identifier.
+    ///
+    /// Module paths are removed, and every run of characters that are not
+    /// ASCII letters, digits or `_` is replaced with a single `_`. The result
+    /// matches `[A-Za-z_][A-Za-z0-9_]*` and is the same for the same type,
+    /// but different types may produce the same identifier, for example
+    /// `Vec<u8>` and `my_crate::Vec<u8>`.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert_eq!(TypeDef::of::<HashMap<String, i32>>().to_ident(), "HashMap_String_i32");
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn to_ident(&self) -> String {
+        self.with_name(|name| format::to_ident(name, false))
     }
-}
 
-impl PartialEq for TypeDef {
-    #[inline(always)]
-    fn eq(&self, other: &TypeDef) -> bool {
-        self.id == other.id
+    /// Convert the type name into a snake case identifier.
+    ///
+    /// This is the same as `to_ident`, but words of camel case names are
+    /// separated with `_` and all letters are converted to lowercase.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert_eq!(TypeDef::of::<HashMap<String, i32>>().to_snake_case(), "hash_map_string_i32");
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn to_snake_case(&self) -> String {
+        self.with_name(|name| format::to_ident(name, true))
     }
-}
 
-impl fmt::Display for TypeDef {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", &self.get_str())
+    /// Encode the type name so it can be used as a file name.
+    ///
+    /// ASCII letters, digits, `_` and `-` are kept and all other bytes are
+    /// percent-encoded, so the result is valid on Windows as well as on Unix
+    /// systems, and percent-decoding it gives back the type name. The result
+    /// is the same for the same type. Note that long type names can exceed
+    /// the file name length limit of the file system, and that file systems
+    /// which ignore case treat `foo::Bar` and `foo::bar` as the same file.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert_eq!(TypeDef::of::<Vec<u8>>().to_filename(), "alloc%3A%3Avec%3A%3AVec%3Cu8%3E");
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn to_filename(&self) -> String {
+        self.with_name(format::to_filename)
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::TypeDef;
+    /// Render the type name as a label value for metrics.
+    ///
+    /// The label is the `to_ident` name cut to at most 55 characters, followed
+    /// by `_` and 8 hexadecimal digits of a hash of the full type name and tag,
+    /// so it is at most 64 characters long, contains only ASCII letters,
+    /// digits and `_`, and types with the same short name still get different
+    /// labels. The hash is `stable_hash64`, so labels are the same between
+    /// builds as long as the type name is the same. With `features = ["id-only"]`
+    /// the hash is `raw_id`.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert_eq!(TypeDef::of::<HashMap<String, i32>>().to_metric_label(), "HashMap_String_i32_b6bef934");
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn to_metric_label(&self) -> String {
+        use core::fmt::Write;
 
-    #[test]
-    fn should_match_type() {
-        assert!(TypeDef::of::<i16>().is::<i16>());
+        const MAX_IDENT_LEN: usize = 55;
+
+        let mut label = self.to_ident();
+        label.truncate(MAX_IDENT_LEN);
+        #[cfg(not(feature = "id-only"))]
+        let hash = self.stable_hash64();
+        #[cfg(feature = "id-only")]
+        let hash = self.raw_id();
+        let _ = write!(label, "_{:08x}", (hash ^ (hash >> 32)) as u32);
+        label
     }
 
-    #[test]
-    fn should_not_match_incorrect_type() {
-        assert!(!TypeDef::of::<i16>().is::<i32>());
+    /// Get the type name with module paths removed only from the types of the
+    /// standard library.
+    ///
+    /// Paths starting with `std`, `core` or `alloc` are shortened to the last
+    /// segment, in the type and in all of its generic arguments, while paths
+    /// into other crates are kept intact.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ TypeDef };
+    ///
+    /// mod shapes {
+    ///     pub struct Circle;
+    /// }
+    ///
+    /// let typedef = TypeDef::of::<Vec<Option<shapes::Circle>>>();
+    ///
+    /// assert!(typedef.name_without_std_paths().starts_with("Vec<Option<"));
+    /// assert!(typedef.name_without_std_paths().ends_with("::shapes::Circle>>"));
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn name_without_std_paths(&self) -> String {
+        self.with_name(|name| format::strip_std_paths(name).to_string())
     }
 
-    #[test]
-    #[cfg(not(feature = "nightly"))]
-    fn should_return_type_name() {
-        assert_eq!(TypeDef::of::<i16>().get_str().into_owned(), format!("{:?}", type_id_fallback::<i16>()));
-        assert_eq!(TypeDef::of::<i64>().get_str().into_owned(), format!("{:?}", type_id_fallback::<i64>()));
+    /// Get the type name in canonical form, with the rules of `canonicalize`.
+    ///
+    /// Unlike `name`, the canonical name is the same for names printed by
+    /// different compiler versions, so it can be persisted and compared with
+    /// canonical names from other builds.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use std::sync::Mutex;
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert_eq!(TypeDef::of::<Mutex<&'static str>>().canonical_name(), "std::sync::Mutex<&str>");
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn canonical_name(&self) -> String {
+        self.with_name(format::canonicalize)
     }
 
-    #[test]
-    #[cfg(feature = "nightly")]
-    fn should_return_type_name() {
-        assert_eq!(&TypeDef::of::<i16>().get_str(), "i16");
-        assert_eq!(&TypeDef::of::<i64>().get_str(), "i64");
+    /// Get the type encoded with the Rust v0 symbol mangling scheme.
+    ///
+    /// This is the fragment that encodes the type in v0 symbols, for example
+    /// in the generic arguments of `my_crate::f::<Vec<u8>>`, which can be
+    /// used to find symbols of the type in object files and backtraces. The
+    /// type name does not contain crate disambiguators, so crates are
+    /// encoded as `C5alloc` where symbols have `Cs` and a hash, as in
+    /// `Cs1234abcd_5alloc`, and repeated paths are not replaced with back
+    /// references. Returns `None` for types that can not be encoded from the
+    /// type name alone, such as closures, `impl Trait` types and types with
+    /// const generic arguments.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert_eq!(TypeDef::of::<Vec<u8>>().mangled_v0().unwrap(), "INtNtC5alloc3vec3VechE");
+    /// assert_eq!(TypeDef::of::<&[u8; 16]>().mangled_v0().unwrap(), "RAhj10_");
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn mangled_v0(&self) -> Option<String> {
+        self.with_name(mangle::v0)
     }
 
-    #[test]
-    fn should_be_equal_to_another_typedef_of_the_same_type() {
-        assert_eq!(TypeDef::of::<i16>(), TypeDef::of::<i16>());
+    /// Compare the type name with the name of another type.
+    ///
+    /// The returned value implements `Display` and writes the type name with
+    /// the parts that differ marked: the name of this type between `[-` and
+    /// `-]`, followed by the name of the other type between `[+` and `+]`.
+    /// Generic arguments, references, tuples and other parts of the names are
+    /// compared one by one, so only the innermost parts that differ are
+    /// marked. Lifetimes and tags are not compared. The alternate flag
+    /// removes module paths.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// let expected = TypeDef::of::<HashMap<String, i32>>();
+    /// let found = TypeDef::of::<HashMap<String, u32>>();
+    ///
+    /// assert_eq!(format!("{:#}", expected.diff(&found)), "HashMap<String, [-i32-][+u32+]>");
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn diff(&self, other: &TypeDef) -> NameDiff {
+        NameDiff {
+            left: *self,
+            right: *other,
+        }
     }
 
-    #[test]
-    fn should_not_be_equal_to_another_typedef_of_different_type() {
-        assert!(TypeDef::of::<i16>() != TypeDef::of::<i32>());
+    /// Score how similar the type name is to the name of another type, from
+    /// `0.0` for entirely different names to `1.0` for the same names.
+    ///
+    /// The score is one minus the edit distance of the canonical names, as
+    /// returned by `canonical_name`, divided by the length of the longer
+    /// name. Use `closest_matches` to find similar names among many.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ TypeDef };
+    ///
+    /// let similarity = TypeDef::of::<Vec<u8>>().similarity(&TypeDef::of::<Vec<i8>>());
+    ///
+    /// assert!(similarity > 0.9 && similarity < 1.0);
+    /// assert_eq!(TypeDef::of::<u8>().similarity(&TypeDef::of::<u8>()), 1.0);
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn similarity(&self, other: &TypeDef) -> f32 {
+        self.with_name(|left| other.with_name(|right| diff::similarity(left, right)))
     }
 
-    #[cfg(not(feature = "nightly"))]
-    fn type_id_fallback<T: 'static>() -> u64 {
-        use std::any::TypeId;
-        unsafe { ::std::mem::transmute_copy::<TypeId, u64>(&TypeId::of::<T>()) }
+    /// Get a readable multi-line report about the type.
+    ///
+    /// The report lists the name, the short name, the crate, module and
+    /// identifier of the type when it has a path, the tag for typedefs created
+    /// with `of_tagged`, and the opaque identifier.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ TypeDef };
+    ///
+    /// let report = TypeDef::of::<Vec<u8>>().describe().to_string();
+    /// let lines: Vec<&str> = report.lines().collect();
+    ///
+    /// assert_eq!(lines[..5], [
+    ///     "name:       alloc::vec::Vec<u8>",
+    ///     "short name: Vec<u8>",
+    ///     "crate:      alloc",
+    ///     "module:     vec",
+    ///     "ident:      Vec",
+    /// ]);
+    /// // The identifier differs between compilations.
+    /// assert!(lines[5].starts_with("id:         0x"));
+    /// # }
+    /// ```
+    pub fn describe(&self) -> Description {
+        Description {
+            typedef: *self,
+        }
+    }
+
+    /// Render the type name escaped for embedding into HTML.
+    ///
+    /// The returned value implements `Display` and writes the same text as
+    /// `Display` of the typedef, including the alternate flag, with `&`, `<`,
+    /// `>`, `"` and `'` replaced by character references. Width, fill,
+    /// alignment and precision are not applied.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ TypeDef };
+    ///
+    /// let typedef = TypeDef::of::<Option<&str>>();
+    ///
+    /// assert_eq!(format!("{:#}", typedef.html_escaped()), "Option&lt;&amp;str&gt;");
+    /// # }
+    /// ```
+    pub fn html_escaped(&self) -> HtmlName {
+        HtmlName {
+            typedef: *self,
+            code: false,
+        }
+    }
+
+    /// Render the type name escaped for embedding into HTML and wrapped in a
+    /// `<code>` element.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ TypeDef };
+    ///
+    /// let typedef = TypeDef::of::<Vec<u8>>();
+    ///
+    /// assert_eq!(format!("{:#}", typedef.html_code()), "<code>Vec&lt;u8&gt;</code>");
+    /// # }
+    /// ```
+    pub fn html_code(&self) -> HtmlName {
+        HtmlName {
+            typedef: *self,
+            code: true,
+        }
+    }
+
+    /// Render the type name highlighted for terminals.
+    ///
+    /// The returned value implements `Display` and writes the name with ANSI
+    /// escape codes: module paths are dim, the outer type is bold, generic
+    /// brackets are colored and lifetimes are italic. The alternate flag
+    /// removes module paths, like with `Display` of the typedef. If the
+    /// `NO_COLOR` environment variable is set to a non-empty value, the name
+    /// is written without escape codes.
+    ///
+    /// This is only available if this crate is compiled with `features = ["color"]`.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ TypeDef };
+    ///
+    /// let highlighted = TypeDef::of::<Vec<u8>>().colored().to_string();
+    ///
+    /// assert!(highlighted.contains("Vec"));
+    /// # }
+    /// ```
+    #[cfg(feature = "color")]
+    pub fn colored(&self) -> ColoredName {
+        ColoredName {
+            typedef: *self,
+        }
+    }
+
+    /// Render the type name on multiple lines, with one generic argument per
+    /// line.
+    ///
+    /// This is the same as `format_with` with `NameFormat::new().multiline(true)`.
+    /// Use `format_with` to combine it with other options.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ TypeDef, NameFormat, PathStyle };
+    ///
+    /// let typedef = TypeDef::of::<Result<Vec<Option<u8>>, String>>();
+    ///
+    /// assert_eq!(typedef.pretty().to_string(), "\
+    /// core::result::Result<
+    ///     alloc::vec::Vec<
+    ///         core::option::Option<u8>,
+    ///     >,
+    ///     alloc::string::String,
+    /// >");
+    ///
+    /// let format = NameFormat::new().paths(PathStyle::Short).multiline(true);
+    ///
+    /// assert_eq!(typedef.format_with(&format).to_string(), "\
+    /// Result<
+    ///     Vec<
+    ///         Option<u8>,
+    ///     >,
+    ///     String,
+    /// >");
+    /// # }
+    /// ```
+    pub fn pretty(&self) -> FormattedName {
+        self.format_with(&NameFormat::new().multiline(true))
+    }
+
+    /// Get the URL of the rustdoc page of the type.
+    ///
+    /// Types of the standard library link to `https://doc.rust-lang.org/std/`
+    /// and types of other crates to the latest version on docs.rs. For
+    /// generic types the page of the outer type is returned, and references,
+    /// slices, tuples and other primitive types link to their primitive pages.
+    /// Closures and other types without a page return `None`.
+    ///
+    /// The URL is built from the path in the type name, which is the module
+    /// where the type is defined. For the standard library only the first
+    /// module is kept, which matches where types are re-exported, but other
+    /// crates may re-export types elsewhere. Items of other crates are linked
+    /// as structs, because the type name does not tell if a type is a struct
+    /// or an enum. Use `docs_url_with` to configure the base URLs.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert_eq!(
+    ///     TypeDef::of::<Vec<u8>>().docs_url().unwrap(),
+    ///     "https://doc.rust-lang.org/std/vec/struct.Vec.html"
+    /// );
+    /// assert_eq!(
+    ///     TypeDef::of::<HashMap<u8, u8>>().docs_url().unwrap(),
+    ///     "https://doc.rust-lang.org/std/collections/struct.HashMap.html"
+    /// );
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn docs_url(&self) -> Option<String> {
+        self.docs_url_with(&DocsUrls::new())
+    }
+
+    /// Get the URL of the rustdoc page of the type, with configured base URLs.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn docs_url_with(&self, urls: &DocsUrls) -> Option<String> {
+        self.with_name(|name| docs::docs_url(name, urls))
+    }
+
+    /// Render the type name with custom options.
+    ///
+    /// The returned value implements `Display` and is written out without
+    /// allocation. Like with `Display` of the typedef, the tag of typedefs
+    /// created with `of_tagged` is rendered after the name, and width, fill,
+    /// alignment and precision are applied to the whole name.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ TypeDef, NameFormat, PathStyle };
+    ///
+    /// let format = NameFormat::new()
+    ///     .paths(PathStyle::StripStd)
+    ///     .arg_separator(",");
+    ///
+    /// let typedef = TypeDef::of::<Result<Vec<u8>, String>>();
+    ///
+    /// assert_eq!(typedef.format_with(&format).to_string(), "Result<Vec<u8>,String>");
+    /// # }
+    /// ```
+    pub fn format_with(&self, format: &NameFormat) -> FormattedName {
+        FormattedName {
+            typedef: *self,
+            format: *format,
+        }
+    }
+
+    /// Get the name of the crate that defines the type, as `"std"` for
+    /// `HashMap<String, i32>`.
+    ///
+    /// Crate disambiguators are removed. Returns `None` for primitive types
+    /// and for names that do not start with a path, such as references,
+    /// tuples and trait objects.
+    ///
+    /// This is not available if this crate is compiled with `features = ["id-only"]`
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert_eq!(TypeDef::of::<HashMap<String, i32>>().crate_name(), Some("std"));
+    /// assert_eq!(TypeDef::of::<i32>().crate_name(), None);
+    /// ```
+    #[cfg(not(feature = "id-only"))]
+    pub fn crate_name(&self) -> Option<&'static str> {
+        format::split_path(self.name())?.krate.map(format::strip_disambiguator)
+    }
+
+    /// Get the path of the module that defines the type, without the crate
+    /// name, as `"collections::hash::map"` for `HashMap<String, i32>`.
+    ///
+    /// Returns `None` for types defined at the crate root, for primitive
+    /// types and for names that do not start with a path.
+    ///
+    /// This is not available if this crate is compiled with `features = ["id-only"]`
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert_eq!(TypeDef::of::<HashMap<String, i32>>().module_path(), Some("collections::hash::map"));
+    /// ```
+    #[cfg(not(feature = "id-only"))]
+    pub fn module_path(&self) -> Option<&'static str> {
+        format::split_path(self.name())?.module
+    }
+
+    /// Get the identifier of the type, without the path and the generic
+    /// arguments, as `"HashMap"` for `HashMap<String, i32>`.
+    ///
+    /// Returns `None` for names that do not start with a path, such as
+    /// references, tuples and trait objects.
+    ///
+    /// This is not available if this crate is compiled with `features = ["id-only"]`
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert_eq!(TypeDef::of::<HashMap<String, i32>>().ident(), Some("HashMap"));
+    /// assert_eq!(TypeDef::of::<i32>().ident(), Some("i32"));
+    /// assert_eq!(TypeDef::of::<&str>().ident(), None);
+    /// ```
+    #[cfg(not(feature = "id-only"))]
+    pub fn ident(&self) -> Option<&'static str> {
+        format::split_path(self.name()).map(|parts| parts.ident)
+    }
+
+    /// Iterate over the segments of the path that names the type, as `std`,
+    /// `collections`, `hash`, `map` and `HashMap` for `HashMap<String, i32>`.
+    ///
+    /// The path is found in the parsed name, so `::` in generic arguments
+    /// does not split segments. Crate disambiguators are removed. The
+    /// iterator is empty for types that are not named by a path, such as
+    /// references, tuples, closures and trait objects.
+    ///
+    /// This is not available if this crate is compiled without the `alloc`
+    /// feature or with `features = ["id-only"]`
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// let typedef = TypeDef::of::<HashMap<String, i32>>();
+    ///
+    /// assert_eq!(typedef.segments().collect::<Vec<_>>(), ["std", "collections", "hash", "map", "HashMap"]);
+    /// assert_eq!(typedef.segments().next_back(), Some("HashMap"));
+    /// assert_eq!(TypeDef::of::<&str>().segments().count(), 0);
+    /// ```
+    #[cfg(all(feature = "alloc", not(feature = "id-only")))]
+    pub fn segments(&self) -> PathSegments {
+        let path = match typename::parse(self.name()) {
+            Some(typename::TypeName::Path { path, .. }) => Some(path),
+            _ => None,
+        };
+        PathSegments { path }
+    }
+
+    /// Get the parsed generic arguments of the type, as `String` and `Vec<u8>`
+    /// for `HashMap<String, Vec<u8>>`.
+    ///
+    /// Only type arguments are returned, const arguments and lifetimes are
+    /// left out. Each argument can be inspected further with the `typename`
+    /// module.
+    ///
+    /// This is not available if this crate is compiled without the `alloc`
+    /// feature or with `features = ["id-only"]`
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// let args = TypeDef::of::<HashMap<String, Vec<u8>>>().generic_args();
+    ///
+    /// assert_eq!(format!("{:#}", args[0]), "String");
+    /// assert_eq!(format!("{:#}", args[1]), "Vec<u8>");
+    /// assert_eq!(args[1].generic_args().len(), 1);
+    /// ```
+    #[cfg(all(feature = "alloc", not(feature = "id-only")))]
+    pub fn generic_args(&self) -> Vec<typename::TypeName<'static>> {
+        let name = match typename::parse(self.name()) {
+            Some(name) => name,
+            None => return Vec::new(),
+        };
+        match name {
+            typename::TypeName::Path { args, .. } => args
+                .into_iter()
+                .filter_map(|arg| match arg {
+                    typename::GenericArg::Type(ty) => Some(ty),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Count the generic arguments of the type, as `2` for
+    /// `HashMap<String, i32>`.
+    ///
+    /// Type and const arguments are counted, lifetimes are not. Only the
+    /// arguments that appear in the type name are counted, so default type
+    /// parameters such as the hasher of `HashMap` are not included. Types
+    /// that are not named by a path, such as references and tuples, have no
+    /// generic arguments: `&Vec<u8>` has the arity `0`.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert_eq!(TypeDef::of::<HashMap<String, i32>>().generic_arity(), 2);
+    /// assert_eq!(TypeDef::of::<[u8; 4]>().generic_arity(), 0);
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn generic_arity(&self) -> usize {
+        self.with_name(|name| match typename::parse(name) {
+            Some(typename::TypeName::Path { args, .. }) => {
+                args.iter().filter(|arg| !matches!(*arg, typename::GenericArg::Lifetime(_))).count()
+            },
+            _ => 0,
+        })
+    }
+
+    /// Get the structural category of the type, determined from its name.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ TypeDef, TypeKind };
+    ///
+    /// assert_eq!(TypeDef::of::<u8>().kind(), TypeKind::Primitive);
+    /// assert_eq!(TypeDef::of::<&[u8]>().kind(), TypeKind::Reference);
+    /// assert_eq!(TypeDef::of::<Vec<u8>>().kind(), TypeKind::Path);
+    /// assert_eq!(TypeDef::of::<fn() -> u8>().kind(), TypeKind::FnPointer);
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn kind(&self) -> TypeKind {
+        self.with_name(typename::kind)
+    }
+
+    /// Check if the type name matches a pattern, as `Vec<_>` or
+    /// `HashMap<String, ..>`.
+    ///
+    /// See `TypePattern` for the pattern syntax. Invalid patterns match no
+    /// types. Use `TypePattern` to compile a pattern once and match it with
+    /// many types.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// let typedef = TypeDef::of::<HashMap<String, Vec<u8>>>();
+    ///
+    /// assert!(typedef.matches("HashMap<String, _>"));
+    /// assert!(typedef.matches("std::collections::HashMap<..>"));
+    /// assert!(!typedef.matches("HashMap<u8, _>"));
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn matches(&self, pattern: &str) -> bool {
+        TypePattern::new(pattern).is_some_and(|pattern| pattern.matches(self))
+    }
+
+    /// Check if the canonical name of the type matches a glob pattern, where
+    /// `*` matches any sequence of characters and `?` matches any one
+    /// character.
+    ///
+    /// The pattern is matched with the whole name returned by
+    /// `canonical_name`, so paths into the standard library are written as
+    /// `std::collections::HashMap` and there is one space after `,`. `*` also
+    /// matches `::` and generic arguments; use `matches` to match the
+    /// structure of names.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use std::collections::HashMap;
+    /// use typedef::{ TypeDef };
+    ///
+    /// let typedef = TypeDef::of::<HashMap<String, i32>>();
+    ///
+    /// assert!(typedef.matches_glob("std::*::HashMap<*>"));
+    /// assert!(typedef.matches_glob("*<std::string::String, i32>"));
+    /// assert!(!typedef.matches_glob("*::HashSet<*>"));
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn matches_glob(&self, glob: &str) -> bool {
+        pattern::matches_glob(glob, &self.canonical_name())
+    }
+
+    /// Check if the canonical name of the type matches a regular expression.
+    ///
+    /// See `TypeRegex` for the syntax. Invalid expressions match no types.
+    /// Use `TypeRegex` to compile an expression once and match it with many
+    /// types.
+    ///
+    /// This is only available if this crate is compiled with `features = ["regex"]`.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ TypeDef };
+    ///
+    /// let typedef = TypeDef::of::<Vec<Option<u8>>>();
+    ///
+    /// assert!(typedef.matches_regex(r"^std::vec::Vec<std::option::Option<[iu]\d+>>$"));
+    /// assert!(!typedef.matches_regex(r"^std::vec::Vec<u8>$"));
+    /// # }
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn matches_regex(&self, regex: &str) -> bool {
+        TypeRegex::new(regex).is_ok_and(|regex| regex.matches(self))
+    }
+
+    /// Check if the type is a trait object, as `dyn Error + Send + 'static`.
+    ///
+    /// Types that contain trait objects, as `Box<dyn Error>`, are not trait
+    /// objects themselves; their trait bounds can be found with the
+    /// `typename` module.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use std::error::Error;
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert!(TypeDef::of::<dyn Error + Send + Sync>().is_trait_object());
+    /// assert!(!TypeDef::of::<Box<dyn Error>>().is_trait_object());
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn is_trait_object(&self) -> bool {
+        self.kind() == TypeKind::TraitObject
+    }
+
+    /// Check if the type has generic arguments, as counted by `generic_arity`.
+    ///
+    /// This is not available if this crate is compiled without the `alloc` feature.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert!(TypeDef::of::<Vec<u8>>().is_generic());
+    /// assert!(!TypeDef::of::<String>().is_generic());
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn is_generic(&self) -> bool {
+        self.generic_arity() > 0
+    }
+
+    /// Check if the typedef was created for a closure type.
+    ///
+    /// This is not available if this crate is compiled with `features = ["id-only"]`
+    ///
+    /// ```
+    /// use typedef::{ TypeDef };
+    ///
+    /// fn typedef_of_val<T: 'static>(_value: &T) -> TypeDef {
+    ///     TypeDef::of::<T>()
+    /// }
+    ///
+    /// assert!(typedef_of_val(&|| 15).is_closure());
+    /// assert!(!TypeDef::of::<fn() -> i32>().is_closure());
+    /// ```
+    #[cfg(not(feature = "id-only"))]
+    pub fn is_closure(&self) -> bool {
+        format::is_closure(self.name)
+    }
+
+    /// Check if the typedef was created for a type that has no name in the
+    /// source code.
+    ///
+    /// Such types are generated by the compiler: closures, async blocks and
+    /// the futures returned by `async fn`. Their names consist of the path of
+    /// the item that defines them followed by segments in double braces, as
+    /// `my_crate::fetch::{{closure}}`. Note that types behind `impl Trait` are
+    /// named after the underlying type, so they are only opaque if that type is.
+    ///
+    /// This is not available if this crate is compiled with `features = ["id-only"]`
+    ///
+    /// ```edition2018
+    /// use typedef::{ TypeDef };
+    ///
+    /// async fn fetch() -> u8 {
+    ///     15
+    /// }
+    ///
+    /// assert!(TypeDef::of_val(&fetch()).is_opaque());
+    /// assert!(!TypeDef::of::<Vec<u8>>().is_opaque());
+    /// ```
+    #[cfg(not(feature = "id-only"))]
+    pub fn is_opaque(&self) -> bool {
+        format::generated_origin(self.name).is_some()
+    }
+
+    nightly_const! {
+        /// Get the name of the tag type, if the typedef was created with `of_tagged`.
+        ///
+        /// This is not available if this crate is compiled with `features = ["id-only"]`
+        ///
+        /// ```
+        /// use typedef::{ TypeDef };
+        ///
+        /// assert_eq!(TypeDef::of_tagged::<u64, i8>().tag_name(), Some("i8"));
+        /// assert_eq!(TypeDef::of::<u64>().tag_name(), None);
+        /// ```
+        #[cfg(not(feature = "id-only"))]
+        pub fn tag_name(&self) -> Option<&'static str> {
+            self.tag
+        }
+    }
+
+    #[cfg(not(feature = "id-only"))]
+    fn tag(&self) -> Option<&'static str> {
+        self.tag
+    }
+
+    #[cfg(feature = "id-only")]
+    fn tag(&self) -> Option<&'static str> {
+        None
+    }
+
+    #[cfg(not(feature = "id-only"))]
+    fn with_name<R, F: FnOnce(&str) -> R>(&self, f: F) -> R {
+        f(self.name())
+    }
+
+    #[cfg(feature = "id-only")]
+    fn with_name<R, F: FnOnce(&str) -> R>(&self, f: F) -> R {
+        match self.custom_name {
+            Some(name) => f(name),
+            None => f(format::Decimal::new(self.raw_id()).as_str()),
+        }
+    }
+
+    /// Get the source location where the typedef was created, if it was created
+    /// with `of_traced`.
+    ///
+    /// ```
+    /// use typedef::{ TypeDef };
+    ///
+    /// assert!(TypeDef::of_traced::<i64>().location().is_some());
+    /// assert!(TypeDef::of::<i64>().location().is_none());
+    /// ```
+    pub fn location(&self) -> Option<&'static Location<'static>> {
+        self.location
+    }
+
+    /// Get the static `&str` for typedef instance.
+    ///
+    /// ```
+    /// # #![allow(deprecated)]
+    /// use typedef::{ TypeDef };
+    ///
+    /// let typedef = TypeDef::of::<i64>();
+    ///
+    /// assert!(typedef.get_str() == "i64");
+    /// ```
+    #[deprecated(note = "use `TypeDef::name` instead")]
+    #[cfg(all(feature = "alloc", not(feature = "id-only")))]
+    pub fn get_str(&self) -> Cow<'static, str> {
+        Cow::Borrowed(self.name())
+    }
+
+    /// Get the static `&str` for typedef instance.
+    ///
+    /// This crate is compiled with `features = ["id-only"]`, so the type
+    /// identifier is formatted instead.
+    #[deprecated(note = "use `TypeDef::name` instead")]
+    #[cfg(all(feature = "alloc", feature = "id-only"))]
+    pub fn get_str(&self) -> Cow<'static, str> {
+        self.name()
+    }
+}
+
+impl PartialOrd for TypeDef {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &TypeDef) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders typedefs by readable type name, falling back to `TypeId` for types
+/// that share the same name.
+///
+/// The order of names is lexicographic and therefore stable between
+/// compilations, which makes it suitable for sorted output. Ordering by
+/// `TypeId` is only consistent within a single binary.
+///
+/// With the `id-only` feature the names are not available, and typedefs are
+/// ordered by `TypeId` only.
+#[cfg(not(feature = "id-only"))]
+impl Ord for TypeDef {
+    #[inline(always)]
+    fn cmp(&self, other: &TypeDef) -> cmp::Ordering {
+        self.name.cmp(other.name)
+            .then_with(|| self.id.cmp(&other.id))
+    }
+}
+
+/// Orders typedefs by `TypeId`.
+///
+/// Type names are not available with the `id-only` feature, so this order
+/// is only consistent within a single binary.
+#[cfg(feature = "id-only")]
+impl Ord for TypeDef {
+    #[inline(always)]
+    fn cmp(&self, other: &TypeDef) -> cmp::Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+/// Hashes only the underlying `TypeId`, consistently with `PartialEq`, so
+/// `TypeDef` can be used as a key in `HashMap` and `HashSet`.
+impl hash::Hash for TypeDef {
+    #[inline(always)]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+impl PartialEq for TypeDef {
+    #[inline(always)]
+    fn eq(&self, other: &TypeDef) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for TypeDef {}
+
+/// Compares the type name with a string.
+///
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
+/// use typedef::{ TypeDef };
+///
+/// assert!(TypeDef::of::<i64>() == "i64");
+/// assert!("i64" == TypeDef::of::<i64>());
+/// # }
+/// ```
+impl PartialEq<str> for TypeDef {
+    #[inline(always)]
+    fn eq(&self, other: &str) -> bool {
+        self.with_name(|name| name == other)
+    }
+}
+
+impl<'a> PartialEq<&'a str> for TypeDef {
+    #[inline(always)]
+    fn eq(&self, other: &&'a str) -> bool {
+        self.with_name(|name| name == *other)
+    }
+}
+
+impl PartialEq<TypeDef> for str {
+    #[inline(always)]
+    fn eq(&self, other: &TypeDef) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<TypeDef> for &str {
+    #[inline(always)]
+    fn eq(&self, other: &TypeDef) -> bool {
+        other == *self
+    }
+}
+
+/// Compares the typedef with a `TypeId` obtained elsewhere.
+///
+/// ```
+/// use std::any::{ TypeId };
+/// use typedef::{ TypeDef };
+///
+/// assert!(TypeDef::of::<i64>() == TypeId::of::<i64>());
+/// assert!(TypeId::of::<i64>() == TypeDef::of::<i64>());
+/// ```
+impl PartialEq<TypeId> for TypeDef {
+    #[inline(always)]
+    fn eq(&self, other: &TypeId) -> bool {
+        self.id == *other
+    }
+}
+
+impl PartialEq<TypeDef> for TypeId {
+    #[inline(always)]
+    fn eq(&self, other: &TypeDef) -> bool {
+        *self == other.id
+    }
+}
+
+/// Exposes the type name as a string slice.
+///
+/// This is not available if this crate is compiled with `features = ["id-only"]`
+#[cfg(not(feature = "id-only"))]
+impl AsRef<str> for TypeDef {
+    #[inline(always)]
+    fn as_ref(&self) -> &str {
+        self.name()
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromStr for TypeDef {
+    type Err = registry::UnknownTypeName;
+
+    fn from_str(name: &str) -> Result<TypeDef, registry::UnknownTypeName> {
+        registry::parse_name(name)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> TryFrom<&'a str> for TypeDef {
+    type Error = registry::UnknownTypeName;
+
+    fn try_from(name: &'a str) -> Result<TypeDef, registry::UnknownTypeName> {
+        registry::parse_name(name)
+    }
+}
+
+impl From<TypeDef> for TypeId {
+    #[inline(always)]
+    fn from(typedef: TypeDef) -> TypeId {
+        typedef.id
+    }
+}
+
+/// Displays the type name.
+///
+/// The alternate flag (`{:#}`) displays the short name instead, with module
+/// paths removed from the type and from all of its generic arguments.
+///
+/// Typedefs created with `of_tagged` display the tag after the name, as
+/// `u64 @ my_crate::UserId`.
+///
+/// Crate disambiguators that some toolchains add to type names, as in
+/// `my_crate[4f2a]::Foo`, are not displayed.
+///
+/// A name set with `registry::set_display_name` is displayed as it is in
+/// both forms.
+///
+/// Width, fill, alignment and precision are applied to the whole name, so
+/// names can be aligned in columns or truncated.
+///
+/// ```
+/// use typedef::{ TypeDef, NamedType };
+///
+/// struct Order;
+///
+/// impl NamedType for Order {
+///     const NAME: &'static str = "Order";
+/// }
+///
+/// let typedef = TypeDef::of_named::<Order>();
+///
+/// assert_eq!(format!("[{:>8}]", typedef), "[   Order]");
+/// assert_eq!(format!("[{:-<8}]", typedef), "[Order---]");
+/// assert_eq!(format!("[{:.3}]", typedef), "[Ord]");
+/// ```
+///
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
+/// use typedef::{ TypeDef };
+///
+/// let typedef = TypeDef::of::<Vec<String>>();
+///
+/// assert_eq!(format!("{}", typedef), "alloc::vec::Vec<alloc::string::String>");
+/// assert_eq!(format!("{:#}", typedef), "Vec<String>");
+/// assert_eq!(format!("[{:>#13}]", typedef), "[  Vec<String>]");
+/// assert_eq!(format!("[{:-<#13}]", typedef), "[Vec<String>--]");
+/// assert_eq!(format!("[{:#.3}]", typedef), "[Vec]");
+/// # }
+/// ```
+impl fmt::Display for TypeDef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if FORCE_ID_DISPLAY.load(Ordering::Relaxed) {
+            return f.pad(format::Decimal::new(self.raw_id()).as_str());
+        }
+        #[cfg(feature = "std")]
+        {
+            if let Some(name) = registry::display_name(self.id) {
+                return f.pad(name);
+            }
+        }
+
+        self.with_name(|name| match (f.alternate(), self.tag()) {
+            (false, None) if !format::has_disambiguator(name) => f.pad(name),
+            (false, tag) => format::pad(f, |out| write!(out, "{}", format::render_tagged(name, tag, NameFormat::new()))),
+            (true, None) => format::pad(f, |out| write!(out, "{}", format::strip_paths(name))),
+            (true, Some(tag)) => format::pad(f, |out| write!(out, "{} @ {}", format::strip_paths(name), format::strip_paths(tag))),
+        })
+    }
+}
+
+/// Formats the underlying type identifier as lowercase hexadecimal.
+///
+/// The value is the same for the same type within a single binary, but may
+/// change between compilations.
+///
+/// ```
+/// use typedef::{ TypeDef };
+///
+/// let typedef = TypeDef::of::<i64>();
+///
+/// assert_eq!(format!("{:x}", typedef), format!("{:x}", TypeDef::of::<i64>()));
+/// assert!(format!("{:x}", typedef) != format!("{:x}", TypeDef::of::<i32>()));
+/// ```
+impl fmt::LowerHex for TypeDef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.raw_id(), f)
+    }
+}
+
+/// Formats the underlying type identifier as uppercase hexadecimal.
+///
+/// The value is the same for the same type within a single binary, but may
+/// change between compilations.
+impl fmt::UpperHex for TypeDef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.raw_id(), f)
+    }
+}
+
+static FORCE_ID_DISPLAY: AtomicBool = AtomicBool::new(false);
+
+fn raw_id(id: &TypeId) -> u64 {
+    use core::hash::Hasher;
+
+    let mut hasher = FnvHasher(FNV_OFFSET_BASIS);
+    hash::Hash::hash(id, &mut hasher);
+    hasher.finish()
+}
+
+/// 64-bit FNV-1a hasher, which unlike `std::collections::hash_map::DefaultHasher`
+/// is available without the standard library.
+struct FnvHasher(u64);
+
+impl hash::Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0 = fnv1a64(self.0, bytes);
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+fn fnv1a64(mut hash: u64, bytes: &[u8]) -> u64 {
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Type name rendered with custom options, created by `TypeDef::format_with`.
+#[derive(Clone, Copy, Debug)]
+pub struct FormattedName {
+    typedef: TypeDef,
+    format: NameFormat,
+}
+
+impl fmt::Display for FormattedName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.typedef.with_name(|name| {
+            format::pad(f, |out| write!(out, "{}", format::render_tagged(name, self.typedef.tag(), self.format)))
+        })
+    }
+}
+
+/// List of types that implements `Display`, as in `expected one of: i32, u8`.
+///
+/// The types are printed as with their own `Display` implementation, and the
+/// alternate flag (`{:#}`) removes module paths from all of them.
+///
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
+/// use typedef::{ TypeDef, TypeDefList };
+///
+/// let types = [TypeDef::of::<String>(), TypeDef::of::<Vec<u8>>()];
+///
+/// assert_eq!(format!("{:#}", TypeDefList::new(&types)), "String, Vec<u8>");
+/// assert_eq!(format!("{:#}", TypeDefList::new(&types).separator(" or ")), "String or Vec<u8>");
+/// assert_eq!(format!("{}", TypeDefList::new(&types[..1])), "alloc::string::String");
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct TypeDefList<'a> {
+    types: &'a [TypeDef],
+    separator: &'a str,
+}
+
+impl<'a> TypeDefList<'a> {
+    /// Create a list of types separated with `", "`.
+    pub const fn new(types: &'a [TypeDef]) -> TypeDefList<'a> {
+        TypeDefList { types, separator: ", " }
+    }
+
+    /// Set the text written between the types.
+    pub const fn separator(mut self, separator: &'a str) -> TypeDefList<'a> {
+        self.separator = separator;
+        self
+    }
+}
+
+impl<'a> fmt::Display for TypeDefList<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, typedef) in self.types.iter().enumerate() {
+            if i > 0 {
+                f.write_str(self.separator)?;
+            }
+            if f.alternate() {
+                write!(f, "{:#}", typedef)?;
+            } else {
+                write!(f, "{}", typedef)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Registry of typedefs that only reads a slice, for targets without
+/// allocation where the global `registry` is not available.
+///
+/// Lookups scan the slice. With `features = ["nightly"]` typedefs are
+/// created in `const` functions, so the slice can be a `static`; otherwise
+/// it is built when the program starts. With `features = ["link"]`, the
+/// `link` module looks up the types of `register_type!` in the same way, in
+/// the slice that the linker collects.
+///
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
+/// use typedef::{ StaticRegistry, TypeDef };
+/// use std::any::TypeId;
+///
+/// struct Sensor;
+///
+/// let types = [TypeDef::of::<Sensor>(), TypeDef::of::<u16>()];
+/// let registry = StaticRegistry::new(&types);
+///
+/// assert!(registry.lookup(TypeId::of::<Sensor>()).unwrap().is::<Sensor>());
+/// assert_eq!(registry.lookup(TypeId::of::<u8>()), None);
+/// assert_eq!(registry.lookup_name("u16"), Some(TypeDef::of::<u16>()));
+/// assert_eq!(registry.iter().count(), 2);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct StaticRegistry<'a> {
+    types: &'a [TypeDef],
+}
+
+impl<'a> StaticRegistry<'a> {
+    /// Create a registry of the typedefs in the slice.
+    pub const fn new(types: &'a [TypeDef]) -> StaticRegistry<'a> {
+        StaticRegistry { types }
+    }
+
+    /// Find the typedef of the type.
+    pub fn lookup(&self, id: TypeId) -> Option<TypeDef> {
+        self.types.iter().find(|typedef| typedef.id() == id).cloned()
+    }
+
+    /// Find the typedef by the name returned by `TypeDef::name`.
+    ///
+    /// Names are compared as they are, unlike the names given to
+    /// `registry::lookup_name`, which are canonicalized first.
+    ///
+    /// This is not available if this crate is compiled with `features = ["id-only"]`.
+    #[cfg(not(feature = "id-only"))]
+    pub fn lookup_name(&self, name: &str) -> Option<TypeDef> {
+        self.types.iter().find(|typedef| typedef.name() == name).cloned()
+    }
+
+    /// Iterate over the typedefs, in the order of the slice.
+    pub fn iter(&self) -> core::iter::Cloned<core::slice::Iter<'a, TypeDef>> {
+        self.types.iter().cloned()
+    }
+
+    /// Get the slice of typedefs.
+    pub const fn types(&self) -> &'a [TypeDef] {
+        self.types
+    }
+}
+
+/// Iterator over the path segments of a type name, created by
+/// `TypeDef::segments`.
+#[cfg(all(feature = "alloc", not(feature = "id-only")))]
+#[derive(Clone, Debug)]
+pub struct PathSegments {
+    path: Option<&'static str>,
+}
+
+#[cfg(all(feature = "alloc", not(feature = "id-only")))]
+impl Iterator for PathSegments {
+    type Item = &'static str;
+
+    fn next(&mut self) -> Option<&'static str> {
+        let path = self.path?;
+        let segment = match path.find("::") {
+            Some(pos) => {
+                self.path = Some(&path[pos + 2..]);
+                &path[..pos]
+            },
+            None => {
+                self.path = None;
+                path
+            },
+        };
+        Some(format::strip_disambiguator(segment))
+    }
+}
+
+#[cfg(all(feature = "alloc", not(feature = "id-only")))]
+impl DoubleEndedIterator for PathSegments {
+    fn next_back(&mut self) -> Option<&'static str> {
+        let path = self.path?;
+        let segment = match path.rfind("::") {
+            Some(pos) => {
+                self.path = Some(&path[..pos]);
+                &path[pos + 2..]
+            },
+            None => {
+                self.path = None;
+                path
+            },
+        };
+        Some(format::strip_disambiguator(segment))
+    }
+}
+
+/// HTML-escaped type name, created by `TypeDef::html_escaped` and
+/// `TypeDef::html_code`.
+#[derive(Clone, Copy, Debug)]
+pub struct HtmlName {
+    typedef: TypeDef,
+    code: bool,
+}
+
+impl fmt::Display for HtmlName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use core::fmt::Write;
+
+        if self.code {
+            f.write_str("<code>")?;
+        }
+        if f.alternate() {
+            write!(format::HtmlEscape(f), "{:#}", self.typedef)?;
+        } else {
+            write!(format::HtmlEscape(f), "{}", self.typedef)?;
+        }
+        if self.code {
+            f.write_str("</code>")?;
+        }
+        Ok(())
+    }
+}
+
+/// Type name highlighted for terminals, created by `TypeDef::colored`.
+#[cfg(feature = "color")]
+#[derive(Clone, Copy, Debug)]
+pub struct ColoredName {
+    typedef: TypeDef,
+}
+
+#[cfg(feature = "color")]
+impl fmt::Display for ColoredName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        if no_color || FORCE_ID_DISPLAY.load(Ordering::Relaxed) {
+            return fmt::Display::fmt(&self.typedef, f);
+        }
+        self.typedef.with_name(|name| {
+            write!(f, "{}", format::Highlighted { name, tag: self.typedef.tag(), short: f.alternate() })
+        })
+    }
+}
+
+/// Differences between two type names, created by `TypeDef::diff`.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy, Debug)]
+pub struct NameDiff {
+    left: TypeDef,
+    right: TypeDef,
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for NameDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let short = f.alternate();
+        self.left.with_name(|left| {
+            self.right.with_name(|right| diff::write_diff(left, right, short, f))
+        })
+    }
+}
+
+/// Multi-line report about a type, created by `TypeDef::describe`.
+#[derive(Clone, Copy, Debug)]
+pub struct Description {
+    typedef: TypeDef,
+}
+
+impl fmt::Display for Description {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.typedef.with_name(|name| self.fmt_name(name, f))?;
+        if let Some(tag) = self.typedef.tag() {
+            writeln!(f, "tag:        {}", tag)?;
+        }
+        write!(f, "id:         {:#018x}", self.typedef)
+    }
+}
+
+impl Description {
+    fn fmt_name(&self, name: &str, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "name:       {}", name)?;
+        writeln!(f, "short name: {}", format::strip_paths(name))?;
+        if let Some(parts) = format::split_path(name) {
+            if let Some(krate) = parts.krate {
+                writeln!(f, "crate:      {}", krate)?;
+            }
+            if let Some(module) = parts.module {
+                writeln!(f, "module:     {}", module)?;
+            }
+            writeln!(f, "ident:      {}", parts.ident)?;
+        }
+        Ok(())
+    }
+}
+
+/// Marker type that gives tagged typedefs their own `TypeId`.
+#[allow(dead_code)]
+struct Tagged<T: ?Sized, Tag: ?Sized>(PhantomData<T>, PhantomData<Tag>);
+
+/// Provide a custom name for a type, to be used by `TypeDef::of_named`.
+///
+/// With the `nightly` feature `NAME` defaults to the compiler-generated type
+/// name, so it only needs to be overridden when a different name is wanted.
+///
+/// ```
+/// use typedef::{ NamedType };
+///
+/// struct Order;
+///
+/// impl NamedType for Order {
+///     const NAME: &'static str = "Order";
+/// }
+/// ```
+#[cfg(all(feature = "nightly", not(feature = "id-only")))]
+pub trait NamedType: Any {
+    /// The name displayed for the type.
+    const NAME: &'static str = ::core::any::type_name::<Self>();
+}
+
+/// Provide a custom name for a type, to be used by `TypeDef::of_named`.
+///
+/// ```
+/// use typedef::{ NamedType };
+///
+/// struct Order;
+///
+/// impl NamedType for Order {
+///     const NAME: &'static str = "Order";
+/// }
+/// ```
+#[cfg(not(all(feature = "nightly", not(feature = "id-only"))))]
+pub trait NamedType: Any {
+    /// The name displayed for the type.
+    const NAME: &'static str;
+}
+
+/// Create a NameDef structure to print the name of a type that is not
+/// necessarily `'static`.
+///
+/// Unlike `TypeDef`, it stores only the type name, so it can not reliably
+/// identify a type: two different types may share the same name.
+///
+/// This is not available if this crate is compiled with `features = ["id-only"]`
+///
+/// ```
+/// use typedef::{ NameDef };
+///
+/// struct Foo<'a>(&'a str);
+///
+/// fn describe<'a>(_foo: &Foo<'a>) -> NameDef {
+///     NameDef::of::<Foo<'a>>()
+/// }
+///
+/// assert!(describe(&Foo("bar")).name().ends_with("Foo<'_>"));
+/// ```
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg(not(feature = "id-only"))]
+pub struct NameDef {
+    name: &'static str,
+}
+
+#[cfg(not(feature = "id-only"))]
+impl NameDef {
+    nightly_const! {
+        /// Create a NameDef structure from a type parameter.
+        ///
+        /// ```
+        /// use typedef::{ NameDef };
+        ///
+        /// let _namedef = NameDef::of::<&str>();
+        /// ```
+        pub fn of<T: ?Sized>() -> NameDef {
+            NameDef {
+                name: TypeDef::name_of_any_lifetime::<T>(),
+            }
+        }
+    }
+
+    nightly_const! {
+        /// Get the type name for namedef instance.
+        ///
+        /// ```
+        /// use typedef::{ NameDef };
+        ///
+        /// assert_eq!(NameDef::of::<&str>().name(), "&str");
+        /// ```
+        pub fn name(&self) -> &'static str {
+            self.name
+        }
+    }
+
+    /// Check if namedef instance matches the name of a type.
+    ///
+    /// The comparison is name-based, so lifetimes are ignored.
+    ///
+    /// ```
+    /// use typedef::{ NameDef };
+    ///
+    /// fn is_str_ref<'a>(namedef: NameDef) -> bool {
+    ///     namedef.is::<&'a str>()
+    /// }
+    ///
+    /// assert!(is_str_ref(NameDef::of::<&'static str>()));
+    /// ```
+    pub fn is<T: ?Sized>(&self) -> bool {
+        self.name == ::core::any::type_name::<T>()
+    }
+}
+
+#[cfg(not(feature = "id-only"))]
+impl From<TypeDef> for NameDef {
+    #[inline(always)]
+    fn from(typedef: TypeDef) -> NameDef {
+        NameDef {
+            name: typedef.name,
+        }
+    }
+}
+
+/// Compares the name with the compiler-generated name of the typedef.
+#[cfg(not(feature = "id-only"))]
+impl PartialEq<TypeDef> for NameDef {
+    #[inline(always)]
+    fn eq(&self, other: &TypeDef) -> bool {
+        self.name == other.name
+    }
+}
+
+#[cfg(not(feature = "id-only"))]
+impl PartialEq<NameDef> for TypeDef {
+    #[inline(always)]
+    fn eq(&self, other: &NameDef) -> bool {
+        self.name == other.name
+    }
+}
+
+#[cfg(not(feature = "id-only"))]
+impl fmt::Display for NameDef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.name)
+    }
+}
+
+/// Create a FutureName structure to print the name of a future together with
+/// its output type.
+///
+/// Futures of `async fn` and async blocks are compiler-generated types with
+/// unhelpful names, like `my_crate::fetch::{{closure}}`. They are displayed
+/// as `impl Future<Output = u8> (from my_crate::fetch)` instead. Other futures
+/// are displayed by their type name.
+///
+/// This is not available if this crate is compiled with `features = ["id-only"]`
+///
+/// ```edition2018
+/// use typedef::{ FutureName };
+///
+/// async fn fetch() -> u8 {
+///     15
+/// }
+///
+/// let name = FutureName::of_val(&fetch());
+///
+/// assert!(name.is_opaque());
+/// assert!(name.to_string().starts_with("impl Future<Output = u8> (from "));
+/// assert!(name.to_string().ends_with("::fetch)"));
+/// ```
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg(not(feature = "id-only"))]
+pub struct FutureName {
+    name: &'static str,
+    output: &'static str,
+}
+
+#[cfg(not(feature = "id-only"))]
+impl FutureName {
+    /// Create a FutureName structure from the type of a future.
+    pub fn of_val<F: ?Sized + Future>(_future: &F) -> FutureName {
+        FutureName {
+            name: ::core::any::type_name::<F>(),
+            output: ::core::any::type_name::<F::Output>(),
+        }
+    }
+
+    /// Get the type name of the future.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Get the type name of the output of the future.
+    pub fn output(&self) -> &'static str {
+        self.output
+    }
+
+    /// Check if the future is a compiler-generated type, created by an
+    /// `async fn` or an async block.
+    pub fn is_opaque(&self) -> bool {
+        format::generated_origin(self.name).is_some()
+    }
+}
+
+#[cfg(not(feature = "id-only"))]
+impl fmt::Display for FutureName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match format::generated_origin(self.name) {
+            Some(origin) => format::pad(f, |out| write!(out, "impl Future<Output = {}> (from {})", self.output, origin)),
+            None => f.pad(self.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TypeDef;
+
+    #[test]
+    fn should_match_type() {
+        assert!(TypeDef::of::<i16>().is::<i16>());
+    }
+
+    #[test]
+    fn should_not_match_incorrect_type() {
+        assert!(!TypeDef::of::<i16>().is::<i32>());
+    }
+
+    #[test]
+    #[cfg(all(feature = "alloc", feature = "id-only"))]
+    fn should_return_name() {
+        assert_eq!(TypeDef::of::<i16>().name().into_owned(), format!("{:?}", type_id_fallback::<i16>()));
+        assert_eq!(TypeDef::of::<i64>().name().into_owned(), format!("{:?}", type_id_fallback::<i64>()));
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_return_name() {
+        let name: &'static str = TypeDef::of::<i16>().name();
+        assert_eq!(name, "i16");
+        assert_eq!(TypeDef::of::<i64>().name(), "i64");
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    #[cfg(all(feature = "alloc", feature = "id-only"))]
+    fn should_return_type_name() {
+        assert_eq!(TypeDef::of::<i16>().get_str().into_owned(), format!("{:?}", type_id_fallback::<i16>()));
+        assert_eq!(TypeDef::of::<i64>().get_str().into_owned(), format!("{:?}", type_id_fallback::<i64>()));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    #[cfg(all(feature = "alloc", not(feature = "id-only")))]
+    fn should_return_type_name() {
+        assert_eq!(&TypeDef::of::<i16>().get_str(), "i16");
+        assert_eq!(&TypeDef::of::<i64>().get_str(), "i64");
+    }
+
+    #[test]
+    fn should_match_type_of_value() {
+        let slice: &[u8] = &[1, 2, 3];
+
+        assert!(TypeDef::of_val(&15i16).is::<i16>());
+        assert!(TypeDef::of_val(slice).is::<[u8]>());
+        #[cfg(feature = "alloc")]
+        assert_eq!(TypeDef::name_of_val(&15i16), TypeDef::name_of::<i16>());
+    }
+
+    #[test]
+    fn should_match_unsized_types() {
+        trait Foo {}
+
+        assert!(TypeDef::of::<str>().is::<str>());
+        assert!(TypeDef::of::<[u8]>().is::<[u8]>());
+        assert!(TypeDef::of::<dyn Foo>().is::<dyn Foo>());
+        assert!(!TypeDef::of::<[u8]>().is::<[u16]>());
+        assert_eq!(TypeDef::id_of::<str>(), ::std::any::TypeId::of::<str>());
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_return_unsized_type_name() {
+        assert_eq!(TypeDef::of::<str>().name(), "str");
+        assert_eq!(TypeDef::of::<[u8]>().name(), "[u8]");
+        #[cfg(feature = "alloc")]
+        assert_eq!(TypeDef::name_of::<[u8]>(), "[u8]");
+    }
+
+    #[test]
+    fn should_be_equal_to_another_typedef_of_the_same_type() {
+        assert_eq!(TypeDef::of::<i16>(), TypeDef::of::<i16>());
+    }
+
+    #[test]
+    fn should_not_be_equal_to_another_typedef_of_different_type() {
+        assert!(TypeDef::of::<i16>() != TypeDef::of::<i32>());
+    }
+
+    #[test]
+    fn should_be_usable_as_hash_set_key() {
+        use std::collections::HashSet;
+
+        let mut set = HashSet::new();
+        set.insert(TypeDef::of::<i16>());
+        set.insert(TypeDef::of::<i16>());
+        set.insert(TypeDef::of::<i32>());
+
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&TypeDef::of::<i16>()));
+        assert!(!set.contains(&TypeDef::of::<i64>()));
+    }
+
+    #[test]
+    fn should_be_usable_as_hash_map_key() {
+        use std::collections::HashMap;
+
+        let mut map = HashMap::new();
+        map.insert(TypeDef::of::<i16>(), "short");
+        map.insert(TypeDef::of::<i64>(), "long");
+
+        assert_eq!(map.get(&TypeDef::of::<i16>()), Some(&"short"));
+        assert_eq!(map.get(&TypeDef::of::<i64>()), Some(&"long"));
+        assert_eq!(map.get(&TypeDef::of::<i32>()), None);
+    }
+
+    #[test]
+    fn should_hash_type_id() {
+        use core::any::TypeId;
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash<T: Hash>(value: T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        assert_eq!(hash(TypeDef::of::<i16>()), hash(TypeId::of::<i16>()));
+        assert_ne!(hash(TypeDef::of_tagged::<u64, u8>()), hash(TypeDef::of_tagged::<u64, u16>()));
+    }
+
+    #[test]
+    fn should_be_usable_as_btree_set_key() {
+        use std::collections::BTreeSet;
+
+        let mut set = BTreeSet::new();
+        set.insert(TypeDef::of::<i16>());
+        set.insert(TypeDef::of::<i32>());
+        set.insert(TypeDef::of::<i16>());
+
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&TypeDef::of::<i32>()));
+    }
+
+    #[test]
+    fn should_order_consistently_with_equality() {
+        use std::cmp::Ordering;
+
+        let a = TypeDef::of::<i16>();
+        let b = TypeDef::of::<i32>();
+
+        assert_eq!(a.cmp(&a), Ordering::Equal);
+        assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+        assert_eq!(a.partial_cmp(&b), Some(a.cmp(&b)));
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_order_by_name() {
+        let mut types = vec![TypeDef::of::<u8>(), TypeDef::of::<bool>(), TypeDef::of::<i64>()];
+        types.sort();
+
+        assert_eq!(types, vec![TypeDef::of::<bool>(), TypeDef::of::<i64>(), TypeDef::of::<u8>()]);
+    }
+
+    #[test]
+    fn should_be_equal_to_its_name() {
+        let typedef = TypeDef::of::<i16>();
+        let owned = typedef.to_string();
+        let name: &str = &owned;
+
+        assert!(typedef == *name);
+        assert!(typedef == name);
+        assert!(*name == typedef);
+        assert!(name == typedef);
+        assert!(typedef != "not a type name");
+        assert!("not a type name" != typedef);
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_be_referenced_as_str() {
+        let typedef = TypeDef::of::<i16>();
+        let name: &str = typedef.as_ref();
+
+        assert_eq!(name, "i16");
+    }
+
+    #[test]
+    fn should_be_equal_to_its_type_id() {
+        use std::any::TypeId;
+
+        assert!(TypeDef::of::<i16>() == TypeId::of::<i16>());
+        assert!(TypeId::of::<i16>() == TypeDef::of::<i16>());
+        assert!(TypeDef::of::<i16>() != TypeId::of::<i32>());
+        assert!(TypeId::of::<i32>() != TypeDef::of::<i16>());
+    }
+
+    #[test]
+    fn should_convert_to_type_id() {
+        use std::any::TypeId;
+
+        assert_eq!(TypeDef::of::<i16>().id(), TypeId::of::<i16>());
+        assert_eq!(TypeId::from(TypeDef::of::<i16>()), TypeId::of::<i16>());
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_return_name_of_non_static_type() {
+        use super::NameDef;
+
+        fn name_of_ref<'a>(_value: &'a i16) -> NameDef {
+            NameDef::of::<&'a i16>()
+        }
+
+        assert_eq!(TypeDef::name_of_any_lifetime::<&i16>(), "&i16");
+        assert_eq!(name_of_ref(&15).name(), "&i16");
+        assert_eq!(format!("{}", name_of_ref(&15)), "&i16");
+        assert_eq!(NameDef::from(TypeDef::of::<&'static i16>()), name_of_ref(&15));
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_erase_lifetimes() {
+        use super::NameDef;
+
+        struct Foo<'a>(&'a str);
+
+        fn erased<'a>(foo: &Foo<'a>) -> NameDef {
+            assert_eq!(foo.0, "bar");
+            TypeDef::of_erased::<Foo<'a>>()
+        }
+
+        let namedef = erased(&Foo("bar"));
+
+        assert_eq!(namedef, TypeDef::of_erased::<Foo<'static>>());
+        assert!(namedef == TypeDef::of::<Foo<'static>>());
+        assert!(TypeDef::of::<Foo<'static>>() == namedef);
+        assert!(namedef.is::<Foo<'static>>());
+        assert!(!namedef.is::<i16>());
+        assert!(namedef != TypeDef::of::<i16>());
+    }
+
+    struct Order;
+
+    impl super::NamedType for Order {
+        const NAME: &'static str = "Order";
+    }
+
+    #[test]
+    fn should_prefer_custom_name() {
+        let typedef = TypeDef::of_named::<Order>();
+
+        #[cfg(feature = "alloc")]
+        assert_eq!(typedef.name(), "Order");
+        assert_eq!(format!("{}", typedef), "Order");
+        assert!(typedef == "Order");
+        assert!(typedef.is::<Order>());
+        assert_eq!(typedef, TypeDef::of::<Order>());
+    }
+
+    #[test]
+    #[cfg(all(feature = "nightly", not(feature = "id-only")))]
+    fn should_fall_back_to_type_name_for_named_type() {
+        struct Unnamed;
+
+        impl super::NamedType for Unnamed {}
+
+        assert_eq!(TypeDef::of_named::<Unnamed>().name(), TypeDef::of::<Unnamed>().name());
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_hash_and_order_named_type_by_type_name() {
+        use std::collections::HashSet;
+        use std::cmp::Ordering;
+
+        let mut set = HashSet::new();
+        set.insert(TypeDef::of::<Order>());
+
+        assert!(set.contains(&TypeDef::of_named::<Order>()));
+        assert_eq!(TypeDef::of_named::<Order>().cmp(&TypeDef::of::<Order>()), Ordering::Equal);
+    }
+
+    #[cfg(all(feature = "nightly", not(feature = "id-only")))]
+    static I16_TYPE: TypeDef = TypeDef::of::<i16>();
+
+    #[test]
+    #[cfg(all(feature = "nightly", not(feature = "id-only")))]
+    fn should_initialize_static() {
+        const NAME: &str = I16_TYPE.name();
+
+        const { assert!(I16_TYPE.is::<i16>()) };
+        const { assert!(!I16_TYPE.is::<i32>()) };
+        assert_eq!(NAME, "i16");
+        assert_eq!(I16_TYPE, TypeDef::of::<i16>());
+        assert_eq!(I16_TYPE.id(), TypeDef::id_of::<i16>());
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_display_short_name_with_alternate_flag() {
+        let typedef = TypeDef::of::<Vec<String>>();
+
+        assert_eq!(format!("{}", typedef), "alloc::vec::Vec<alloc::string::String>");
+        assert_eq!(format!("{:#}", typedef), "Vec<String>");
+    }
+
+    #[test]
+    fn should_display_custom_name_with_alternate_flag() {
+        assert_eq!(format!("{:#}", TypeDef::of_named::<Order>()), "Order");
+    }
+
+    #[test]
+    fn should_distinguish_tagged_types() {
+        struct UserId;
+        struct OrderId;
+
+        let user_id = TypeDef::of_tagged::<u64, UserId>();
+
+        assert_eq!(user_id, TypeDef::of_tagged::<u64, UserId>());
+        assert!(user_id != TypeDef::of_tagged::<u64, OrderId>());
+        assert!(user_id != TypeDef::of_tagged::<UserId, u64>());
+        assert!(user_id != TypeDef::of::<u64>());
+        assert!(!user_id.is::<u64>());
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_display_tagged_type_name() {
+        struct UserId;
+
+        let user_id = TypeDef::of_tagged::<u64, UserId>();
+
+        assert_eq!(user_id.name(), "u64");
+        assert!(user_id.tag_name().unwrap().ends_with("::UserId"));
+        assert_eq!(format!("{}", user_id), format!("u64 @ {}", user_id.tag_name().unwrap()));
+        assert_eq!(format!("{:#}", user_id), "u64 @ UserId");
+    }
+
+    #[test]
+    fn should_return_raw_id() {
+        assert_eq!(TypeDef::of::<i16>().raw_id(), TypeDef::of::<i16>().raw_id());
+        assert!(TypeDef::of::<i16>().raw_id() != TypeDef::of::<i32>().raw_id());
+        assert!(TypeDef::of::<i16>().raw_id() != TypeDef::of::<u16>().raw_id());
+    }
+
+    #[test]
+    #[cfg(all(feature = "alloc", feature = "id-only"))]
+    fn should_format_raw_id_as_name() {
+        assert_eq!(TypeDef::name_of::<i16>(), TypeDef::of::<i16>().raw_id().to_string());
+    }
+
+    #[test]
+    fn should_keep_raw_id_stable_within_binary() {
+        fn raw_id_in_generic<T: 'static>() -> u64 {
+            TypeDef::of::<T>().raw_id()
+        }
+
+        let raw_id = TypeDef::of::<Vec<i16>>().raw_id();
+
+        assert_eq!(raw_id_in_generic::<Vec<i16>>(), raw_id);
+        assert_eq!(TypeDef::of_val(&vec![1i16]).raw_id(), raw_id);
+        assert_eq!(format!("{:x}", TypeDef::of::<Vec<i16>>()), format!("{:x}", raw_id));
+        assert!(raw_id_in_generic::<Vec<u16>>() != raw_id);
+    }
+
+    #[test]
+    #[cfg(all(feature = "alloc", feature = "id-only"))]
+    fn should_keep_fallback_name_stable_within_binary() {
+        let name = TypeDef::name_of::<Vec<i16>>().into_owned();
+
+        assert_eq!(TypeDef::of::<Vec<i16>>().name(), name);
+        assert_eq!(TypeDef::name_of_val(&vec![1i16]), name);
+        assert_eq!(TypeDef::of::<Vec<i16>>().to_string(), name);
+        assert_eq!(name.parse::<u64>().unwrap(), TypeDef::of::<Vec<i16>>().raw_id());
+        assert!(TypeDef::name_of::<Vec<u16>>() != name);
+    }
+
+    #[test]
+    #[cfg(all(feature = "alloc", not(feature = "id-only")))]
+    fn should_return_short_name() {
+        use std::collections::HashMap;
+
+        assert_eq!(TypeDef::of::<i16>().short_name(), "i16");
+        assert_eq!(TypeDef::of::<HashMap<String, i32>>().short_name(), "HashMap<String, i32>");
+        assert_eq!(TypeDef::of::<Vec<Option<String>>>().short_name(), "Vec<Option<String>>");
+        assert_eq!(TypeDef::of_tagged::<Vec<u8>, Order>().short_name(), "Vec<u8>");
+        assert_eq!(TypeDef::of_named::<Order>().short_name(), "Order");
+    }
+
+    #[test]
+    #[cfg(all(feature = "alloc", not(feature = "id-only")))]
+    fn should_return_name_without_std_paths() {
+        let name = TypeDef::of::<Vec<Option<Order>>>().name_without_std_paths();
+
+        assert_eq!(name, format!("Vec<Option<{}>>", TypeDef::name_of::<Order>()));
+        assert_eq!(TypeDef::of::<String>().name_without_std_paths(), "String");
+    }
+
+    #[test]
+    #[cfg(all(feature = "alloc", not(feature = "id-only")))]
+    fn should_render_bounded_metric_labels() {
+        mod other {
+            pub struct Order;
+        }
+
+        type Long = Vec<Option<Result<Vec<Option<Result<Order, Order>>>, Vec<Option<Result<Order, Order>>>>>>;
+
+        let label = TypeDef::of::<Long>().to_metric_label();
+
+        assert_eq!(label.len(), 64);
+        assert!(label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_'));
+        assert_eq!(label, TypeDef::of::<Long>().to_metric_label());
+        assert!(TypeDef::of::<Order>().to_metric_label().starts_with("Order_"));
+        assert!(TypeDef::of::<Order>().to_metric_label() != TypeDef::of::<other::Order>().to_metric_label());
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_format_with_options() {
+        use super::{NameFormat, PathStyle};
+
+        struct UserId;
+
+        let format = NameFormat::new().paths(PathStyle::Short);
+
+        assert_eq!(TypeDef::of::<Vec<Option<String>>>().format_with(&format).to_string(), "Vec<Option<String>>");
+        assert_eq!(TypeDef::of::<Vec<Option<String>>>().format_with(&NameFormat::default()).to_string(), TypeDef::of::<Vec<Option<String>>>().name());
+        assert_eq!(TypeDef::of_tagged::<Vec<u8>, UserId>().format_with(&format).to_string(), "Vec<u8> @ UserId");
+        assert_eq!(format!("[{:>8}]", TypeDef::of::<Vec<u8>>().format_with(&format.max_depth(0))), "[  Vec<…>]");
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_elide_deeply_nested_generics() {
+        use std::collections::HashMap;
+        use std::sync::{Arc, Mutex};
+        use super::{NameFormat, PathStyle};
+
+        type Deep = Result<Vec<HashMap<u8, Arc<Mutex<i16>>>>, String>;
+
+        let format = NameFormat::new().paths(PathStyle::Short);
+
+        assert_eq!(TypeDef::of::<Deep>().format_with(&format.max_depth(1)).to_string(), "Result<Vec<…>, String>");
+        assert_eq!(TypeDef::of::<Deep>().format_with(&format.max_depth(3)).to_string(), "Result<Vec<HashMap<u8, Arc<…>>>, String>");
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_render_closures_readably() {
+        use super::NameFormat;
+
+        fn typedef_of_val<T: 'static>(_value: &T) -> TypeDef {
+            TypeDef::of::<T>()
+        }
+
+        let closure = typedef_of_val(&|value: i16| value + 1);
+
+        assert!(closure.is_closure());
+        assert!(!TypeDef::of::<i16>().is_closure());
+        assert_eq!(
+            closure.format_with(&NameFormat::new().closures(true)).to_string(),
+            "closure@typedef::test::should_render_closures_readably"
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_detect_opaque_types() {
+        use super::FutureName;
+
+        // `async` is not available in this edition, so the name of a future
+        // returned by `async fn fetch<T>(value: T) -> T` is spelled out.
+        let future = FutureName {
+            name: "my_crate::fetch<i16>::{{closure}}",
+            output: "i16",
+        };
+
+        assert!(TypeDef::of_val(&|| 15).is_opaque());
+        assert!(!TypeDef::of::<i16>().is_opaque());
+        assert!(future.is_opaque());
+        assert_eq!(future.to_string(), "impl Future<Output = i16> (from my_crate::fetch<i16>)");
+        assert!(!FutureName::of_val(&::std::future::ready(15u8)).is_opaque());
+        assert_eq!(FutureName::of_val(&::std::future::ready(15u8)).output(), "u8");
+        assert_eq!(FutureName::of_val(&::std::future::ready(15u8)).to_string(), "core::future::ready::Ready<u8>");
+    }
+
+    #[test]
+    fn should_write_name() {
+        let mut name = String::new();
+        TypeDef::of::<i16>().write_name(&mut name).unwrap();
+        assert_eq!(name, TypeDef::of::<i16>().to_string());
+
+        let mut name = String::new();
+        TypeDef::of_named::<Order>().write_name(&mut name).unwrap();
+        assert_eq!(name, "Order");
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_return_stable_hash() {
+        struct Tag;
+
+        assert_eq!(super::fnv1a64(super::FNV_OFFSET_BASIS, b""), 0xcbf29ce484222325);
+        assert_eq!(super::fnv1a64(super::FNV_OFFSET_BASIS, b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(TypeDef::of::<i16>().stable_hash64(), super::fnv1a64(super::FNV_OFFSET_BASIS, b"i16"));
+        assert_eq!(TypeDef::of_named::<Order>().stable_hash64(), TypeDef::of::<Order>().stable_hash64());
+        assert!(TypeDef::of::<i16>().stable_hash64() != TypeDef::of::<i32>().stable_hash64());
+        assert!(TypeDef::of_tagged::<i16, Tag>().stable_hash64() != TypeDef::of::<i16>().stable_hash64());
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_describe_type() {
+        let typedef = TypeDef::of::<Vec<String>>();
+
+        assert_eq!(
+            format!("{}", typedef.describe()),
+            format!(
+                "name:       alloc::vec::Vec<alloc::string::String>\n\
+                 short name: Vec<String>\n\
+                 crate:      alloc\n\
+                 module:     vec\n\
+                 ident:      Vec\n\
+                 id:         {:#018x}",
+                typedef
+            )
+        );
+    }
+
+    #[test]
+    fn should_describe_type_without_path() {
+        let typedef = TypeDef::of_named::<Order>();
+
+        assert_eq!(
+            format!("{}", typedef.describe()),
+            format!("name:       Order\nshort name: Order\nident:      Order\nid:         {:#018x}", typedef)
+        );
+    }
+
+    #[test]
+    fn should_pad_displayed_name() {
+        let typedef = TypeDef::of_named::<Order>();
+
+        assert_eq!(format!("[{:>8}]", typedef), "[   Order]");
+        assert_eq!(format!("[{:<8}]", typedef), "[Order   ]");
+        assert_eq!(format!("[{:*^9}]", typedef), "[**Order**]");
+        assert_eq!(format!("[{:#>8}]", typedef), "[###Order]");
+        assert_eq!(format!("[{:.3}]", typedef), "[Ord]");
+        assert_eq!(format!("[{:>5.3}]", typedef), "[  Ord]");
+        assert_eq!(format!("[{:3}]", typedef), "[Order]");
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_pad_displayed_short_and_tagged_name() {
+        struct UserId;
+
+        assert_eq!(format!("[{:>#10}]", TypeDef::of::<Vec<u8>>()), "[   Vec<u8>]");
+        assert_eq!(format!("[{:<#14}]", TypeDef::of_tagged::<u64, UserId>()), "[u64 @ UserId  ]");
+        assert_eq!(format!("[{:>4}]", super::NameDef::of::<u8>()), "[  u8]");
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_join_short_and_tagged_names() {
+        struct UserId;
+
+        let types = [TypeDef::of::<u64>(), TypeDef::of_tagged::<u64, UserId>(), TypeDef::of::<Option<u8>>()];
+
+        assert_eq!(format!("{:#}", super::TypeDefList::new(&types)), "u64, u64 @ UserId, Option<u8>");
+        assert_eq!(format!("{}", super::TypeDefList::new(&types[2..]).separator(" | ")), "core::option::Option<u8>");
+        assert_eq!(format!("{}", super::TypeDefList::new(&[])), "");
+    }
+
+    #[test]
+    fn should_look_up_types_of_static_registry() {
+        use core::any::TypeId;
+
+        let types = [TypeDef::of::<u8>(), TypeDef::of_named::<Order>()];
+        let registry = super::StaticRegistry::new(&types);
+
+        assert_eq!(registry.lookup(TypeId::of::<Order>()), Some(TypeDef::of_named::<Order>()));
+        assert_eq!(registry.lookup(TypeId::of::<u16>()), None);
+        assert_eq!(registry.iter().collect::<Vec<_>>(), types);
+        assert!(super::StaticRegistry::new(&[]).lookup(TypeId::of::<u8>()).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "nightly")]
+    fn should_look_up_types_of_const_static_registry() {
+        use core::any::TypeId;
+
+        static TYPES: [TypeDef; 2] = [TypeDef::of::<u8>(), TypeDef::of_named::<Order>()];
+        static REGISTRY: super::StaticRegistry<'static> = super::StaticRegistry::new(&TYPES);
+
+        assert_eq!(REGISTRY.lookup(TypeId::of::<Order>()), Some(TypeDef::of_named::<Order>()));
+        #[cfg(not(feature = "id-only"))]
+        assert_eq!(REGISTRY.lookup_name("Order"), Some(TypeDef::of_named::<Order>()));
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_split_name_into_crate_module_and_ident() {
+        let typedef = TypeDef::of_named::<Order>();
+
+        assert_eq!(typedef.crate_name(), None);
+        assert_eq!(typedef.module_path(), None);
+        assert_eq!(typedef.ident(), Some("Order"));
+
+        let typedef = TypeDef::of::<String>();
+
+        assert_eq!(typedef.crate_name(), Some("alloc"));
+        assert_eq!(typedef.module_path(), Some("string"));
+        assert_eq!(typedef.ident(), Some("String"));
+
+        let typedef = TypeDef::of::<(u8, i8)>();
+
+        assert_eq!(typedef.crate_name(), None);
+        assert_eq!(typedef.ident(), None);
+    }
+
+    #[test]
+    #[cfg(all(feature = "alloc", not(feature = "id-only")))]
+    fn should_iterate_over_path_segments() {
+        let typedef = TypeDef::of::<Option<Vec<u8>>>();
+        let mut segments = typedef.segments();
+
+        assert_eq!(segments.next(), Some("core"));
+        assert_eq!(segments.next_back(), Some("Option"));
+        assert_eq!(segments.next_back(), Some("option"));
+        assert_eq!(segments.next(), None);
+        assert_eq!(segments.next_back(), None);
+
+        assert_eq!(TypeDef::of_named::<Order>().segments().collect::<Vec<_>>(), ["Order"]);
+        assert_eq!(TypeDef::of::<u8>().segments().collect::<Vec<_>>(), ["u8"]);
+        assert_eq!(TypeDef::of::<[String; 2]>().segments().count(), 0);
+    }
+
+    #[test]
+    #[cfg(all(feature = "alloc", not(feature = "id-only")))]
+    fn should_count_generic_arguments() {
+        struct Fixed<T, const N: usize>([T; N]);
+
+        assert_eq!(TypeDef::of::<Fixed<u8, 4>>().generic_arity(), 2);
+        assert_eq!(TypeDef::of::<Option<&'static str>>().generic_arity(), 1);
+        assert_eq!(TypeDef::of::<Box<dyn Fn(u8) -> u8>>().generic_arity(), 1);
+        assert_eq!(TypeDef::of::<&Vec<u8>>().generic_arity(), 0);
+        assert_eq!(TypeDef::of::<fn(Vec<u8>)>().generic_arity(), 0);
+        assert!(!TypeDef::of_named::<Order>().is_generic());
+    }
+
+    #[test]
+    fn should_record_creation_site() {
+        let line = line!() + 1;
+        let typedef = TypeDef::of_traced::<i16>();
+        let location = typedef.location().unwrap();
+
+        assert_eq!(location.file(), file!());
+        assert_eq!(location.line(), line);
+        assert!(format!("{:?}", typedef).contains(file!()));
+        assert_eq!(typedef, TypeDef::of::<i16>());
+        assert!(TypeDef::of::<i16>().location().is_none());
+    }
+
+    #[test]
+    fn should_format_id_as_hex() {
+        let lower = format!("{:x}", TypeDef::of::<i16>());
+        let upper = format!("{:X}", TypeDef::of::<i16>());
+
+        assert!(!lower.is_empty());
+        assert!(lower.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+        assert_eq!(upper, lower.to_uppercase());
+        assert_eq!(lower, format!("{:x}", TypeDef::of::<i16>()));
+        assert!(lower != format!("{:x}", TypeDef::of::<i32>()));
+        assert_eq!(format!("{:#x}", TypeDef::of::<i16>()), format!("0x{}", lower));
+    }
+
+    #[cfg(all(feature = "alloc", feature = "id-only"))]
+    fn type_id_fallback<T: 'static>() -> u64 {
+        TypeDef::of::<T>().raw_id()
     }
 }
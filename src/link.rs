@@ -0,0 +1,241 @@
+//! Typedefs collected by the linker.
+//!
+//! With `features = ["link"]`, `register_type!` places the types in a
+//! section of the executable instead of registering each of them in a
+//! constructor, and the linker collects the sections of all crates into one
+//! list, which `types` goes over. With the `std` feature, the types of the
+//! list are added to the `registry` before `main` runs.
+//!
+//! The list is a static slice, so it is also available without allocation,
+//! and on targets that do not run constructors. `lookup` and `lookup_name`
+//! scan it like `StaticRegistry` does, without locks.
+//!
+//! The section is only supported on targets that use ELF, where the linker
+//! defines the `__start_` and `__stop_` symbols of the section: Linux,
+//! Android, the BSDs, and bare-metal targets whose linker script keeps
+//! sections it does not know. The entries are marked as used, so the linker
+//! keeps them with `--gc-sections` and `-z start-stop-gc`.
+//!
+//! ```
+//! #[macro_use]
+//! extern crate typedef;
+//!
+//! use typedef::{ link, TypeDef };
+//!
+//! struct Sensor;
+//!
+//! register_type!(Sensor);
+//!
+//! fn main() {
+//!     assert!(link::types().any(|typedef| typedef.is::<Sensor>()));
+//!     assert_eq!(link::lookup(TypeDef::id_of::<Sensor>()), Some(TypeDef::of::<Sensor>()));
+//! }
+//! ```
+
+use core::any::TypeId;
+use core::{fmt, mem, slice};
+
+use TypeDef;
+
+#[cfg(not(any(
+    target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "netbsd",
+    target_os = "openbsd", target_os = "dragonfly", target_os = "none",
+)))]
+compile_error!("`features = [\"link\"]` is only supported on targets that use ELF");
+
+/// Entry of the section that `register_type!` places typedefs in.
+///
+/// The entry holds a function that creates the typedef, so that entries can
+/// be created in `const` context without `features = ["nightly"]`.
+#[derive(Clone, Copy)]
+pub struct LinkedType {
+    typedef: fn() -> TypeDef,
+}
+
+impl LinkedType {
+    /// Create an entry with the function that creates the typedef.
+    pub const fn new(typedef: fn() -> TypeDef) -> LinkedType {
+        LinkedType { typedef }
+    }
+
+    /// Create the typedef of the entry.
+    pub fn typedef(&self) -> TypeDef {
+        (self.typedef)()
+    }
+}
+
+impl fmt::Debug for LinkedType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("LinkedType").field(&self.typedef()).finish()
+    }
+}
+
+/// Get the entries placed in the section by `register_type!`, in the order
+/// the linker put them in.
+pub fn entries() -> &'static [LinkedType] {
+    let (start, stop) = section::bounds();
+    let len = (stop as usize - start as usize) / mem::size_of::<LinkedType>();
+    // The linker places the entries of all crates between the bounds, and
+    // all of them are initialized statics of the same type.
+    unsafe { slice::from_raw_parts(start, len) }
+}
+
+/// Iterate over the typedefs placed in the section by `register_type!`.
+pub fn types() -> Types {
+    Types {
+        entries: entries().iter(),
+    }
+}
+
+/// Find the typedef of the type among the typedefs placed in the section.
+pub fn lookup(id: TypeId) -> Option<TypeDef> {
+    types().find(|typedef| typedef.id() == id)
+}
+
+/// Find the typedef by the name returned by `TypeDef::name` among the
+/// typedefs placed in the section.
+///
+/// Names are compared as they are, as `StaticRegistry::lookup_name` does.
+///
+/// This is not available if this crate is compiled with `features = ["id-only"]`.
+#[cfg(not(feature = "id-only"))]
+pub fn lookup_name(name: &str) -> Option<TypeDef> {
+    types().find(|typedef| typedef.name() == name)
+}
+
+/// Iterator over the typedefs collected by the linker, created by `types`.
+#[derive(Clone, Debug)]
+pub struct Types {
+    entries: slice::Iter<'static, LinkedType>,
+}
+
+impl Iterator for Types {
+    type Item = TypeDef;
+
+    fn next(&mut self) -> Option<TypeDef> {
+        self.entries.next().map(LinkedType::typedef)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Types {}
+
+/// Bounds of the section, which the linker marks with the `__start_` and
+/// `__stop_` symbols of sections named like identifiers.
+mod section {
+    use super::LinkedType;
+
+    extern "C" {
+        #[link_name = "__start_typedef_types"]
+        static START: u8;
+        #[link_name = "__stop_typedef_types"]
+        static STOP: u8;
+    }
+
+    // Keeps the section in the executable when no types are placed in it,
+    // so that the linker defines the bounds.
+    #[used]
+    #[link_section = "typedef_types"]
+    static EMPTY: [LinkedType; 0] = [];
+
+    pub fn bounds() -> (*const LinkedType, *const LinkedType) {
+        unsafe { (&START as *const u8 as *const LinkedType, &STOP as *const u8 as *const LinkedType) }
+    }
+}
+
+/// Register types by placing them in a section that the linker collects.
+///
+/// With `features = ["link"]` the macro places the typedefs in a section of
+/// the executable, which `link::types` goes over. With the `std` feature,
+/// the typedefs are added to the registry with `registry::register_typedef`
+/// before `main` runs, by one constructor for all of them. It is used at the
+/// module level.
+///
+/// This is only available if this crate is compiled with
+/// `features = ["link"]`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate typedef;
+///
+/// # #[cfg(feature = "std")]
+/// use typedef::{ registry, TypeDef };
+///
+/// mod my_crate {
+///     pub struct Foo;
+///     pub struct Bar;
+/// }
+///
+/// register_type!(my_crate::Foo, my_crate::Bar);
+///
+/// # #[cfg(feature = "std")]
+/// fn main() {
+///     assert!(registry::lookup(TypeDef::id_of::<my_crate::Foo>()).is_some());
+///     assert!(registry::lookup(TypeDef::id_of::<my_crate::Bar>()).is_some());
+/// }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() {}
+/// ```
+#[cfg(feature = "link")]
+#[macro_export]
+macro_rules! register_type {
+    (@typedefs $($typedef:expr),+) => {
+        $(
+            const _: () = {
+                #[used]
+                #[link_section = "typedef_types"]
+                static TYPE: $crate::link::LinkedType = $crate::link::LinkedType::new(|| $typedef);
+            };
+        )+
+    };
+    ($($ty:ty),+ $(,)*) => {
+        $crate::register_type!(@typedefs $($crate::TypeDef::of_traced::<$ty>()),+);
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::{entries, lookup, types};
+    use core::any::TypeId;
+
+    struct Linked;
+
+    register_type!(Linked, [Linked; 2]);
+
+    #[test]
+    fn should_collect_registered_types() {
+        assert!(types().any(|typedef| typedef.is::<Linked>()));
+        assert!(types().any(|typedef| typedef.is::<[Linked; 2]>()));
+        assert_eq!(types().len(), entries().len());
+    }
+
+    #[test]
+    fn should_not_collect_other_types() {
+        assert!(!types().any(|typedef| typedef.is::<[Linked; 3]>()));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn should_register_collected_types() {
+        assert!(::registry::lookup(TypeId::of::<Linked>()).is_some());
+        assert!(::registry::lookup(TypeId::of::<[Linked; 2]>()).is_some());
+    }
+
+    #[test]
+    fn should_look_up_collected_types() {
+        assert!(lookup(TypeId::of::<Linked>()).unwrap().is::<Linked>());
+        assert_eq!(lookup(TypeId::of::<[Linked; 3]>()), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_look_up_collected_types_by_name() {
+        let typedef = lookup(TypeId::of::<[Linked; 2]>()).unwrap();
+
+        assert_eq!(super::lookup_name(typedef.name()), Some(typedef));
+        assert_eq!(super::lookup_name("Linked"), None);
+    }
+}
@@ -0,0 +1,256 @@
+//! Symbol mangling of type names.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+use format;
+use typename::{self, GenericArg, Signature, TypeName};
+
+/// Encode a type name with the Rust v0 symbol mangling scheme.
+///
+/// Returns `None` for types that can not be encoded from the name alone,
+/// such as closures, `impl Trait` types, const generic arguments and
+/// identifiers that are not ASCII.
+pub fn v0(name: &str) -> Option<String> {
+    let mut out = String::new();
+    ty(&typename::parse(name)?, &mut out)?;
+    Some(out)
+}
+
+fn ty(ty: &TypeName, out: &mut String) -> Option<()> {
+    match *ty {
+        TypeName::Path { path, ref args, ref signature } => {
+            if let Some(basic) = basic_type(path) {
+                out.push(basic);
+                return Some(());
+            }
+            // The type of a const argument is not known from the name.
+            if args.iter().any(|arg| matches!(*arg, GenericArg::Binding(..) | GenericArg::Const(_))) {
+                return None;
+            }
+            generic_path(path, args, signature.as_ref(), out)?;
+        },
+        TypeName::Ref { mutable, ref inner, .. } => {
+            out.push(if mutable { 'Q' } else { 'R' });
+            self::ty(inner, out)?;
+        },
+        TypeName::Ptr { mutable, ref inner } => {
+            out.push(if mutable { 'O' } else { 'P' });
+            self::ty(inner, out)?;
+        },
+        TypeName::Slice(ref inner) => {
+            out.push('S');
+            self::ty(inner, out)?;
+        },
+        TypeName::Array(ref inner, len) => {
+            out.push('A');
+            self::ty(inner, out)?;
+            out.push('j');
+            const_data(len.parse().ok()?, out);
+        },
+        TypeName::Tuple(ref items) if items.is_empty() => out.push('u'),
+        TypeName::Tuple(ref items) => {
+            out.push('T');
+            for item in items {
+                self::ty(item, out)?;
+            }
+            out.push('E');
+        },
+        TypeName::Fn(ref signature) => {
+            out.push('F');
+            if signature.unsafety {
+                out.push('U');
+            }
+            match signature.abi {
+                Some("C") => out.push_str("KC"),
+                Some(abi) => {
+                    out.push('K');
+                    identifier(&abi.replace('-', "_"), out)?;
+                },
+                None => {},
+            }
+            for arg in &signature.args {
+                self::ty(arg, out)?;
+            }
+            if signature.variadic {
+                out.push('v');
+            }
+            out.push('E');
+            ret(signature, out)?;
+        },
+        TypeName::Dyn(ref bounds) => {
+            out.push('D');
+            for bound in &bounds.traits {
+                match *bound {
+                    TypeName::Path { path, ref args, ref signature } => {
+                        generic_path(path, args, signature.as_ref(), out)?;
+                        for arg in args {
+                            if let GenericArg::Binding(name, ref binding) = *arg {
+                                out.push('p');
+                                identifier(name, out)?;
+                                self::ty(binding, out)?;
+                            }
+                        }
+                        if let Some(signature) = signature {
+                            out.push('p');
+                            identifier("Output", out)?;
+                            ret(signature, out)?;
+                        }
+                    },
+                    _ => return None,
+                }
+            }
+            // Lifetimes are erased in type names.
+            out.push_str("EL_");
+        },
+        TypeName::Never => out.push('z'),
+        TypeName::Impl(_) | TypeName::Other(_) => return None,
+    }
+    Some(())
+}
+
+/// Encode a path with its generic type arguments. The arguments of `Fn`
+/// signatures are encoded as a tuple, as in `Fn<(u8,)>`.
+fn generic_path(path: &str, args: &[GenericArg], signature: Option<&Signature>, out: &mut String) -> Option<()> {
+    let is_generic = signature.is_some() || args.iter().any(|arg| matches!(*arg, GenericArg::Type(_)));
+    if is_generic {
+        out.push('I');
+    }
+
+    let mut segments = path.split("::").map(format::strip_disambiguator);
+    let krate = segments.next()?;
+    for _ in segments.clone() {
+        out.push_str("Nt");
+    }
+    out.push('C');
+    identifier(krate, out)?;
+    for segment in segments {
+        identifier(segment, out)?;
+    }
+
+    if !is_generic {
+        return Some(());
+    }
+    for arg in args {
+        match *arg {
+            GenericArg::Type(ref arg) => ty(arg, out)?,
+            GenericArg::Const(_) => return None,
+            GenericArg::Binding(..) | GenericArg::Lifetime(_) => {},
+        }
+    }
+    if let Some(signature) = signature {
+        out.push('T');
+        for arg in &signature.args {
+            ty(arg, out)?;
+        }
+        out.push('E');
+    }
+    out.push('E');
+    Some(())
+}
+
+fn ret(signature: &Signature, out: &mut String) -> Option<()> {
+    match signature.ret {
+        Some(ref ret) => ty(ret, out),
+        None => {
+            out.push('u');
+            Some(())
+        },
+    }
+}
+
+fn identifier(identifier: &str, out: &mut String) -> Option<()> {
+    if identifier.is_empty() || !identifier.is_ascii() || identifier.contains(['{', '}']) {
+        return None;
+    }
+    let _ = write!(out, "{}", identifier.len());
+    if identifier.starts_with(|c: char| c.is_ascii_digit() || c == '_') {
+        out.push('_');
+    }
+    out.push_str(identifier);
+    Some(())
+}
+
+fn const_data(value: u64, out: &mut String) {
+    if value != 0 {
+        let _ = write!(out, "{:x}", value);
+    }
+    out.push('_');
+}
+
+fn basic_type(name: &str) -> Option<char> {
+    Some(match name {
+        "i8" => 'a',
+        "bool" => 'b',
+        "char" => 'c',
+        "f64" => 'd',
+        "str" => 'e',
+        "f32" => 'f',
+        "u8" => 'h',
+        "isize" => 'i',
+        "usize" => 'j',
+        "i32" => 'l',
+        "u32" => 'm',
+        "i128" => 'n',
+        "u128" => 'o',
+        "i16" => 's',
+        "u16" => 't',
+        "i64" => 'x',
+        "u64" => 'y',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::v0;
+
+    #[test]
+    fn should_mangle_basic_types() {
+        assert_eq!(v0("u8").unwrap(), "h");
+        assert_eq!(v0("&str").unwrap(), "Re");
+        assert_eq!(v0("&mut [u8]").unwrap(), "QSh");
+        assert_eq!(v0("*const [u8; 16]").unwrap(), "PAhj10_");
+        assert_eq!(v0("[u8; 0]").unwrap(), "Ahj_");
+        assert_eq!(v0("()").unwrap(), "u");
+        assert_eq!(v0("(u8, bool)").unwrap(), "ThbE");
+        assert_eq!(v0("!").unwrap(), "z");
+    }
+
+    #[test]
+    fn should_mangle_paths() {
+        assert_eq!(v0("alloc::string::String").unwrap(), "NtNtC5alloc6string6String");
+        assert_eq!(v0("alloc::vec::Vec<u8>").unwrap(), "INtNtC5alloc3vec3VechE");
+        assert_eq!(
+            v0("core::option::Option<alloc::vec::Vec<u8>>").unwrap(),
+            "INtNtC4core6option6OptionINtNtC5alloc3vec3VechEE"
+        );
+        assert_eq!(v0("my_crate[4f2a]::_Foo").unwrap(), "NtC8my_crate4__Foo");
+    }
+
+    #[test]
+    fn should_mangle_functions_and_trait_objects() {
+        assert_eq!(v0("fn(u8) -> u16").unwrap(), "FhEt");
+        assert_eq!(v0("unsafe extern \"C\" fn()").unwrap(), "FUKCEu");
+        assert_eq!(v0("unsafe extern \"C\" fn(i32, ...)").unwrap(), "FUKClvEu");
+        assert_eq!(v0("extern \"efiapi\" fn()").unwrap(), "FK6efiapiEu");
+        assert_eq!(v0("dyn core::marker::Send").unwrap(), "DNtNtC4core6marker4SendEL_");
+        assert_eq!(
+            v0("dyn core::ops::function::Fn(u8) -> bool + core::marker::Send").unwrap(),
+            "DINtNtNtC4core3ops8function2FnThEEp6OutputbNtNtC4core6marker4SendEL_"
+        );
+        assert_eq!(
+            v0("dyn core::future::future::Future<Output = u8>").unwrap(),
+            "DNtNtNtC4core6future6future6Futurep6OutputhEL_"
+        );
+    }
+
+    #[test]
+    fn should_not_mangle_unsupported_types() {
+        assert_eq!(v0("my_crate::main::{{closure}}"), None);
+        assert_eq!(v0("impl core::marker::Send"), None);
+        assert_eq!(v0("my_crate::Foo<3>"), None);
+        assert_eq!(v0("my_crate::Größe"), None);
+        assert_eq!(v0("Vec<u8"), None);
+    }
+}
@@ -0,0 +1,288 @@
+//! Matching of type names against patterns.
+
+use alloc::vec::Vec;
+
+use format;
+use typename::{self, GenericArg, Signature, TypeName};
+use TypeDef;
+
+/// Pattern that selects a family of types by name, as `Vec<_>`.
+///
+/// Patterns are written as type names, with these additions:
+///
+/// - `_` matches any type or generic argument, and `[T; _]` matches arrays
+///   of any length;
+/// - `..` matches the rest of generic arguments, tuple elements, function
+///   arguments or trait bounds, as in `HashMap<String, ..>`, `(u8, ..)` or
+///   `dyn Error + ..`.
+///
+/// Paths in the pattern match paths that end with the same segments, so
+/// `Vec`, `vec::Vec` and `alloc::vec::Vec` all match `alloc::vec::Vec`.
+/// Paths into `std`, `core` and `alloc` are compared by their public path
+/// in `std`, as in `canonicalize`, so `std::collections::HashMap` matches
+/// `std::collections::hash::map::HashMap`. A path without generic arguments
+/// only matches types without generic arguments; use `Vec<..>` to match all
+/// vectors. Lifetimes and crate disambiguators are not compared.
+///
+/// This is not available if this crate is compiled without the `alloc` feature.
+///
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
+/// use typedef::{ TypeDef, TypePattern };
+///
+/// let pattern = TypePattern::new("Option<Vec<_>>").unwrap();
+///
+/// assert!(pattern.matches(&TypeDef::of::<Option<Vec<u8>>>()));
+/// assert!(pattern.matches(&TypeDef::of::<Option<Vec<String>>>()));
+/// assert!(!pattern.matches(&TypeDef::of::<Option<u8>>()));
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypePattern<'a> {
+    pattern: TypeName<'a>,
+}
+
+impl<'a> TypePattern<'a> {
+    /// Compile a pattern, returning `None` if it is not a valid pattern.
+    pub fn new(pattern: &'a str) -> Option<TypePattern<'a>> {
+        typename::parse_pattern(pattern).map(|pattern| TypePattern { pattern })
+    }
+
+    /// Check if the name of a type matches the pattern.
+    pub fn matches(&self, typedef: &TypeDef) -> bool {
+        typedef.with_name(|name| self.matches_name(name))
+    }
+
+    /// Check if a type name matches the pattern.
+    pub fn matches_name(&self, name: &str) -> bool {
+        typename::parse(name).is_some_and(|name| ty(&self.pattern, &name))
+    }
+}
+
+/// Check if a name matches a glob pattern, where `*` matches any sequence of
+/// characters and `?` matches any one character.
+pub fn matches_glob(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut g, mut n) = (0, 0);
+    // Position after the last `*` and the position in the name it matched up to.
+    let mut star = None;
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                g += 1;
+                star = Some((g, n));
+            },
+            Some(&c) if c == '?' || c == name[n] => {
+                g += 1;
+                n += 1;
+            },
+            _ => match star {
+                Some((star_g, star_n)) => {
+                    g = star_g;
+                    n = star_n + 1;
+                    star = Some((star_g, star_n + 1));
+                },
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+fn ty(pattern: &TypeName, name: &TypeName) -> bool {
+    if is_any(pattern) {
+        return true;
+    }
+    match (pattern, name) {
+        (
+            TypeName::Path { path, args, signature },
+            TypeName::Path { path: name_path, args: name_args, signature: name_signature },
+        ) => {
+            let signatures_match = match (signature, name_signature) {
+                (Some(pattern), Some(name)) => self::signature(pattern, name),
+                (None, None) => true,
+                _ => false,
+            };
+            same_path(path, name_path) && generic_args(args, name_args) && signatures_match
+        },
+        (TypeName::Ref { mutable, inner, .. }, TypeName::Ref { mutable: name_mutable, inner: name_inner, .. })
+        | (TypeName::Ptr { mutable, inner }, TypeName::Ptr { mutable: name_mutable, inner: name_inner }) => {
+            mutable == name_mutable && ty(inner, name_inner)
+        },
+        (TypeName::Slice(pattern), TypeName::Slice(name)) => ty(pattern, name),
+        (TypeName::Array(pattern, len), TypeName::Array(name, name_len)) => {
+            (*len == "_" || len == name_len) && ty(pattern, name)
+        },
+        (TypeName::Tuple(pattern), TypeName::Tuple(name)) => list(pattern, name, is_rest, ty),
+        (TypeName::Fn(pattern), TypeName::Fn(name)) => {
+            pattern.unsafety == name.unsafety && pattern.abi == name.abi && signature(pattern, name)
+        },
+        (TypeName::Dyn(pattern), TypeName::Dyn(name)) | (TypeName::Impl(pattern), TypeName::Impl(name)) => {
+            list(&pattern.traits, &name.traits, is_rest, ty)
+        },
+        (TypeName::Never, TypeName::Never) => true,
+        (TypeName::Other(pattern), TypeName::Other(name)) => pattern == name,
+        _ => false,
+    }
+}
+
+fn generic_args(pattern: &[GenericArg], name: &[GenericArg]) -> bool {
+    let is_type_or_const = |arg: &&GenericArg| !matches!(**arg, GenericArg::Lifetime(_));
+    let pattern: Vec<&GenericArg> = pattern.iter().filter(is_type_or_const).collect();
+    let name: Vec<&GenericArg> = name.iter().filter(is_type_or_const).collect();
+    list(&pattern, &name, |arg| matches!(**arg, GenericArg::Type(ref arg) if is_rest(arg)), |pattern, name| {
+        match (*pattern, *name) {
+            (GenericArg::Type(pattern), _) if is_any(pattern) => true,
+            (GenericArg::Type(pattern), GenericArg::Type(name)) => ty(pattern, name),
+            (GenericArg::Binding(pattern_name, pattern), GenericArg::Binding(name, ty_name)) => {
+                pattern_name == name && ty(pattern, ty_name)
+            },
+            (GenericArg::Const(pattern), GenericArg::Const(name)) => pattern == name,
+            _ => false,
+        }
+    })
+}
+
+/// Compare arguments and return types, where a missing return type is `()`.
+fn signature(pattern: &Signature, name: &Signature) -> bool {
+    let unit = TypeName::Tuple(Vec::new());
+    pattern.variadic == name.variadic
+        && list(&pattern.args, &name.args, is_rest, ty)
+        && ty(pattern.ret().unwrap_or(&unit), name.ret().unwrap_or(&unit))
+}
+
+/// Match items one by one, where `..` in the pattern matches any number of
+/// items.
+fn list<T, R, M>(pattern: &[T], name: &[T], is_rest: R, item: M) -> bool
+    where R: Fn(&T) -> bool + Copy, M: Fn(&T, &T) -> bool + Copy
+{
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((first, pattern)) if is_rest(first) => (0..=name.len()).any(|skip| list(pattern, &name[skip..], is_rest, item)),
+        Some((first, pattern)) => match name.split_first() {
+            Some((name_first, name)) => item(first, name_first) && list(pattern, name, is_rest, item),
+            None => false,
+        },
+    }
+}
+
+fn is_any(pattern: &TypeName) -> bool {
+    matches!(*pattern, TypeName::Path { path: "_", ref args, signature: None } if args.is_empty())
+}
+
+fn is_rest(pattern: &TypeName) -> bool {
+    *pattern == TypeName::Other("..")
+}
+
+/// Check if the `name` path ends with the segments of the `pattern` path.
+fn same_path(pattern: &str, name: &str) -> bool {
+    let pattern = public_segments(pattern);
+    let name = public_segments(name);
+    name.ends_with(&pattern)
+}
+
+/// Get the segments of a path without crate disambiguators, with paths into
+/// the standard library rewritten to the public path in `std`.
+fn public_segments(path: &str) -> Vec<&str> {
+    let mut segments: Vec<&str> = path.split("::").map(format::strip_disambiguator).collect();
+    if segments.len() > 2 && matches!(segments[0], "std" | "core" | "alloc") {
+        let item = segments[segments.len() - 1];
+        let modules = &path[path.find("::").unwrap_or(0) + 2..path.rfind("::").unwrap_or(0)];
+        segments.truncate(1);
+        segments[0] = "std";
+        segments.extend(format::std_public_modules(modules, item).split("::"));
+        segments.push(item);
+    } else if segments.len() == 2 && matches!(segments[0], "core" | "alloc") {
+        segments[0] = "std";
+    }
+    segments
+}
+
+#[cfg(test)]
+mod test {
+    use super::{matches_glob, TypePattern};
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        TypePattern::new(pattern).unwrap().matches_name(name)
+    }
+
+    #[test]
+    fn should_match_wildcards() {
+        assert!(matches("_", "u8"));
+        assert!(matches("_", "&alloc::vec::Vec<u8>"));
+        assert!(matches("Vec<_>", "alloc::vec::Vec<u8>"));
+        assert!(matches("Vec<_>", "alloc::vec::Vec<alloc::vec::Vec<u8>>"));
+        assert!(!matches("Vec<_>", "alloc::vec::Vec<u8, my_crate::Alloc>"));
+        assert!(matches("my_crate::Buffer<_>", "my_crate::Buffer<16>"));
+        assert!(matches("[_; _]", "[u8; 4]"));
+        assert!(matches("[u8; _]", "[u8; 16]"));
+        assert!(!matches("[u8; 4]", "[u8; 16]"));
+        assert!(!matches("[u8; _]", "[u8]"));
+    }
+
+    #[test]
+    fn should_match_the_rest_of_lists() {
+        let name = "std::collections::hash::map::HashMap<alloc::string::String, u8>";
+
+        assert!(matches("HashMap<..>", name));
+        assert!(matches("HashMap<String, ..>", name));
+        assert!(matches("HashMap<String, u8, ..>", name));
+        assert!(matches("HashMap<.., u8>", name));
+        assert!(!matches("HashMap<u8, ..>", name));
+        assert!(!matches("HashMap", name));
+        assert!(matches("(u8, ..)", "(u8, i8, i16)"));
+        assert!(matches("(..)", "()"));
+        assert!(!matches("(.., u8)", "(u8, i8)"));
+        assert!(matches("fn(u8, ..) -> _", "fn(u8, u16) -> u32"));
+        assert!(!matches("unsafe extern \"C\" fn(..)", "unsafe extern \"C\" fn(i32, ...)"));
+        assert!(matches("unsafe extern \"C\" fn(i32, ...)", "unsafe extern \"C\" fn(i32, ...)"));
+    }
+
+    #[test]
+    fn should_match_paths_by_suffix_and_public_path() {
+        assert!(matches("alloc::string::String", "alloc::string::String"));
+        assert!(matches("string::String", "alloc::string::String"));
+        assert!(matches("std::string::String", "alloc::string::String"));
+        assert!(!matches("core::string::String", "my_crate::string::String"));
+        assert!(matches("std::collections::HashMap<..>", "std::collections::hash::map::HashMap<u8, u8>"));
+        assert!(matches("std::sync::Mutex<_>", "std::sync::poison::mutex::Mutex<u8>"));
+        assert!(matches("my_crate::Foo", "my_crate[4f2a]::Foo"));
+        assert!(!matches("Foo", "my_crate::FooBar"));
+        assert!(!matches("other::Foo", "my_crate::Foo"));
+    }
+
+    #[test]
+    fn should_match_references_traits_and_lifetimes() {
+        assert!(matches("&str", "&'static str"));
+        assert!(!matches("&str", "&mut str"));
+        assert!(matches("*const _", "*const u8"));
+        assert!(matches("Cow<str>", "alloc::borrow::Cow<'_, str>"));
+        assert!(matches("Box<dyn Error + ..>", "alloc::boxed::Box<dyn core::error::Error + core::marker::Send + core::marker::Sync>"));
+        assert!(!matches("Box<dyn Error>", "alloc::boxed::Box<dyn core::error::Error + core::marker::Send>"));
+        assert!(matches("Box<dyn Fn(_) -> bool>", "alloc::boxed::Box<dyn core::ops::function::Fn(u8) -> bool>"));
+        assert!(matches("Box<dyn Fn()>", "alloc::boxed::Box<dyn core::ops::function::Fn()>"));
+        assert!(matches("Pin<Box<dyn Future<Output = _>>>", "core::pin::Pin<alloc::boxed::Box<dyn core::future::future::Future<Output = u8>>>"));
+    }
+
+    #[test]
+    fn should_match_globs() {
+        assert!(matches_glob("std::*::HashMap<*>", "std::collections::HashMap<std::string::String, i32>"));
+        assert!(matches_glob("*", ""));
+        assert!(matches_glob("*Vec<*>*", "core::option::Option<std::vec::Vec<u8>>"));
+        assert!(matches_glob("[u8; ?]", "[u8; 4]"));
+        assert!(!matches_glob("[u8; ?]", "[u8; 16]"));
+        assert!(matches_glob("a*b*c", "abbbc"));
+        assert!(!matches_glob("a*b*c", "abbbcd"));
+        assert!(!matches_glob("std::*::HashMap<*>", "std::collections::HashSet<i32>"));
+        assert!(matches_glob("my_crate::Größe<*>", "my_crate::Größe<u8>"));
+    }
+
+    #[test]
+    fn should_reject_invalid_patterns() {
+        assert_eq!(TypePattern::new("Vec<_"), None);
+        assert_eq!(TypePattern::new(".."), None);
+        assert!(!TypePattern::new("_").unwrap().matches_name("Vec<u8"));
+        assert_eq!(TypePattern::new(&"&".repeat(1_000_000)), None);
+    }
+}
@@ -0,0 +1,603 @@
+//! Matching of canonical type names against regular expressions.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+use format;
+use TypeDef;
+
+/// The most instructions a compiled expression may have, so that nested
+/// repetitions as `(a{100}){100}` are rejected instead of using a lot of memory.
+const MAX_INSTS: usize = 10_000;
+
+/// The deepest nesting of groups, which keeps the parser from exhausting the
+/// stack.
+const MAX_DEPTH: usize = 128;
+
+/// Regular expression that selects types by their canonical name.
+///
+/// The expression is matched with the name returned by `canonicalize`, so
+/// paths into the standard library are written as `std::collections::HashMap`
+/// and there is one space after `,`. Like with most regular expression
+/// engines, the expression matches if it matches any part of the name; use
+/// `^` and `$` to match the whole name.
+///
+/// The syntax is a subset of the usual one:
+///
+/// - `.` matches any character, and `\d`, `\w` and `\s` match ASCII digits,
+///   word characters and whitespace, with `\D`, `\W` and `\S` matching the
+///   other characters;
+/// - `[abc]`, `[a-z]` and `[^abc]` match characters of a class;
+/// - `^` and `$` match at the start and the end of the name;
+/// - `*`, `+`, `?`, `{n}`, `{n,}` and `{n,m}` repeat the preceding item, and
+///   may be followed by `?`, which does not change what matches;
+/// - `(...)` and `(?:...)` group items, nested up to 128 levels deep, and
+///   `|` separates alternatives;
+/// - `\` followed by a punctuation character matches the character.
+///
+/// Matching takes time proportional to the length of the name times the
+/// length of the expression, without backtracking.
+///
+/// This is only available if this crate is compiled with `features = ["regex"]`.
+///
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
+/// use std::collections::HashMap;
+/// use typedef::{ TypeDef, TypeRegex };
+///
+/// let regex = TypeRegex::new(r"^std::collections::(Hash|BTree)Map<std::string::String, ").unwrap();
+///
+/// assert!(regex.matches(&TypeDef::of::<HashMap<String, u8>>()));
+/// assert!(regex.matches_name("alloc::collections::btree::map::BTreeMap<alloc::string::String, i8>"));
+/// assert!(!regex.matches(&TypeDef::of::<HashMap<u8, String>>()));
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct TypeRegex {
+    program: Vec<Inst>,
+}
+
+impl TypeRegex {
+    /// Compile a regular expression.
+    pub fn new(regex: &str) -> Result<TypeRegex, RegexError> {
+        let mut parser = Parser { regex, pos: 0, depth: 0 };
+        let node = parser.alternation()?;
+        if parser.pos < regex.len() {
+            return Err(parser.error("unmatched `)`"));
+        }
+        let mut program = Vec::new();
+        if compile(&node, &mut program).is_none() {
+            return Err(RegexError { message: "expression is too large", offset: 0 });
+        }
+        program.push(Inst::Match);
+        Ok(TypeRegex { program })
+    }
+
+    /// Check if the canonical name of a type matches the expression.
+    pub fn matches(&self, typedef: &TypeDef) -> bool {
+        self.is_match(&typedef.canonical_name())
+    }
+
+    /// Check if a type name matches the expression, after rewriting it with
+    /// `canonicalize`.
+    pub fn matches_name(&self, name: &str) -> bool {
+        self.is_match(&format::canonicalize(name))
+    }
+
+    fn is_match(&self, name: &str) -> bool {
+        let chars: Vec<char> = name.chars().collect();
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        for pos in 0..=chars.len() {
+            // Every position can start a match.
+            if self.add(&mut current, 0, pos, chars.len()) {
+                return true;
+            }
+            let c = match chars.get(pos) {
+                Some(&c) => c,
+                None => break,
+            };
+            for i in 0..current.pcs.len() {
+                let pc = current.pcs[i];
+                let step = match self.program[pc] {
+                    Inst::Char(expected) => c == expected,
+                    Inst::Any => true,
+                    Inst::Class(ref class) => class.matches(c),
+                    _ => false,
+                };
+                if step && self.add(&mut next, pc + 1, pos + 1, chars.len()) {
+                    return true;
+                }
+            }
+            current.clear();
+            ::core::mem::swap(&mut current, &mut next);
+        }
+        false
+    }
+
+    /// Add the thread at `pc` and the threads reachable from it without
+    /// reading a character, returning `true` if one of them is a match.
+    fn add(&self, threads: &mut Threads, pc: usize, pos: usize, len: usize) -> bool {
+        let mut stack = alloc::vec![pc];
+        while let Some(pc) = stack.pop() {
+            if threads.seen[pc] {
+                continue;
+            }
+            threads.seen[pc] = true;
+            match self.program[pc] {
+                Inst::Jump(to) => stack.push(to),
+                Inst::Split(first, second) => {
+                    stack.push(second);
+                    stack.push(first);
+                },
+                Inst::Start if pos == 0 => stack.push(pc + 1),
+                Inst::End if pos == len => stack.push(pc + 1),
+                Inst::Start | Inst::End => {},
+                Inst::Match => return true,
+                Inst::Char(_) | Inst::Any | Inst::Class(_) => threads.pcs.push(pc),
+            }
+        }
+        false
+    }
+}
+
+/// Error returned by `TypeRegex::new` for an invalid expression.
+///
+/// This is only available if this crate is compiled with `features = ["regex"]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegexError {
+    message: &'static str,
+    offset: usize,
+}
+
+impl RegexError {
+    /// Get the byte offset in the expression where the error was found.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid regular expression at byte {}: {}", self.offset, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for RegexError {}
+
+/// Parsed expression.
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat { node: Box<Node>, min: usize, max: Option<usize> },
+}
+
+/// Characters of a `[...]` class or of an escape as `\d`.
+#[derive(Clone, Debug)]
+struct Class {
+    negated: bool,
+    ranges: Vec<(char, char)>,
+}
+
+impl Class {
+    fn matches(&self, c: char) -> bool {
+        self.ranges.iter().any(|&(start, end)| start <= c && c <= end) != self.negated
+    }
+}
+
+/// Instruction of a compiled expression.
+#[derive(Clone, Debug)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    Jump(usize),
+    Split(usize, usize),
+    Match,
+}
+
+/// Threads of the matcher at one position of the name.
+struct Threads {
+    pcs: Vec<usize>,
+    seen: Vec<bool>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Threads {
+        Threads {
+            pcs: Vec::new(),
+            seen: alloc::vec![false; len],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.pcs.clear();
+        for seen in &mut self.seen {
+            *seen = false;
+        }
+    }
+}
+
+struct Parser<'a> {
+    regex: &'a str,
+    pos: usize,
+    /// Number of groups that contain the current position.
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> RegexError {
+        RegexError { message, offset: self.pos }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.regex[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            return true;
+        }
+        false
+    }
+
+    fn alternation(&mut self) -> Result<Node, RegexError> {
+        let mut alternatives = alloc::vec![self.concat()?];
+        while self.eat('|') {
+            alternatives.push(self.concat()?);
+        }
+        Ok(if alternatives.len() == 1 { alternatives.pop().unwrap() } else { Node::Alternation(alternatives) })
+    }
+
+    fn concat(&mut self) -> Result<Node, RegexError> {
+        let mut items = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let item = self.atom()?;
+            items.push(self.repeat(item)?);
+        }
+        Ok(match items.len() {
+            0 => Node::Empty,
+            1 => items.pop().unwrap(),
+            _ => Node::Concat(items),
+        })
+    }
+
+    fn atom(&mut self) -> Result<Node, RegexError> {
+        let start = self.pos;
+        Ok(match self.next() {
+            Some('(') => {
+                if self.eat('?') && !self.eat(':') {
+                    self.pos = start;
+                    return Err(self.error("unsupported group"));
+                }
+                if self.depth >= MAX_DEPTH {
+                    self.pos = start;
+                    return Err(self.error("groups are nested too deeply"));
+                }
+                self.depth += 1;
+                let node = self.alternation()?;
+                self.depth -= 1;
+                if !self.eat(')') {
+                    self.pos = start;
+                    return Err(self.error("unclosed group"));
+                }
+                node
+            },
+            Some('[') => self.class(start)?,
+            Some('.') => Node::Any,
+            Some('^') => Node::Start,
+            Some('$') => Node::End,
+            Some('\\') => match self.escape(start)? {
+                Escape::Char(c) => Node::Char(c),
+                Escape::Class(class) => Node::Class(class),
+            },
+            Some('*') | Some('+') | Some('?') | Some('{') => {
+                self.pos = start;
+                return Err(self.error("nothing to repeat"));
+            },
+            Some(c) => Node::Char(c),
+            None => Node::Empty,
+        })
+    }
+
+    fn repeat(&mut self, node: Node) -> Result<Node, RegexError> {
+        let start = self.pos;
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.next();
+                let min = self.number().ok_or_else(|| self.error("expected a number"))?;
+                let max = if self.eat(',') {
+                    if self.peek() == Some('}') { None } else { Some(self.number().ok_or_else(|| self.error("expected a number"))?) }
+                } else {
+                    Some(min)
+                };
+                if !self.eat('}') {
+                    return Err(self.error("expected `}`"));
+                }
+                if max.is_some_and(|max| max < min) {
+                    self.pos = start;
+                    return Err(self.error("invalid repetition range"));
+                }
+                self.eat('?');
+                return Ok(Node::Repeat { node: Box::new(node), min, max });
+            },
+            _ => return Ok(node),
+        };
+        self.next();
+        self.eat('?');
+        Ok(Node::Repeat { node: Box::new(node), min, max })
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.regex[start..self.pos].parse().ok().filter(|&n| n <= MAX_INSTS)
+    }
+
+    fn class(&mut self, start: usize) -> Result<Node, RegexError> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let item_start = self.pos;
+            let c = match self.next() {
+                Some(']') if !first => break,
+                Some('\\') => match self.escape(item_start)? {
+                    Escape::Char(c) => c,
+                    Escape::Class(Class { negated: false, ranges: escaped }) => {
+                        ranges.extend(escaped);
+                        first = false;
+                        continue;
+                    },
+                    Escape::Class(_) => {
+                        self.pos = item_start;
+                        return Err(self.error("negated escape in a class"));
+                    },
+                },
+                Some(c) => c,
+                None => {
+                    self.pos = start;
+                    return Err(self.error("unclosed class"));
+                },
+            };
+            first = false;
+            let end = if self.regex[self.pos..].starts_with('-') && !self.regex[self.pos..].starts_with("-]") {
+                self.next();
+                match self.next() {
+                    Some('\\') => match self.escape(self.pos - 1)? {
+                        Escape::Char(c) => c,
+                        Escape::Class(_) => return Err(self.error("invalid class range")),
+                    },
+                    Some(end) => end,
+                    None => {
+                        self.pos = start;
+                        return Err(self.error("unclosed class"));
+                    },
+                }
+            } else {
+                c
+            };
+            if end < c {
+                self.pos = item_start;
+                return Err(self.error("invalid class range"));
+            }
+            ranges.push((c, end));
+        }
+        Ok(Node::Class(Class { negated, ranges }))
+    }
+
+    fn escape(&mut self, start: usize) -> Result<Escape, RegexError> {
+        let c = match self.next() {
+            Some(c) => c,
+            None => {
+                self.pos = start;
+                return Err(self.error("unfinished escape"));
+            },
+        };
+        let ranges: &[(char, char)] = match c.to_ascii_lowercase() {
+            'd' => &[('0', '9')],
+            'w' => &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')],
+            's' => &[('\t', '\r'), (' ', ' ')],
+            _ if c.is_ascii_punctuation() => return Ok(Escape::Char(c)),
+            _ => {
+                self.pos = start;
+                return Err(self.error("unsupported escape"));
+            },
+        };
+        Ok(Escape::Class(Class {
+            negated: c.is_ascii_uppercase(),
+            ranges: ranges.to_vec(),
+        }))
+    }
+}
+
+enum Escape {
+    Char(char),
+    Class(Class),
+}
+
+/// Append the instructions of a node, returning `None` if there are more
+/// than `MAX_INSTS` of them.
+fn compile(node: &Node, program: &mut Vec<Inst>) -> Option<()> {
+    if program.len() > MAX_INSTS {
+        return None;
+    }
+    match *node {
+        Node::Empty => {},
+        Node::Char(c) => program.push(Inst::Char(c)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class(ref class) => program.push(Inst::Class(class.clone())),
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+        Node::Concat(ref items) => {
+            for item in items {
+                compile(item, program)?;
+            }
+        },
+        Node::Alternation(ref alternatives) => {
+            let mut jumps = Vec::new();
+            for (i, alternative) in alternatives.iter().enumerate() {
+                if i + 1 == alternatives.len() {
+                    compile(alternative, program)?;
+                    break;
+                }
+                let split = program.len();
+                program.push(Inst::Split(split + 1, 0));
+                compile(alternative, program)?;
+                jumps.push(program.len());
+                program.push(Inst::Jump(0));
+                let next = program.len();
+                program[split] = Inst::Split(split + 1, next);
+            }
+            let end = program.len();
+            for jump in jumps {
+                program[jump] = Inst::Jump(end);
+            }
+        },
+        Node::Repeat { node: ref item, min, max } => {
+            // Items that only match the empty string are not repeated.
+            let start = program.len();
+            compile(item, program)?;
+            if program.len() == start {
+                return Some(());
+            }
+            program.truncate(start);
+            for _ in 0..min {
+                compile(item, program)?;
+            }
+            match max {
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(item, program)?;
+                    program.push(Inst::Jump(split));
+                    let end = program.len();
+                    program[split] = Inst::Split(split + 1, end);
+                },
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in min..max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(program.len() + 1, 0));
+                        compile(item, program)?;
+                        if program.len() > MAX_INSTS {
+                            return None;
+                        }
+                    }
+                    let end = program.len();
+                    for split in splits {
+                        program[split] = Inst::Split(split + 1, end);
+                    }
+                },
+            }
+        },
+    }
+    if program.len() > MAX_INSTS {
+        return None;
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::TypeRegex;
+
+    fn matches(regex: &str, name: &str) -> bool {
+        TypeRegex::new(regex).unwrap().matches_name(name)
+    }
+
+    #[test]
+    fn should_match_anywhere_in_the_name() {
+        assert!(matches("Vec", "alloc::vec::Vec<u8>"));
+        assert!(matches("^std::vec::Vec<u8>$", "alloc::vec::Vec<u8>"));
+        assert!(!matches("^Vec", "alloc::vec::Vec<u8>"));
+        assert!(!matches("u8$", "alloc::vec::Vec<u8>"));
+        assert!(matches("", "u8"));
+    }
+
+    #[test]
+    fn should_match_canonical_names() {
+        assert!(matches(r"^std::sync::Mutex<&str>$", "std::sync::poison::mutex::Mutex<&'static str>"));
+        assert!(matches(r"HashMap<u8, u8>", "std::collections::hash::map::HashMap<u8,u8>"));
+        assert!(matches(r"^my_crate::Foo$", "my_crate[4f2a]::Foo"));
+    }
+
+    #[test]
+    fn should_match_repetitions_and_alternatives() {
+        assert!(matches(r"^\(u8(, u8)*\)$", "(u8, u8, u8)"));
+        assert!(matches(r"^\[u8; \d+\]$", "[u8; 16]"));
+        assert!(!matches(r"^\[u8; \d+\]$", "[u8; _]"));
+        assert!(matches(r"^std::collections::(Hash|BTree)(Map|Set)<", "alloc::collections::btree::set::BTreeSet<u8>"));
+        assert!(matches(r"^u(8|16|32){1,2}$", "u816"));
+        assert!(!matches(r"^u(8|16|32){1,2}$", "u81632"));
+        assert!(matches(r"^u\d{2,}?$", "u128"));
+        assert!(!matches(r"^u\d{3}$", "u64"));
+        assert!(matches(r"^(a*)*$", "aaaa"));
+        assert!(matches(r"^u8((){1000}){1000}$", "u8"));
+        assert!(matches(r"^(?:i|u)size$", "usize"));
+    }
+
+    #[test]
+    fn should_match_classes() {
+        assert!(matches(r"^[iu](8|16)$", "i16"));
+        assert!(!matches(r"^[iu](8|16)$", "f16"));
+        assert!(matches(r"^[^:]+$", "u8"));
+        assert!(!matches(r"^[^:]+$", "my_crate::Foo"));
+        assert!(matches(r"^[\w:]+$", "my_crate::Foo"));
+        assert!(matches(r"^[]a]+$", "]a]"));
+        assert!(matches(r"^[a-]+$", "a-a"));
+        assert!(matches(r"\W", "&u8"));
+        assert!(!matches(r"\s", "u8"));
+    }
+
+    #[test]
+    fn should_reject_invalid_expressions() {
+        let error = |regex: &str| TypeRegex::new(regex).unwrap_err();
+
+        assert_eq!(error("(u8").offset(), 0);
+        assert_eq!(error("u8)").offset(), 2);
+        assert_eq!(error("*u8").offset(), 0);
+        assert_eq!(error("u8**").offset(), 3);
+        assert_eq!(error("[u8").offset(), 0);
+        assert_eq!(error("[z-a]").offset(), 1);
+        assert_eq!(error("u{2,1}").offset(), 1);
+        assert_eq!(error(r"\q").offset(), 0);
+        assert_eq!(error("(?=u8)").offset(), 0);
+        assert_eq!(error("(a{1000}){1000}").to_string(), "invalid regular expression at byte 0: expression is too large");
+        assert_eq!(error(&"(".repeat(200_000)).to_string(), "invalid regular expression at byte 128: groups are nested too deeply");
+        assert!(TypeRegex::new(&format!("{}u8{}", "(".repeat(128), ")".repeat(128))).is_ok());
+    }
+
+    #[test]
+    fn should_match_in_linear_time() {
+        let name = "a".repeat(1000);
+
+        assert!(!matches(r"^(a|aa)*b$", &name));
+        assert!(!matches(r"^(a*)*b$", &name));
+    }
+}
@@ -0,0 +1,2747 @@
+//! Global registry of typedefs, to recover a named typedef from a `TypeId`.
+//!
+//! A `TypeId` alone, as the one returned by `Any::type_id` on a `dyn Any`,
+//! has no name. Registering the type first makes the name available later
+//! through `TypeDef::from_id`:
+//!
+//! ```
+//! # #[cfg(not(feature = "id-only"))] {
+//! use std::any::Any;
+//! use typedef::{ registry, TypeDef };
+//!
+//! struct Order;
+//!
+//! registry::register::<Order>();
+//!
+//! let value: Box<dyn Any> = Box::new(Order);
+//! let typedef = TypeDef::from_id((*value).type_id()).unwrap();
+//!
+//! assert!(typedef.is::<Order>());
+//! assert_eq!(typedef.ident(), Some("Order"));
+//! # }
+//! ```
+//!
+//! Registered types can also be found by name with `TypeDef::from_name`, so
+//! that configuration files and messages can refer to them:
+//!
+//! ```
+//! # #[cfg(not(feature = "id-only"))] {
+//! use typedef::{ registry, TypeDef };
+//!
+//! registry::register::<Vec<String>>();
+//!
+//! let typedef: TypeDef = "std::vec::Vec<std::string::String>".parse().unwrap();
+//!
+//! assert!(typedef.is::<Vec<String>>());
+//! # }
+//! ```
+//!
+//! The functions of this module use the global registry. A `Registry` can
+//! also be created and passed around, to keep its types apart from the global
+//! ones.
+//!
+//! This is not available if this crate is compiled without the `std`
+//! feature.
+//!
+//! With `features = ["ctor"]`, types can be registered where they are
+//! declared with `register_type!`, which runs the registration at startup.
+
+use core::any::{Any, TypeId};
+use core::fmt;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::boxed::Box;
+use std::string::{String, ToString};
+use std::vec::Vec;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use format;
+use json;
+use TypeDef;
+#[cfg(feature = "regex")]
+use TypeRegex;
+
+// The registry is read far more often than it is written, mostly by
+// `TypeId`. Everything found by `TypeId` is split into shards by the id, so
+// that threads looking up different types take different locks; only the
+// lookups by name share one lock.
+//
+// A write that needs both takes the lock of `Names` first and then the lock
+// of one shard. No code holds two shard locks at once.
+
+/// Part of the registry that is found by name.
+struct Names {
+    by_name: BTreeMap<String, TypeDef>,
+    by_exact_name: BTreeMap<String, TypeDef>,
+    #[cfg(not(feature = "id-only"))]
+    by_hash: BTreeMap<u64, TypeDef>,
+    collisions: Vec<Collision>,
+    aliases: BTreeMap<String, TypeId>,
+}
+
+/// Part of the registry for the types whose `TypeId` falls in the shard.
+///
+/// Maps keyed by a pair keep in the shard of the registered type, which is
+/// the second id for `impls` and `casts` and the first one for `converters`.
+struct Shard {
+    by_id: BTreeMap<TypeId, TypeDef>,
+    display_names: BTreeMap<TypeId, &'static str>,
+    metadata: BTreeMap<(TypeId, TypeId), Arc<dyn Any + Send + Sync>>,
+    factories: BTreeMap<TypeId, Factory>,
+    facets: BTreeMap<TypeId, Facets>,
+    impls: BTreeSet<(TypeId, TypeId)>,
+    casts: BTreeMap<(TypeId, TypeId), Arc<dyn Any + Send + Sync>>,
+    converters: BTreeMap<(TypeId, TypeId), Converter>,
+}
+
+impl Shard {
+    const fn new() -> Shard {
+        Shard {
+            by_id: BTreeMap::new(),
+            display_names: BTreeMap::new(),
+            metadata: BTreeMap::new(),
+            factories: BTreeMap::new(),
+            facets: BTreeMap::new(),
+            impls: BTreeSet::new(),
+            casts: BTreeMap::new(),
+            converters: BTreeMap::new(),
+        }
+    }
+}
+
+const SHARD_COUNT: usize = 16;
+
+/// Registry of typedefs and of what is registered for them.
+///
+/// The functions of this module use the global registry, returned by
+/// `global`. Other registries are created with `Registry::new` and only see
+/// what is registered in them, as tests that must not share types or servers
+/// that keep the types of each tenant apart need.
+///
+/// ```
+/// use std::any::TypeId;
+/// use typedef::registry::{ self, Registry };
+///
+/// struct Tenant;
+///
+/// let registry = Registry::new();
+/// registry.register::<Tenant>();
+///
+/// assert!(registry.lookup(TypeId::of::<Tenant>()).is_some());
+/// assert!(registry::lookup(TypeId::of::<Tenant>()).is_none());
+/// ```
+///
+/// Names set with `set_display_name` are only used by `Display` of
+/// `TypeDef` if they are set in the global registry.
+pub struct Registry {
+    names: RwLock<Names>,
+    shards: [RwLock<Shard>; SHARD_COUNT],
+    has_display_names: AtomicBool,
+}
+
+static GLOBAL: Registry = Registry::new();
+
+/// Get the global registry, which the functions of this module use.
+pub fn global() -> &'static Registry {
+    &GLOBAL
+}
+
+impl Registry {
+    /// Create an empty registry.
+    pub const fn new() -> Registry {
+        Registry {
+            names: RwLock::new(Names {
+                by_name: BTreeMap::new(),
+                by_exact_name: BTreeMap::new(),
+                #[cfg(not(feature = "id-only"))]
+                by_hash: BTreeMap::new(),
+                collisions: Vec::new(),
+                aliases: BTreeMap::new(),
+            }),
+            shards: [const { RwLock::new(Shard::new()) }; SHARD_COUNT],
+            has_display_names: AtomicBool::new(false),
+        }
+    }
+
+    /// Register the type in this registry, as `register` does.
+    #[track_caller]
+    pub fn register<T: ?Sized + Any>(&self) -> TypeDef {
+        let typedef = TypeDef::of_traced::<T>();
+        self.register_typedef(typedef);
+        typedef
+    }
+
+    /// Register a typedef in this registry, as `register_typedef` does.
+    pub fn register_typedef(&self, typedef: TypeDef) {
+        let mut names = self.names_mut();
+        let mut shard = self.shard_mut(typedef.id());
+        match names.find_collision(&typedef) {
+            Some(collision) => {
+                shard.by_id.insert(typedef.id(), typedef);
+                names.by_exact_name.entry(exact_name(&typedef)).or_insert(typedef);
+                names.collisions.push(collision);
+            }
+            None => names.insert(typedef, shard.by_id.insert(typedef.id(), typedef)),
+        }
+    }
+
+    /// Register a typedef in this registry, as `try_register_typedef` does.
+    pub fn try_register_typedef(&self, typedef: TypeDef) -> Result<(), Box<Collision>> {
+        let mut names = self.names_mut();
+        let mut shard = self.shard_mut(typedef.id());
+        match names.find_collision(&typedef) {
+            Some(collision) => Err(Box::new(collision)),
+            None => {
+                names.insert(typedef, shard.by_id.insert(typedef.id(), typedef));
+                Ok(())
+            }
+        }
+    }
+
+    /// Get the collisions of this registry, as `collisions` does.
+    pub fn collisions(&self) -> Vec<Collision> {
+        self.names().collisions.clone()
+    }
+
+    /// Register an alias in this registry, as `register_alias` does.
+    pub fn register_alias<T: ?Sized + Any>(&self, alias: &str) {
+        let id = TypeId::of::<T>();
+        if self.lookup(id).is_none() {
+            self.register::<T>();
+        }
+        self.names_mut().aliases.insert(format::canonicalize(alias), id);
+    }
+
+    /// Set the display name in this registry, as `set_display_name` does.
+    pub fn set_display_name<T: ?Sized + Any>(&self, name: &'static str) {
+        let id = TypeId::of::<T>();
+        if self.lookup(id).is_none() {
+            self.register::<T>();
+        }
+        self.shard_mut(id).display_names.insert(id, name);
+        self.has_display_names.store(true, Ordering::Release);
+    }
+
+    /// Get the display name set in this registry, as `display_name` does.
+    pub fn display_name(&self, id: TypeId) -> Option<&'static str> {
+        if !self.has_display_names.load(Ordering::Acquire) {
+            return None;
+        }
+        self.shard(id).display_names.get(&id).cloned()
+    }
+
+    /// Attach metadata in this registry, as `attach` does.
+    pub fn attach<T: ?Sized + Any, M: Any + Send + Sync>(&self, meta: M) {
+        let id = TypeId::of::<T>();
+        if self.lookup(id).is_none() {
+            self.register::<T>();
+        }
+        self.shard_mut(id).metadata.insert((id, TypeId::of::<M>()), Arc::new(meta));
+    }
+
+    /// Get metadata attached in this registry, as `metadata` does.
+    pub fn metadata<M: Any + Send + Sync>(&self, typedef: &TypeDef) -> Option<Arc<M>> {
+        let meta = self.shard(typedef.id()).metadata.get(&(typedef.id(), TypeId::of::<M>()))?.clone();
+        meta.downcast().ok()
+    }
+
+    /// Register `Default` of the type in this registry, as
+    /// `register_default` does.
+    #[track_caller]
+    pub fn register_default<T: Any + Default>(&self) -> TypeDef {
+        self.register_factory(T::default)
+    }
+
+    /// Register a factory in this registry, as `register_factory` does.
+    #[track_caller]
+    pub fn register_factory<T: Any, F: Fn() -> T + Send + Sync + 'static>(&self, factory: F) -> TypeDef {
+        let typedef = self.lookup_or_register::<T>();
+        self.shard_mut(typedef.id())
+            .factories
+            .insert(typedef.id(), Arc::new(move || Box::new(factory()) as Box<dyn Any>));
+        typedef
+    }
+
+    /// Create a value with a factory of this registry, as `construct` does.
+    pub fn construct(&self, name: &str) -> Option<Box<dyn Any>> {
+        let typedef = self.lookup_name(name)?;
+        let factory = self.shard(typedef.id()).factories.get(&typedef.id())?.clone();
+        Some(factory())
+    }
+
+    /// Register `Clone` of the type in this registry, as `register_clone`
+    /// does.
+    #[track_caller]
+    pub fn register_clone<T: Any + Clone>(&self) -> TypeDef {
+        self.update_facets::<T, _>(|facets| facets.clone = Some(clone_any::<T>))
+    }
+
+    /// Register `Debug` of the type in this registry, as `register_debug`
+    /// does.
+    #[track_caller]
+    pub fn register_debug<T: Any + fmt::Debug>(&self) -> TypeDef {
+        self.update_facets::<T, _>(|facets| facets.debug = Some(debug_any::<T>))
+    }
+
+    /// Register `PartialEq` of the type in this registry, as `register_eq`
+    /// does.
+    #[track_caller]
+    pub fn register_eq<T: Any + PartialEq>(&self) -> TypeDef {
+        self.update_facets::<T, _>(|facets| facets.eq = Some(eq_any::<T>))
+    }
+
+    /// Get the facets registered in this registry, as `facets` does.
+    pub fn facets(&self, typedef: &TypeDef) -> Facets {
+        self.shard(typedef.id()).facets.get(&typedef.id()).cloned().unwrap_or_default()
+    }
+
+    #[track_caller]
+    fn update_facets<T: Any, F: FnOnce(&mut Facets)>(&self, update: F) -> TypeDef {
+        let typedef = self.lookup_or_register::<T>();
+        let mut shard = self.shard_mut(typedef.id());
+        let facets = shard.facets.entry(typedef.id()).or_default();
+        facets.id = Some(typedef.id());
+        update(facets);
+        typedef
+    }
+
+    /// Record an implementation in this registry, as `register_impl` does.
+    #[track_caller]
+    pub fn register_impl<T: ?Sized + Any, Trait: ?Sized + Any>(&self) -> TypeDef {
+        let typedef = self.lookup_or_register::<T>();
+        self.shard_mut(typedef.id()).impls.insert((TypeId::of::<Trait>(), typedef.id()));
+        typedef
+    }
+
+    /// Check an implementation recorded in this registry, as `implements`
+    /// does.
+    pub fn implements<Trait: ?Sized + Any>(&self, typedef: &TypeDef) -> bool {
+        self.shard(typedef.id()).impls.contains(&(TypeId::of::<Trait>(), typedef.id()))
+    }
+
+    /// Get the implementors recorded in this registry, as `implementors_of`
+    /// does.
+    pub fn implementors_of<Trait: ?Sized + Any>(&self) -> Vec<TypeDef> {
+        let trait_id = TypeId::of::<Trait>();
+        let mut implementors = Vec::new();
+        for index in 0..SHARD_COUNT {
+            let shard = self.shard_at(index);
+            implementors.extend(
+                shard
+                    .impls
+                    .iter()
+                    .filter(|entry| entry.0 == trait_id)
+                    .filter_map(|entry| shard.by_id.get(&entry.1).cloned()),
+            );
+        }
+        implementors
+    }
+
+    /// Register a cast in this registry, as `register_cast` does.
+    #[track_caller]
+    pub fn register_cast<T: Any, Trait: ?Sized + Any>(&self, cast: fn(&T) -> &Trait) -> TypeDef {
+        let typedef = self.register_impl::<T, Trait>();
+        let caster: Caster<Trait> = Box::new(move |value: &dyn Any| value.downcast_ref::<T>().map(cast));
+        self.shard_mut(typedef.id()).casts.insert((TypeId::of::<Trait>(), typedef.id()), Arc::new(caster));
+        typedef
+    }
+
+    /// Cast a value with a cast registered in this registry, as `cast` does.
+    pub fn cast<'v, Trait: ?Sized + Any>(&self, value: &'v dyn Any) -> Option<&'v Trait> {
+        let caster = self.shard(value.type_id()).casts.get(&(TypeId::of::<Trait>(), value.type_id()))?.clone();
+        let caster = caster.downcast_ref::<Caster<Trait>>()?;
+        caster(value)
+    }
+
+    /// Register a converter in this registry, as `register_converter` does.
+    pub fn register_converter(&self, from: TypeDef, to: TypeDef, converter: Converter) {
+        for typedef in &[from, to] {
+            if self.lookup(typedef.id()).is_none() {
+                self.register_typedef(*typedef);
+            }
+        }
+        self.shard_mut(from.id()).converters.insert((from.id(), to.id()), converter);
+    }
+
+    /// Register `Into` between the types in this registry, as
+    /// `register_into` does.
+    #[track_caller]
+    pub fn register_into<From: Any + Into<To>, To: Any>(&self) {
+        let from = self.lookup_or_register::<From>();
+        let to = self.lookup_or_register::<To>();
+        self.register_converter(from, to, into_any::<From, To>);
+    }
+
+    /// Get a converter registered in this registry, as `converter` does.
+    pub fn converter(&self, from: &TypeDef, to: &TypeDef) -> Option<Converter> {
+        self.shard(from.id()).converters.get(&(from.id(), to.id())).cloned()
+    }
+
+    /// Get the conversions registered in this registry, as
+    /// `conversions_from` does.
+    pub fn conversions_from(&self, from: &TypeDef) -> Vec<TypeDef> {
+        let targets: Vec<TypeId> = self
+            .shard(from.id())
+            .converters
+            .keys()
+            .filter(|key| key.0 == from.id())
+            .map(|key| key.1)
+            .collect();
+        targets.into_iter().filter_map(|id| self.lookup(id)).collect()
+    }
+
+    /// Convert a value with a converter of this registry, as `convert` does.
+    pub fn convert(&self, value: Box<dyn Any>, to: &TypeDef) -> Result<Box<dyn Any>, Box<dyn Any>> {
+        let from = (*value).type_id();
+        if from == to.id() {
+            return Ok(value);
+        }
+        let converter = self.shard(from).converters.get(&(from, to.id())).cloned();
+        match converter {
+            Some(converter) => Ok(converter(value)),
+            None => Err(value),
+        }
+    }
+
+    /// Find a typedef registered in this registry, as `lookup` does.
+    pub fn lookup(&self, id: TypeId) -> Option<TypeDef> {
+        self.shard(id).by_id.get(&id).cloned()
+    }
+
+    /// Find a typedef registered in this registry by name, as `lookup_name`
+    /// does.
+    pub fn lookup_name(&self, name: &str) -> Option<TypeDef> {
+        let id = {
+            let names = self.names();
+            if let Some(typedef) = names.by_exact_name.get(name) {
+                return Some(*typedef);
+            }
+            let name = canonical_lookup_name(name);
+            match names.by_name.get(&name) {
+                Some(typedef) => return Some(*typedef),
+                None => *names.aliases.get(&name)?,
+            }
+        };
+        self.lookup(id)
+    }
+
+    /// Iterate over a copy of the typedefs of this registry, as `iter` does.
+    pub fn iter(&self) -> impl Iterator<Item = TypeDef> {
+        let mut types = Vec::new();
+        for index in 0..SHARD_COUNT {
+            types.extend(self.shard_at(index).by_id.values().cloned());
+        }
+        types.into_iter()
+    }
+
+    /// Create a query of the typedefs of this registry, as `query` does.
+    pub fn query<'a>(&'a self) -> Query<'a> {
+        Query::in_registry(self)
+    }
+
+    /// Search for types of this registry by name, as `search` does.
+    pub fn search(&self, text: &str) -> Vec<TypeDef> {
+        let text = text.to_lowercase();
+        let mut found: Vec<(u8, usize, String, TypeDef)> = self
+            .iter()
+            .filter_map(|typedef| {
+                let short = match self.display_name(typedef.id()) {
+                    Some(name) => name.to_lowercase(),
+                    None => typedef.with_name(|name| format::strip_paths(name).to_string().to_lowercase()),
+                };
+                let name = registry_name(&typedef).to_lowercase();
+                let rank = search_rank(&text, &short, &name)?;
+                Some((rank, short.len(), name, typedef))
+            })
+            .collect();
+        found.sort_by(|left, right| (left.0, left.1, &left.2).cmp(&(right.0, right.1, &right.2)));
+        found.into_iter().map(|found| found.3).collect()
+    }
+
+    /// Write this registry to JSON, as `export_json` does.
+    pub fn export_json(&self) -> String {
+        self.snapshot().to_json()
+    }
+
+    /// Take a snapshot of this registry, as `snapshot` does.
+    pub fn snapshot(&self) -> RegistrySnapshot {
+        let names = self.names();
+        let mut types = Vec::new();
+        for index in 0..SHARD_COUNT {
+            let shard = self.shard_at(index);
+            types.extend(shard.by_id.values().map(|typedef| SnapshotType {
+                name: registry_name(typedef),
+                #[cfg(not(feature = "id-only"))]
+                stable_hash: Some(snapshot_hash(typedef)),
+                #[cfg(feature = "id-only")]
+                stable_hash: None,
+                display_name: shard.display_names.get(&typedef.id()).map(|name| name.to_string()),
+                aliases: names
+                    .aliases
+                    .iter()
+                    .filter(|alias| *alias.1 == typedef.id())
+                    .map(|alias| alias.0.clone())
+                    .collect(),
+                location: typedef.location().map(|location| location.to_string()),
+            }));
+        }
+        types.sort_by(|left, right| left.name.cmp(&right.name));
+        RegistrySnapshot { types }
+    }
+
+    /// Merge a snapshot into this registry, as `merge` does.
+    pub fn merge(&self, snapshot: &RegistrySnapshot) -> usize {
+        let mut found = 0;
+        for ty in &snapshot.types {
+            let names = || Some(&ty.name).into_iter().chain(&ty.aliases);
+            let typedef = match names().filter_map(|name| self.lookup_name(name)).next() {
+                Some(typedef) => typedef,
+                None => continue,
+            };
+            found += 1;
+            let mut registry = self.names_mut();
+            for name in names() {
+                if !registry.by_name.contains_key(name) {
+                    registry.aliases.entry(name.clone()).or_insert_with(|| typedef.id());
+                }
+            }
+        }
+        found
+    }
+
+    /// Count the types of this registry, as `stats` does.
+    pub fn stats(&self) -> RegistryStats {
+        let types: Vec<TypeDef> = self.iter().collect();
+        #[cfg_attr(feature = "id-only", allow(unused_mut))]
+        let mut stats = RegistryStats {
+            types: types.len(),
+            aliases: self.names().aliases.len(),
+            collisions: self.names().collisions.len(),
+            ..RegistryStats::default()
+        };
+        #[cfg(not(feature = "id-only"))]
+        for typedef in types {
+            let krate = match typedef.crate_name() {
+                Some(krate) => krate,
+                None => continue,
+            };
+            *stats.crates.entry(krate.to_string()).or_insert(0) += 1;
+            if let Some(module) = typedef.module_path() {
+                *stats.modules.entry(format!("{}::{}", krate, module)).or_insert(0) += 1;
+            }
+        }
+        stats
+    }
+
+    #[track_caller]
+    fn lookup_or_register<T: ?Sized + Any>(&self) -> TypeDef {
+        match self.lookup(TypeId::of::<T>()) {
+            Some(typedef) => typedef,
+            None => self.register::<T>(),
+        }
+    }
+
+    // The maps are never left half-updated, so a panic while a lock is held
+    // does not make their contents invalid.
+    fn names(&self) -> RwLockReadGuard<'_, Names> {
+        self.names.read().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn names_mut(&self) -> RwLockWriteGuard<'_, Names> {
+        self.names.write().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn shard(&self, id: TypeId) -> RwLockReadGuard<'_, Shard> {
+        self.shard_at(shard_index(id))
+    }
+
+    fn shard_at(&self, index: usize) -> RwLockReadGuard<'_, Shard> {
+        self.shards[index].read().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn shard_mut(&self, id: TypeId) -> RwLockWriteGuard<'_, Shard> {
+        self.shards[shard_index(id)].write().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Registry {
+        Registry::new()
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+fn shard_index(id: TypeId) -> usize {
+    (::raw_id(&id) % SHARD_COUNT as u64) as usize
+}
+
+/// Constructor that adds the types collected by the linker to the registry
+/// before `main` runs.
+#[cfg(feature = "link")]
+#[used]
+#[link_section = ".init_array"]
+static REGISTER_LINKED: extern "C" fn() = {
+    extern "C" fn register_linked() {
+        for typedef in ::link::types() {
+            register_typedef(typedef);
+        }
+    }
+    register_linked
+};
+
+/// Register the type and return its typedef.
+///
+/// The typedef records the location of the caller, as with
+/// `TypeDef::of_traced`, which is reported in collisions. Registering the
+/// same type again replaces the stored typedef.
+#[track_caller]
+pub fn register<T: ?Sized + Any>() -> TypeDef {
+    GLOBAL.register::<T>()
+}
+
+/// Register a typedef, for example one created with `TypeDef::of_named` or
+/// `TypeDef::of_tagged`, so that it is returned by `lookup` as it is.
+///
+/// The typedef is found by `lookup_name` with its canonical name, so a
+/// typedef with a custom name is found by the custom name. Tagged typedefs are
+/// found by the name followed by ` @ ` and the tag, as they are displayed.
+///
+/// If another type is already registered with the same name or the same
+/// `TypeDef::stable_hash64`, as happens when the same crate is linked twice
+/// into different dynamic libraries, the name keeps finding the type that was
+/// registered first, and the collision is added to `collisions`. The new
+/// typedef is still found by `lookup`. Use `try_register_typedef` to get the
+/// collision as an error instead.
+///
+/// ```
+/// use typedef::{ registry, TypeDef, NamedType };
+///
+/// struct Order;
+///
+/// impl NamedType for Order {
+///     const NAME: &'static str = "Order";
+/// }
+///
+/// registry::register_typedef(TypeDef::of_named::<Order>());
+///
+/// assert_eq!(registry::lookup(TypeDef::id_of::<Order>()).unwrap().name(), "Order");
+/// ```
+pub fn register_typedef(typedef: TypeDef) {
+    GLOBAL.register_typedef(typedef)
+}
+
+/// Register a typedef as `register_typedef` does, or return the collision if
+/// another type is already registered with the same name or stable hash, in
+/// which case the registry is not changed.
+///
+/// ```
+/// use typedef::{ registry, TypeDef, NamedType };
+///
+/// struct Order;
+/// struct OtherOrder;
+///
+/// impl NamedType for Order {
+///     const NAME: &'static str = "shop::Order";
+/// }
+///
+/// impl NamedType for OtherOrder {
+///     const NAME: &'static str = "shop::Order";
+/// }
+///
+/// assert!(registry::try_register_typedef(TypeDef::of_named::<Order>()).is_ok());
+///
+/// let collision = registry::try_register_typedef(TypeDef::of_named::<OtherOrder>()).unwrap_err();
+///
+/// assert_eq!(collision.kind(), registry::CollisionKind::Name);
+/// assert!(collision.registered().is::<Order>());
+/// assert!(collision.rejected().is::<OtherOrder>());
+/// assert!(registry::lookup(TypeDef::id_of::<OtherOrder>()).is_none());
+/// ```
+pub fn try_register_typedef(typedef: TypeDef) -> Result<(), Box<Collision>> {
+    GLOBAL.try_register_typedef(typedef)
+}
+
+/// Get the collisions found by `register_typedef` so far, in the order they
+/// were found.
+///
+/// Applications can check this after registering their types, to report
+/// types that can not be found by name.
+pub fn collisions() -> Vec<Collision> {
+    GLOBAL.collisions()
+}
+
+impl Names {
+    fn find_collision(&self, typedef: &TypeDef) -> Option<Collision> {
+        let other_type = |registered: &&TypeDef| registered.id() != typedef.id();
+        let collision = |kind, registered: &TypeDef| Collision {
+            kind,
+            registered: *registered,
+            rejected: *typedef,
+        };
+        if let Some(registered) = self.by_name.get(&registry_name(typedef)).filter(other_type) {
+            return Some(collision(CollisionKind::Name, registered));
+        }
+        #[cfg(not(feature = "id-only"))]
+        {
+            if let Some(registered) = self.by_hash.get(&typedef.stable_hash64()).filter(other_type) {
+                return Some(collision(CollisionKind::StableHash, registered));
+            }
+        }
+        None
+    }
+
+    /// Find the typedef by its names, after it replaced `previous` in its
+    /// shard.
+    fn insert(&mut self, typedef: TypeDef, previous: Option<TypeDef>) {
+        if let Some(previous) = previous {
+            let name = registry_name(&previous);
+            if self.by_name.get(&name).map(TypeDef::id) == Some(previous.id()) {
+                self.by_name.remove(&name);
+            }
+            let name = exact_name(&previous);
+            if self.by_exact_name.get(&name).map(TypeDef::id) == Some(previous.id()) {
+                self.by_exact_name.remove(&name);
+            }
+        }
+        self.by_name.insert(registry_name(&typedef), typedef);
+        self.by_exact_name.insert(exact_name(&typedef), typedef);
+        #[cfg(not(feature = "id-only"))]
+        self.by_hash.insert(typedef.stable_hash64(), typedef);
+    }
+}
+
+/// Get the name that finds the typedef, as it is displayed but in the
+/// canonical form.
+fn registry_name(typedef: &TypeDef) -> String {
+    let mut name = typedef.canonical_name();
+    if let Some(tag) = typedef.tag() {
+        name.push_str(" @ ");
+        name.push_str(&format::canonicalize(tag));
+    }
+    name
+}
+
+/// Hash the canonical compiler-generated name and tag of the typedef as
+/// `TypeDef::stable_hash64` hashes them as they are, so that snapshots of
+/// builds with other compilers have the same hashes.
+#[cfg(not(feature = "id-only"))]
+fn snapshot_hash(typedef: &TypeDef) -> u64 {
+    let mut hash = ::fnv1a64(::FNV_OFFSET_BASIS, format::canonicalize(typedef.name).as_bytes());
+    if let Some(tag) = typedef.tag {
+        hash = ::fnv1a64(hash, b" @ ");
+        hash = ::fnv1a64(hash, format::canonicalize(tag).as_bytes());
+    }
+    hash
+}
+
+/// Get the name that finds the typedef when it is given exactly, as
+/// returned by `TypeDef::name`, followed by ` @ ` and the tag name for
+/// tagged typedefs.
+fn exact_name(typedef: &TypeDef) -> String {
+    let mut name: String = typedef.name().into();
+    if let Some(tag) = typedef.tag() {
+        name.push_str(" @ ");
+        name.push_str(tag);
+    }
+    name
+}
+
+/// Canonicalize a name given to `lookup_name`, which may end with a tag.
+fn canonical_lookup_name(name: &str) -> String {
+    match name.find(" @ ") {
+        Some(pos) => {
+            let mut canonical = format::canonicalize(&name[..pos]);
+            canonical.push_str(" @ ");
+            canonical.push_str(&format::canonicalize(&name[pos + 3..]));
+            canonical
+        }
+        None => format::canonicalize(name),
+    }
+}
+
+/// Registration that was not added to the names of the registry, because
+/// another type already has the same name or stable hash.
+#[derive(Clone, Debug)]
+pub struct Collision {
+    kind: CollisionKind,
+    registered: TypeDef,
+    rejected: TypeDef,
+}
+
+/// What two colliding registrations have in common.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionKind {
+    /// The canonical names are the same.
+    Name,
+    /// The names are different, but `TypeDef::stable_hash64` is the same.
+    StableHash,
+}
+
+impl Collision {
+    /// Get what the registrations have in common.
+    pub fn kind(&self) -> CollisionKind {
+        self.kind
+    }
+
+    /// Get the typedef that was registered first, and is found by the name.
+    pub fn registered(&self) -> TypeDef {
+        self.registered
+    }
+
+    /// Get the typedef that collided with the registered one.
+    pub fn rejected(&self) -> TypeDef {
+        self.rejected
+    }
+}
+
+impl fmt::Display for Collision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            CollisionKind::Name => write!(f, "type name `{}`", registry_name(&self.rejected))?,
+            CollisionKind::StableHash => write!(f, "stable hash of `{}`", registry_name(&self.rejected))?,
+        }
+        write!(f, " registered ")?;
+        write_origin(&self.rejected, f)?;
+        write!(f, " is already registered for `{:?}` ", self.registered.id())?;
+        write_origin(&self.registered, f)?;
+        if self.kind == CollisionKind::StableHash {
+            write!(f, " with the name `{}`", registry_name(&self.registered))?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for Collision {}
+
+fn write_origin(typedef: &TypeDef, f: &mut fmt::Formatter) -> fmt::Result {
+    match typedef.location() {
+        Some(location) => write!(f, "at {}", location),
+        None => write!(f, "at an unknown location"),
+    }
+}
+
+/// Register another name of the type, as the name it had in an earlier
+/// release, so that `lookup_name` with this name finds the type.
+///
+/// The alias finds the typedef that is registered for the type when it is
+/// looked up, and the type is registered if it is not registered yet. Names
+/// of registered typedefs are found before aliases.
+///
+/// ```
+/// use typedef::{ registry, TypeDef };
+///
+/// struct Order;
+///
+/// registry::register_alias::<Order>("old_crate::orders::OldOrder");
+///
+/// assert!(TypeDef::from_name("old_crate::orders::OldOrder").unwrap().is::<Order>());
+/// ```
+pub fn register_alias<T: ?Sized + Any>(alias: &str) {
+    GLOBAL.register_alias::<T>(alias)
+}
+
+/// Set the name displayed for the type, for names shown to users that should
+/// not include module paths.
+///
+/// The display name is used by `Display` of `TypeDef`, in both the normal and
+/// the alternate form, and by `TypeDef::short_name`. It does not change
+/// `TypeDef::name`, and the type is not found by `lookup_name` with it. The
+/// type is registered if it is not registered yet.
+///
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
+/// use typedef::{ registry, TypeDef };
+///
+/// mod shop {
+///     pub struct Order;
+/// }
+///
+/// registry::set_display_name::<shop::Order>("Order");
+///
+/// let typedef = TypeDef::of::<shop::Order>();
+///
+/// assert_eq!(typedef.to_string(), "Order");
+/// assert_eq!(typedef.short_name(), "Order");
+/// assert!(typedef.name().ends_with("shop::Order"));
+/// # }
+/// ```
+pub fn set_display_name<T: ?Sized + Any>(name: &'static str) {
+    GLOBAL.set_display_name::<T>(name)
+}
+
+/// Get the name set with `set_display_name` for the type.
+pub fn display_name(id: TypeId) -> Option<&'static str> {
+    GLOBAL.display_name(id)
+}
+
+/// Attach metadata to the type, as an icon name, a schema id or a category.
+///
+/// The metadata is keyed by its own type, so a type can have one value of
+/// each metadata type, and attaching a value of the same metadata type again
+/// replaces it. Use a dedicated type for each kind of metadata, as
+/// `struct Icon(&'static str)`, rather than `String`. The type is registered
+/// if it is not registered yet.
+///
+/// ```
+/// use typedef::{ registry, TypeDef };
+///
+/// struct Order;
+/// struct Icon(&'static str);
+/// struct Category(&'static str);
+///
+/// registry::attach::<Order, _>(Icon("cart"));
+/// registry::attach::<Order, _>(Category("shop"));
+///
+/// let typedef = TypeDef::of::<Order>();
+///
+/// assert_eq!(registry::metadata::<Icon>(&typedef).unwrap().0, "cart");
+/// assert_eq!(registry::metadata::<Category>(&typedef).unwrap().0, "shop");
+/// assert!(registry::metadata::<Icon>(&TypeDef::of::<u8>()).is_none());
+/// ```
+pub fn attach<T: ?Sized + Any, M: Any + Send + Sync>(meta: M) {
+    GLOBAL.attach::<T, M>(meta)
+}
+
+/// Get the metadata of type `M` attached to the type with `attach`.
+///
+/// The typedef is compared by identity, so metadata attached to a type is not
+/// found with a tagged typedef of the same type.
+pub fn metadata<M: Any + Send + Sync>(typedef: &TypeDef) -> Option<Arc<M>> {
+    GLOBAL.metadata(typedef)
+}
+
+/// Register the type with `Default::default` as the function that creates
+/// its values for `construct`.
+///
+/// ```
+/// use typedef::{ registry, TypeDef };
+///
+/// #[derive(Default, Debug, PartialEq)]
+/// struct Settings {
+///     retries: u32,
+/// }
+///
+/// registry::register_default::<Settings>();
+///
+/// let value = registry::construct(&TypeDef::name_of::<Settings>()).unwrap();
+///
+/// assert_eq!(value.downcast_ref::<Settings>(), Some(&Settings { retries: 0 }));
+/// ```
+#[track_caller]
+pub fn register_default<T: Any + Default>() -> TypeDef {
+    GLOBAL.register_default::<T>()
+}
+
+/// Register the type with a function that creates its values for
+/// `construct`, replacing the function registered before.
+///
+/// ```
+/// use typedef::{ registry };
+///
+/// struct Connection {
+///     port: u16,
+/// }
+///
+/// let typedef = registry::register_factory(|| Connection { port: 8080 });
+/// let value = registry::construct(&typedef.name()).unwrap();
+///
+/// assert_eq!(value.downcast_ref::<Connection>().unwrap().port, 8080);
+/// ```
+#[track_caller]
+pub fn register_factory<T: Any, F: Fn() -> T + Send + Sync + 'static>(factory: F) -> TypeDef {
+    GLOBAL.register_factory(factory)
+}
+
+/// Create a value of the type found by the name with `lookup_name`, with the
+/// function registered by `register_default` or `register_factory`.
+///
+/// Returns `None` if no type is found by the name or the type has no such
+/// function. The function is called without holding the registry lock, so it
+/// can use the registry.
+pub fn construct(name: &str) -> Option<Box<dyn Any>> {
+    GLOBAL.construct(name)
+}
+
+/// Register `Clone` of the type in its `Facets`, so that type-erased values
+/// of the type can be cloned.
+///
+/// ```
+/// use std::any::Any;
+/// use typedef::{ registry, TypeDef };
+///
+/// registry::register_clone::<String>();
+/// registry::register_debug::<String>();
+/// registry::register_eq::<String>();
+///
+/// let value: Box<dyn Any> = Box::new(String::from("order"));
+/// let facets = registry::facets(&TypeDef::from_id((*value).type_id()).unwrap());
+///
+/// let copy = facets.clone_value(&*value).unwrap();
+///
+/// assert_eq!(facets.eq(&*value, &*copy), Some(true));
+/// assert_eq!(format!("{:?}", facets.debug(&*copy).unwrap()), "\"order\"");
+/// ```
+#[track_caller]
+pub fn register_clone<T: Any + Clone>() -> TypeDef {
+    GLOBAL.register_clone::<T>()
+}
+
+/// Register `Debug` of the type in its `Facets`, so that type-erased values
+/// of the type can be printed.
+#[track_caller]
+pub fn register_debug<T: Any + fmt::Debug>() -> TypeDef {
+    GLOBAL.register_debug::<T>()
+}
+
+/// Register `PartialEq` of the type in its `Facets`, so that type-erased
+/// values of the type can be compared.
+#[track_caller]
+pub fn register_eq<T: Any + PartialEq>() -> TypeDef {
+    GLOBAL.register_eq::<T>()
+}
+
+/// Get the functions registered for the type with `register_clone`,
+/// `register_debug` and `register_eq`.
+pub fn facets(typedef: &TypeDef) -> Facets {
+    GLOBAL.facets(typedef)
+}
+
+fn clone_any<T: Any + Clone>(value: &dyn Any) -> Option<Box<dyn Any>> {
+    value.downcast_ref::<T>().map(|value| Box::new(value.clone()) as Box<dyn Any>)
+}
+
+fn debug_any<T: Any + fmt::Debug>(value: &dyn Any, f: &mut fmt::Formatter) -> fmt::Result {
+    match value.downcast_ref::<T>() {
+        Some(value) => fmt::Debug::fmt(value, f),
+        None => Err(fmt::Error),
+    }
+}
+
+fn eq_any<T: Any + PartialEq>(left: &dyn Any, right: &dyn Any) -> Option<bool> {
+    Some(left.downcast_ref::<T>()? == right.downcast_ref::<T>()?)
+}
+
+/// Functions that clone, print and compare type-erased values of a type,
+/// returned by `facets`.
+///
+/// Each method returns `None` if the function is not registered for the type,
+/// or if a value is not of the type.
+#[derive(Clone, Copy, Default)]
+pub struct Facets {
+    id: Option<TypeId>,
+    clone: Option<CloneFn>,
+    debug: Option<DebugFn>,
+    eq: Option<EqFn>,
+}
+
+type CloneFn = fn(&dyn Any) -> Option<Box<dyn Any>>;
+type DebugFn = fn(&dyn Any, &mut fmt::Formatter) -> fmt::Result;
+type EqFn = fn(&dyn Any, &dyn Any) -> Option<bool>;
+
+impl Facets {
+    /// Clone the value.
+    pub fn clone_value(&self, value: &dyn Any) -> Option<Box<dyn Any>> {
+        (self.clone?)(value)
+    }
+
+    /// Get a wrapper that formats the value with `Debug`.
+    pub fn debug<'a>(&self, value: &'a dyn Any) -> Option<DebugValue<'a>> {
+        let debug = self.debug?;
+        if self.id != Some((*value).type_id()) {
+            return None;
+        }
+        Some(DebugValue { value, debug })
+    }
+
+    /// Compare the values with `PartialEq`.
+    pub fn eq(&self, left: &dyn Any, right: &dyn Any) -> Option<bool> {
+        (self.eq?)(left, right)
+    }
+
+    /// Check if `Clone` is registered.
+    pub fn has_clone(&self) -> bool {
+        self.clone.is_some()
+    }
+
+    /// Check if `Debug` is registered.
+    pub fn has_debug(&self) -> bool {
+        self.debug.is_some()
+    }
+
+    /// Check if `PartialEq` is registered.
+    pub fn has_eq(&self) -> bool {
+        self.eq.is_some()
+    }
+}
+
+impl fmt::Debug for Facets {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Facets")
+            .field("clone", &self.has_clone())
+            .field("debug", &self.has_debug())
+            .field("eq", &self.has_eq())
+            .finish()
+    }
+}
+
+/// Type-erased value formatted with the `Debug` function of its type,
+/// returned by `Facets::debug`.
+#[derive(Clone, Copy)]
+pub struct DebugValue<'a> {
+    value: &'a dyn Any,
+    debug: DebugFn,
+}
+
+impl<'a> fmt::Debug for DebugValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.debug)(self.value, f)
+    }
+}
+
+/// Record that the type implements a trait, given as the trait object type,
+/// as `register_impl::<Order, dyn Billable>()`.
+///
+/// The registry does not check that the type implements the trait, as this
+/// can not be expressed on stable Rust. The type is registered if it is not
+/// registered yet.
+///
+/// ```
+/// use typedef::{ registry, TypeDef };
+///
+/// trait Plugin {}
+///
+/// struct Resize;
+/// struct Crop;
+///
+/// impl Plugin for Resize {}
+/// impl Plugin for Crop {}
+///
+/// registry::register_impl::<Resize, dyn Plugin>();
+/// registry::register_impl::<Crop, dyn Plugin>();
+///
+/// assert!(TypeDef::of::<Resize>().implements::<dyn Plugin>());
+/// assert!(!TypeDef::of::<u8>().implements::<dyn Plugin>());
+/// assert_eq!(registry::implementors_of::<dyn Plugin>().len(), 2);
+/// ```
+#[track_caller]
+pub fn register_impl<T: ?Sized + Any, Trait: ?Sized + Any>() -> TypeDef {
+    GLOBAL.register_impl::<T, Trait>()
+}
+
+/// Check if the type was recorded with `register_impl` to implement the
+/// trait.
+pub fn implements<Trait: ?Sized + Any>(typedef: &TypeDef) -> bool {
+    GLOBAL.implements::<Trait>(typedef)
+}
+
+/// Get the registered types recorded with `register_impl` to implement the
+/// trait, in no specified order.
+pub fn implementors_of<Trait: ?Sized + Any>() -> Vec<TypeDef> {
+    GLOBAL.implementors_of::<Trait>()
+}
+
+/// Register the function that converts a reference to the type into a
+/// reference to a trait object, so that `cast` can convert type-erased
+/// values of the type. The implementation is also recorded as with
+/// `register_impl`.
+///
+/// The function is usually the coercion `|value| value`, which
+/// `register_cast!` writes for each trait.
+///
+/// ```
+/// use std::any::Any;
+/// use typedef::registry;
+///
+/// trait Area {
+///     fn area(&self) -> f64;
+/// }
+///
+/// struct Square(f64);
+///
+/// impl Area for Square {
+///     fn area(&self) -> f64 {
+///         self.0 * self.0
+///     }
+/// }
+///
+/// registry::register_cast::<Square, dyn Area>(|square| square);
+///
+/// let value: Box<dyn Any> = Box::new(Square(2.0));
+///
+/// assert_eq!(registry::cast::<dyn Area>(&*value).unwrap().area(), 4.0);
+/// assert!(registry::cast::<dyn Area>(&1u8).is_none());
+/// ```
+#[track_caller]
+pub fn register_cast<T: Any, Trait: ?Sized + Any>(cast: fn(&T) -> &Trait) -> TypeDef {
+    GLOBAL.register_cast(cast)
+}
+
+/// Convert a type-erased value into a trait object, if a conversion is
+/// registered with `register_cast` for the type of the value.
+pub fn cast<Trait: ?Sized + Any>(value: &dyn Any) -> Option<&Trait> {
+    GLOBAL.cast(value)
+}
+
+type Caster<Trait> = Box<dyn Fn(&dyn Any) -> Option<&Trait> + Send + Sync>;
+
+/// Function that converts a type-erased value into a value of another type,
+/// registered with `register_converter`.
+pub type Converter = fn(Box<dyn Any>) -> Box<dyn Any>;
+
+/// Register the function that converts values of the `from` type into values
+/// of the `to` type, replacing the function registered before. Both typedefs
+/// are registered if they are not registered yet.
+///
+/// The converter is only called with values of the `from` type, and should
+/// return a value of the `to` type.
+///
+/// ```
+/// use std::any::Any;
+/// use typedef::{ registry, TypeDef };
+///
+/// struct Celsius(f64);
+/// struct Fahrenheit(f64);
+///
+/// fn to_fahrenheit(value: Box<dyn Any>) -> Box<dyn Any> {
+///     let celsius = value.downcast::<Celsius>().unwrap();
+///     Box::new(Fahrenheit(celsius.0 * 1.8 + 32.0))
+/// }
+///
+/// registry::register_converter(TypeDef::of::<Celsius>(), TypeDef::of::<Fahrenheit>(), to_fahrenheit);
+///
+/// let converted = registry::convert(Box::new(Celsius(100.0)), &TypeDef::of::<Fahrenheit>()).ok().unwrap();
+///
+/// assert_eq!(converted.downcast_ref::<Fahrenheit>().unwrap().0, 212.0);
+/// ```
+pub fn register_converter(from: TypeDef, to: TypeDef, converter: Converter) {
+    GLOBAL.register_converter(from, to, converter)
+}
+
+/// Register the conversion with `Into` from one type to another, as
+/// `register_into::<u8, u32>()`.
+#[track_caller]
+pub fn register_into<From: Any + Into<To>, To: Any>() {
+    GLOBAL.register_into::<From, To>()
+}
+
+fn into_any<From: Any + Into<To>, To: Any>(value: Box<dyn Any>) -> Box<dyn Any> {
+    match value.downcast::<From>() {
+        Ok(value) => Box::new(Into::<To>::into(*value)),
+        Err(value) => value,
+    }
+}
+
+/// Get the function registered to convert values between the types.
+pub fn converter(from: &TypeDef, to: &TypeDef) -> Option<Converter> {
+    GLOBAL.converter(from, to)
+}
+
+/// Get the registered types that values of the type can be converted to, in
+/// no specified order.
+pub fn conversions_from(from: &TypeDef) -> Vec<TypeDef> {
+    GLOBAL.conversions_from(from)
+}
+
+/// Convert the value into a value of the `to` type with the registered
+/// converter for the type of the value.
+///
+/// A value that already has the `to` type is returned as it is. Returns the
+/// value back as the error if no converter is registered.
+pub fn convert(value: Box<dyn Any>, to: &TypeDef) -> Result<Box<dyn Any>, Box<dyn Any>> {
+    GLOBAL.convert(value, to)
+}
+
+/// Find the registered typedef of the type, or `None` if the type is not
+/// registered.
+pub fn lookup(id: TypeId) -> Option<TypeDef> {
+    GLOBAL.lookup(id)
+}
+
+/// Find the registered typedef by the name of the type, or `None` if no
+/// registered type has this name.
+///
+/// A name that is exactly the name of a registered type, as returned by
+/// `TypeDef::name`, finds that type first. This includes types that were
+/// not added to the canonical names because of a collision, if no other
+/// type has the same exact name. Otherwise both the name and the names of
+/// the registered types are compared in the canonical form, as returned by
+/// `canonicalize`, so
+/// `alloc::string::String`, `std::string::String` and
+/// `std::string::String` printed by an older compiler find the same type.
+/// Paths are not shortened, so `String` alone does not.
+///
+/// Names registered with `register_alias` are also found.
+pub fn lookup_name(name: &str) -> Option<TypeDef> {
+    GLOBAL.lookup_name(name)
+}
+
+/// Iterate over all registered typedefs.
+///
+/// The iterator goes over a copy of the registry taken when `iter` is
+/// called, so types registered later are not included, and the registry is
+/// not locked while iterating. The order of the typedefs is not specified.
+///
+/// ```
+/// use typedef::{ registry, TypeDef };
+///
+/// registry::register::<u8>();
+/// registry::register::<String>();
+///
+/// let types: Vec<TypeDef> = registry::iter().collect();
+///
+/// assert!(types.contains(&TypeDef::of::<u8>()));
+/// assert!(types.contains(&TypeDef::of::<String>()));
+/// ```
+pub fn iter() -> impl Iterator<Item = TypeDef> {
+    GLOBAL.iter()
+}
+
+/// Write all registered types as a JSON document, for build tools and
+/// services that read the types known to the binary.
+///
+/// This is the same as `snapshot().to_json()`. The document is an object
+/// with a `types` array, sorted by name. Each type has:
+///
+/// - `name`: the name that finds the type with `lookup_name`.
+/// - `stable_hash`: the hash of `TypeDef::stable_hash64`, computed over the
+///   canonical name so that it does not change with the compiler, as 16
+///   hexadecimal digits, unless this crate is compiled with
+///   `features = ["id-only"]`.
+/// - `display_name`: the name set with `set_display_name`, if any.
+/// - `aliases`: the names registered with `register_alias`, if any.
+/// - `location`: where the type was registered, as `file:line:column`, if
+///   it is known.
+///
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
+/// use typedef::{ registry, TypeDef, NamedType };
+///
+/// struct Order;
+///
+/// impl NamedType for Order {
+///     const NAME: &'static str = "shop::Order";
+/// }
+///
+/// registry::register_typedef(TypeDef::of_named::<Order>());
+/// registry::register_alias::<Order>("old_shop::Order");
+///
+/// let json = registry::export_json();
+/// let hash = TypeDef::of::<Order>().stable_hash64();
+///
+/// assert!(json.starts_with("{\"types\": [\n"));
+/// assert!(json.contains(&format!(
+///     "{{\"name\": \"shop::Order\", \"stable_hash\": \"{:016x}\", \"aliases\": [\"old_shop::Order\"]}}",
+///     hash
+/// )));
+/// # }
+/// ```
+pub fn export_json() -> String {
+    GLOBAL.export_json()
+}
+
+/// Copy the registered types into a snapshot, which can be written with
+/// `RegistrySnapshot::to_json` and compared with other snapshots.
+pub fn snapshot() -> RegistrySnapshot {
+    GLOBAL.snapshot()
+}
+
+/// Add the names and aliases of the types in the snapshot as aliases of the
+/// registered types they find, and return how many types of the snapshot
+/// were found.
+///
+/// A type of the snapshot finds a registered type by its name or one of its
+/// aliases, as with `lookup_name`. This keeps names that a type had in an
+/// earlier build, whose snapshot recorded them as aliases, working in the
+/// current build. Display names of the snapshot are not applied.
+///
+/// ```
+/// use typedef::{ registry, TypeDef };
+/// use typedef::registry::RegistrySnapshot;
+///
+/// struct Order;
+///
+/// registry::register::<Order>();
+///
+/// let name = TypeDef::name_of::<Order>();
+/// let json = format!("{{\"types\": [{{\"name\": \"old::Order\", \"aliases\": [\"{}\"]}}]}}", name);
+/// let snapshot = RegistrySnapshot::from_json(&json).unwrap();
+///
+/// assert_eq!(registry::merge(&snapshot), 1);
+/// assert!(TypeDef::from_name("old::Order").unwrap().is::<Order>());
+/// ```
+pub fn merge(snapshot: &RegistrySnapshot) -> usize {
+    GLOBAL.merge(snapshot)
+}
+
+/// Count the registered types, by crate and by module, and the aliases and
+/// collisions, for example to log what types a program knows about when it
+/// starts.
+///
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
+/// use typedef::registry;
+///
+/// mod shapes {
+///     pub struct Circle;
+///     pub struct Square;
+/// }
+///
+/// registry::register::<shapes::Circle>();
+/// registry::register::<shapes::Square>();
+///
+/// let stats = registry::stats();
+///
+/// assert_eq!(stats.crates[module_path!()], 2);
+/// assert!(stats.to_string().starts_with("types: 2, aliases: 0, collisions: 0\n"));
+/// # }
+/// ```
+pub fn stats() -> RegistryStats {
+    GLOBAL.stats()
+}
+
+/// Counts of the types of a registry, created with `stats`.
+///
+/// `Display` writes the totals on one line, followed by a line for each crate
+/// and, indented under it, for each of its modules.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegistryStats {
+    /// Number of registered types.
+    pub types: usize,
+    /// Number of names registered with `register_alias` or `merge`.
+    pub aliases: usize,
+    /// Number of collisions, as returned by `collisions`.
+    pub collisions: usize,
+    /// Number of types by the crate that defines them, as returned by
+    /// `TypeDef::crate_name`. Types without a crate name are not counted.
+    ///
+    /// This is empty if this crate is compiled with `features = ["id-only"]`.
+    pub crates: BTreeMap<String, usize>,
+    /// Number of types by the module that defines them, given with the crate
+    /// name, as `"my_crate::plugins"`. Types at the crate root are only
+    /// counted in `crates`.
+    ///
+    /// This is empty if this crate is compiled with `features = ["id-only"]`.
+    pub modules: BTreeMap<String, usize>,
+}
+
+impl fmt::Display for RegistryStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "types: {}, aliases: {}, collisions: {}", self.types, self.aliases, self.collisions)?;
+        for (krate, count) in &self.crates {
+            write!(f, "\n  {}: {}", krate, count)?;
+            let modules = self
+                .modules
+                .iter()
+                .filter(|module| module.0.starts_with(krate.as_str()) && module.0[krate.len()..].starts_with("::"));
+            for (module, count) in modules {
+                write!(f, "\n    {}: {}", module, count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Types of a registry that are written to or read from JSON, as the
+/// registered types of a build or of another process.
+///
+/// Types are found by their canonical names, not by `TypeId`, which is
+/// different in each build.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegistrySnapshot {
+    types: Vec<SnapshotType>,
+}
+
+/// Type in a `RegistrySnapshot`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotType {
+    /// Canonical name of the type, as it is found by `lookup_name`.
+    pub name: String,
+    /// Stable hash of the canonical type name, if it is known.
+    pub stable_hash: Option<u64>,
+    /// Name set with `set_display_name`, if any.
+    pub display_name: Option<String>,
+    /// Canonical names registered with `register_alias`.
+    pub aliases: Vec<String>,
+    /// Where the type was registered, as `file:line:column`, if it is known.
+    pub location: Option<String>,
+}
+
+/// Differences between two snapshots, returned by `RegistrySnapshot::compare`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    /// Types of the other snapshot that are not in this one.
+    pub added: Vec<SnapshotType>,
+    /// Types of this snapshot that are not in the other one.
+    pub removed: Vec<SnapshotType>,
+    /// Types found in both snapshots with a different stable hash, with the
+    /// type of this snapshot first.
+    pub changed: Vec<(SnapshotType, SnapshotType)>,
+}
+
+impl SnapshotDiff {
+    /// Check if the snapshots have the same types.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl RegistrySnapshot {
+    /// Create an empty snapshot.
+    pub fn new() -> RegistrySnapshot {
+        RegistrySnapshot::default()
+    }
+
+    /// Read a snapshot written by `to_json` or `export_json`.
+    ///
+    /// Only `name` is required for each type. Names and aliases are
+    /// canonicalized, so snapshots written by builds with other compilers can
+    /// be compared with this one. Fields that are not known are ignored.
+    ///
+    /// ```
+    /// use typedef::registry::RegistrySnapshot;
+    ///
+    /// let snapshot = RegistrySnapshot::from_json(r#"{"types": [
+    ///     {"name": "alloc::vec::Vec<u8>", "stable_hash": "00000000000000ff"},
+    ///     {"name": "shop::Order", "aliases": ["old_shop::Order"]}
+    /// ]}"#).unwrap();
+    ///
+    /// assert_eq!(snapshot.get("std::vec::Vec<u8>").unwrap().stable_hash, Some(0xff));
+    /// assert_eq!(snapshot.get("old_shop::Order").unwrap().name, "shop::Order");
+    ///
+    /// let error = RegistrySnapshot::from_json(r#"{"types": [{}]}"#).unwrap_err();
+    ///
+    /// assert_eq!(error.to_string(), "invalid registry snapshot at byte 11: expected a type with a `name`");
+    /// ```
+    pub fn from_json(input: &str) -> Result<RegistrySnapshot, SnapshotError> {
+        let document = json::parse(input).map_err(|error| SnapshotError {
+            message: error.message,
+            offset: error.offset,
+        })?;
+        fn string(field: &json::Item) -> Result<&str, SnapshotError> {
+            field.value.as_str().ok_or(SnapshotError { message: "expected a string", offset: field.offset })
+        }
+
+        let invalid = |message, offset| SnapshotError { message, offset };
+        let items = match document.get("types") {
+            Some(types) => types.value.as_array().ok_or_else(|| invalid("expected a `types` array", types.offset))?,
+            None => return Err(invalid("expected an object with a `types` array", 0)),
+        };
+
+        let mut types = Vec::with_capacity(items.len());
+        for item in items {
+            let field = |key| item.value.get(key).filter(|field| field.value != json::Value::Null);
+            let optional_string = |key| field(key).map(string).transpose();
+
+            let name = optional_string("name")?.ok_or_else(|| invalid("expected a type with a `name`", item.offset))?;
+            let stable_hash = match field("stable_hash") {
+                Some(hash) => Some(
+                    u64::from_str_radix(string(hash)?, 16)
+                        .map_err(|_| invalid("expected a hexadecimal `stable_hash`", hash.offset))?,
+                ),
+                None => None,
+            };
+            let aliases = match field("aliases") {
+                None => Vec::new(),
+                Some(aliases) => aliases
+                    .value
+                    .as_array()
+                    .ok_or_else(|| invalid("expected an array of `aliases`", aliases.offset))?
+                    .iter()
+                    .map(|alias| string(alias).map(canonical_lookup_name))
+                    .collect::<Result<Vec<_>, _>>()?,
+            };
+
+            types.push(SnapshotType {
+                name: canonical_lookup_name(name),
+                stable_hash,
+                display_name: optional_string("display_name")?.map(String::from),
+                aliases,
+                location: optional_string("location")?.map(String::from),
+            });
+        }
+        types.sort_by(|left, right| left.name.cmp(&right.name));
+        Ok(RegistrySnapshot { types })
+    }
+
+    /// Write the snapshot as JSON, in the format described in `export_json`.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\"types\": [\n");
+        for (i, ty) in self.types.iter().enumerate() {
+            out.push_str("  {\"name\": ");
+            json::push_string(&mut out, &ty.name);
+            if let Some(hash) = ty.stable_hash {
+                out.push_str(&format!(", \"stable_hash\": \"{:016x}\"", hash));
+            }
+            if let Some(ref display_name) = ty.display_name {
+                out.push_str(", \"display_name\": ");
+                json::push_string(&mut out, display_name);
+            }
+            if !ty.aliases.is_empty() {
+                out.push_str(", \"aliases\": [");
+                for (j, alias) in ty.aliases.iter().enumerate() {
+                    if j > 0 {
+                        out.push_str(", ");
+                    }
+                    json::push_string(&mut out, alias);
+                }
+                out.push(']');
+            }
+            if let Some(ref location) = ty.location {
+                out.push_str(", \"location\": ");
+                json::push_string(&mut out, location);
+            }
+            out.push('}');
+            if i + 1 < self.types.len() {
+                out.push(',');
+            }
+            out.push('\n');
+        }
+        out.push_str("]}\n");
+        out
+    }
+
+    /// Get the types of the snapshot, sorted by name.
+    pub fn types(&self) -> &[SnapshotType] {
+        &self.types
+    }
+
+    /// Find a type by its name or one of its aliases, compared in the
+    /// canonical form.
+    pub fn get(&self, name: &str) -> Option<&SnapshotType> {
+        let name = canonical_lookup_name(name);
+        self.types
+            .iter()
+            .find(|ty| ty.name == name)
+            .or_else(|| self.types.iter().find(|ty| ty.aliases.contains(&name)))
+    }
+
+    /// Add the types of the other snapshot that are not in this one, and the
+    /// aliases of the types in both that this snapshot does not have.
+    ///
+    /// Types are matched by name. Other fields of the types in this snapshot
+    /// are kept.
+    pub fn merge(&mut self, other: &RegistrySnapshot) {
+        for ty in &other.types {
+            match self.types.iter_mut().find(|existing| existing.name == ty.name) {
+                Some(existing) => {
+                    for alias in &ty.aliases {
+                        if !existing.aliases.contains(alias) {
+                            existing.aliases.push(alias.clone());
+                        }
+                    }
+                }
+                None => self.types.push(ty.clone()),
+            }
+        }
+        self.types.sort_by(|left, right| left.name.cmp(&right.name));
+    }
+
+    /// Compare the types of both snapshots by name.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::{ registry, TypeDef };
+    /// use typedef::registry::RegistrySnapshot;
+    ///
+    /// registry::register::<u8>();
+    ///
+    /// let previous = RegistrySnapshot::from_json(r#"{"types": [{"name": "u8"}, {"name": "shop::Order"}]}"#).unwrap();
+    /// let diff = previous.compare(&registry::snapshot());
+    ///
+    /// assert_eq!(diff.removed.len(), 1);
+    /// assert_eq!(diff.removed[0].name, "shop::Order");
+    /// assert!(!diff.added.iter().any(|ty| ty.name == "u8"));
+    /// # }
+    /// ```
+    ///
+    /// Stable hashes are only compared if both types have them.
+    pub fn compare(&self, other: &RegistrySnapshot) -> SnapshotDiff {
+        let mut diff = SnapshotDiff::default();
+        for ty in &self.types {
+            match other.types.iter().find(|other| other.name == ty.name) {
+                Some(other) => {
+                    if let (Some(left), Some(right)) = (ty.stable_hash, other.stable_hash) {
+                        if left != right {
+                            diff.changed.push((ty.clone(), other.clone()));
+                        }
+                    }
+                }
+                None => diff.removed.push(ty.clone()),
+            }
+        }
+        for ty in &other.types {
+            if !self.types.iter().any(|existing| existing.name == ty.name) {
+                diff.added.push(ty.clone());
+            }
+        }
+        diff
+    }
+}
+
+/// Error returned by `RegistrySnapshot::from_json`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnapshotError {
+    message: &'static str,
+    offset: usize,
+}
+
+impl SnapshotError {
+    /// Get the byte offset in the input where the error was found.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid registry snapshot at byte {}: {}", self.offset, self.message)
+    }
+}
+
+impl Error for SnapshotError {}
+
+/// Start a query of the registered types.
+///
+/// Each filter added to the query narrows the types it returns, and
+/// `Query::iter` goes over the registered types that pass all of them.
+///
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
+/// use typedef::{ registry, TypeDef };
+///
+/// mod plugins {
+///     pub struct Resize;
+///     pub struct Crop;
+/// }
+///
+/// registry::register::<plugins::Resize>();
+/// registry::register::<plugins::Crop>();
+/// registry::register::<Vec<plugins::Crop>>();
+///
+/// let mut found: Vec<_> = registry::query()
+///     .module(module_path!())
+///     .filter(|typedef| !typedef.is_generic())
+///     .iter()
+///     .map(|typedef| typedef.short_name())
+///     .collect();
+/// found.sort();
+///
+/// assert_eq!(found, ["Crop", "Resize"]);
+/// # }
+/// ```
+pub fn query<'a>() -> Query<'a> {
+    Query::new()
+}
+
+/// Filters of registered types, created with `query`.
+pub struct Query<'a> {
+    registry: &'a Registry,
+    #[cfg(not(feature = "id-only"))]
+    krate: Option<&'a str>,
+    #[cfg(not(feature = "id-only"))]
+    module: Option<&'a str>,
+    pattern: Option<&'a str>,
+    glob: Option<&'a str>,
+    #[cfg(feature = "regex")]
+    regex: Option<&'a TypeRegex>,
+    predicates: Vec<Predicate<'a>>,
+}
+
+type Factory = Arc<dyn Fn() -> Box<dyn Any> + Send + Sync>;
+
+type Predicate<'a> = Box<dyn Fn(&TypeDef) -> bool + 'a>;
+
+impl<'a> Default for Query<'a> {
+    fn default() -> Query<'a> {
+        Query::new()
+    }
+}
+
+impl<'a> Query<'a> {
+    /// Create a query that returns all types of the global registry.
+    pub fn new() -> Query<'a> {
+        Query::in_registry(&GLOBAL)
+    }
+
+    /// Create a query that returns all types of the registry.
+    pub fn in_registry(registry: &'a Registry) -> Query<'a> {
+        Query {
+            registry,
+            #[cfg(not(feature = "id-only"))]
+            krate: None,
+            #[cfg(not(feature = "id-only"))]
+            module: None,
+            pattern: None,
+            glob: None,
+            #[cfg(feature = "regex")]
+            regex: None,
+            predicates: Vec::new(),
+        }
+    }
+
+    /// Keep types defined in the crate, as `"my_crate"`.
+    ///
+    /// This is not available if this crate is compiled with `features = ["id-only"]`
+    #[cfg(not(feature = "id-only"))]
+    pub fn crate_name(mut self, krate: &'a str) -> Query<'a> {
+        self.krate = Some(krate);
+        self
+    }
+
+    /// Keep types defined in the module or in its submodules, given with the
+    /// crate name, as `"my_crate::plugins"`. A prefix without `::` keeps the
+    /// types of the crate.
+    ///
+    /// This is not available if this crate is compiled with `features = ["id-only"]`
+    #[cfg(not(feature = "id-only"))]
+    pub fn module(mut self, prefix: &'a str) -> Query<'a> {
+        self.module = Some(prefix);
+        self
+    }
+
+    /// Keep types that match the pattern, as `TypeDef::matches`.
+    pub fn pattern(mut self, pattern: &'a str) -> Query<'a> {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    /// Keep types that match the glob, as `TypeDef::matches_glob`.
+    pub fn glob(mut self, glob: &'a str) -> Query<'a> {
+        self.glob = Some(glob);
+        self
+    }
+
+    /// Keep types whose canonical names match the regular expression, as
+    /// `TypeRegex::matches`.
+    ///
+    /// This is only available if this crate is compiled with `features = ["regex"]`.
+    #[cfg(feature = "regex")]
+    pub fn regex(mut self, regex: &'a TypeRegex) -> Query<'a> {
+        self.regex = Some(regex);
+        self
+    }
+
+    /// Keep types for which the predicate returns `true`. Predicates are
+    /// called after the other filters, in the order they were added.
+    pub fn filter<F: Fn(&TypeDef) -> bool + 'a>(mut self, predicate: F) -> Query<'a> {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    /// Check if the typedef passes all filters of the query.
+    pub fn matches(&self, typedef: &TypeDef) -> bool {
+        #[cfg(not(feature = "id-only"))]
+        {
+            if let Some(krate) = self.krate {
+                if typedef.crate_name() != Some(krate) {
+                    return false;
+                }
+            }
+            if let Some(prefix) = self.module {
+                if !in_module(typedef, prefix) {
+                    return false;
+                }
+            }
+        }
+        if let Some(pattern) = self.pattern {
+            if !typedef.matches(pattern) {
+                return false;
+            }
+        }
+        if let Some(glob) = self.glob {
+            if !typedef.matches_glob(glob) {
+                return false;
+            }
+        }
+        #[cfg(feature = "regex")]
+        {
+            if let Some(regex) = self.regex {
+                if !regex.matches(typedef) {
+                    return false;
+                }
+            }
+        }
+        self.predicates.iter().all(|predicate| predicate(typedef))
+    }
+
+    /// Iterate over the registered types that pass all filters, over a copy
+    /// of the registry as `iter` does.
+    pub fn iter(self) -> impl Iterator<Item = TypeDef> + 'a {
+        self.registry.iter().filter(move |typedef| self.matches(typedef))
+    }
+}
+
+#[cfg(not(feature = "id-only"))]
+fn in_module(typedef: &TypeDef, prefix: &str) -> bool {
+    let (krate, module) = match prefix.find("::") {
+        Some(pos) => (&prefix[..pos], Some(&prefix[pos + 2..])),
+        None => (prefix, None),
+    };
+    if typedef.crate_name() != Some(krate) {
+        return false;
+    }
+    match (module, typedef.module_path()) {
+        (None, _) => true,
+        (Some(prefix), Some(path)) => path == prefix || path.starts_with(prefix) && path[prefix.len()..].starts_with("::"),
+        (Some(_), None) => false,
+    }
+}
+
+/// Find the registered types whose short or canonical names match the text,
+/// ignoring case, with the best matches first, for tools where the type is
+/// typed in by hand. The display name set with `set_display_name` is used
+/// as the short name.
+///
+/// Names that contain the text match better than short names that only
+/// contain its characters in the same order, as `"hmap"` in `HashMap`; the
+/// paths of canonical names are too long to match that way. Short names that
+/// are equal to the text or start with it come first. Matches of the
+/// same kind are sorted by the length of the short name.
+///
+/// ```
+/// # #[cfg(not(feature = "id-only"))] {
+/// use std::collections::{ BTreeMap, HashMap };
+/// use typedef::{ registry, TypeDef };
+///
+/// registry::register::<HashMap<String, u8>>();
+/// registry::register::<BTreeMap<String, u8>>();
+/// registry::register::<Vec<u8>>();
+///
+/// let found = registry::search("map");
+///
+/// assert_eq!(found.len(), 2);
+/// assert!(found[0].is::<HashMap<String, u8>>());
+/// assert!(registry::search("hashmap")[0].is::<HashMap<String, u8>>());
+/// assert!(registry::search("hmap")[0].is::<HashMap<String, u8>>());
+/// assert!(registry::search("xyz").is_empty());
+/// # }
+/// ```
+pub fn search(text: &str) -> Vec<TypeDef> {
+    GLOBAL.search(text)
+}
+
+/// Get how well the lowercase names match the lowercase text, from `0` for
+/// the best match, or `None` if they do not match.
+fn search_rank(text: &str, short: &str, name: &str) -> Option<u8> {
+    if short == text {
+        Some(0)
+    } else if short.starts_with(text) {
+        Some(1)
+    } else if short.contains(text) {
+        Some(2)
+    } else if name.contains(text) {
+        Some(3)
+    } else if is_subsequence(text, short) {
+        Some(4)
+    } else {
+        None
+    }
+}
+
+fn is_subsequence(text: &str, name: &str) -> bool {
+    let mut chars = name.chars();
+    text.chars().all(|c| chars.any(|other| other == c))
+}
+
+/// Error returned when parsing a name of a type that is not registered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownTypeName {
+    name: String,
+}
+
+impl UnknownTypeName {
+    /// Get the name that was not found.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for UnknownTypeName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "type `{}` is not registered", self.name)
+    }
+}
+
+impl Error for UnknownTypeName {}
+
+pub(crate) fn parse_name(name: &str) -> Result<TypeDef, UnknownTypeName> {
+    lookup_name(name).ok_or_else(|| UnknownTypeName { name: name.into() })
+}
+
+/// Register conversions of the type into trait objects with
+/// `registry::register_cast`, as `register_cast!(Square => dyn Area, dyn Debug)`.
+///
+/// The type must implement every trait. This is not available if this crate
+/// is compiled without the `std` feature.
+///
+/// ```
+/// #[macro_use]
+/// extern crate typedef;
+///
+/// use std::any::Any;
+/// use std::fmt::Debug;
+/// use typedef::{ registry, TypeDef };
+///
+/// trait Plugin {
+///     fn name(&self) -> &'static str;
+/// }
+///
+/// #[derive(Debug)]
+/// struct Resize;
+///
+/// impl Plugin for Resize {
+///     fn name(&self) -> &'static str {
+///         "resize"
+///     }
+/// }
+///
+/// fn main() {
+///     register_cast!(Resize => dyn Plugin, dyn Debug);
+///
+///     let value: &dyn Any = &Resize;
+///
+///     assert_eq!(registry::cast::<dyn Plugin>(value).unwrap().name(), "resize");
+///     assert_eq!(format!("{:?}", registry::cast::<dyn Debug>(value).unwrap()), "Resize");
+///     assert!(TypeDef::of::<Resize>().implements::<dyn Debug>());
+/// }
+/// ```
+#[macro_export]
+macro_rules! register_cast {
+    ($ty:ty => $($trait_object:ty),+ $(,)*) => {
+        $( $crate::registry::register_cast::<$ty, $trait_object>(|value| value); )+
+    };
+}
+
+/// Register types at startup, before `main` runs.
+///
+/// The macro places a function in the section of the executable that holds
+/// the constructors of the platform, which registers the types with
+/// `registry::register_typedef`. It is used at the module level, and supports
+/// Linux, Android, the BSDs, macOS, iOS and Windows.
+///
+/// This is only available if this crate is compiled with
+/// `features = ["ctor"]`. With `features = ["link"]` as well, the macro of the
+/// `link` module is used instead.
+///
+/// ```
+/// #[macro_use]
+/// extern crate typedef;
+///
+/// use typedef::TypeDef;
+///
+/// mod my_crate {
+///     pub struct Foo;
+///     pub struct Bar;
+/// }
+///
+/// register_type!(my_crate::Foo, my_crate::Bar);
+///
+/// fn main() {
+///     assert!(TypeDef::from_name(&TypeDef::name_of::<my_crate::Foo>()).is_some());
+/// }
+/// ```
+#[cfg(all(feature = "ctor", not(feature = "link")))]
+#[macro_export]
+macro_rules! register_type {
+    (@typedefs $($typedef:expr),+) => {
+        const _: () = {
+            #[cfg(not(any(
+                target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "netbsd",
+                target_os = "openbsd", target_os = "dragonfly", target_os = "macos", target_os = "ios", windows,
+            )))]
+            compile_error!("`register_type!` does not support this platform");
+
+            #[used]
+            #[cfg_attr(
+                any(
+                    target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "netbsd",
+                    target_os = "openbsd", target_os = "dragonfly",
+                ),
+                link_section = ".init_array"
+            )]
+            #[cfg_attr(any(target_os = "macos", target_os = "ios"), link_section = "__DATA,__mod_init_func")]
+            #[cfg_attr(windows, link_section = ".CRT$XCU")]
+            static REGISTER: extern "C" fn() = {
+                extern "C" fn register() {
+                    $( $crate::registry::register_typedef($typedef); )+
+                }
+                register
+            };
+        };
+    };
+    ($($ty:ty),+ $(,)*) => {
+        $crate::register_type!(@typedefs $($crate::TypeDef::of_traced::<$ty>()),+);
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use core::any::{Any, TypeId};
+    use super::{
+        collisions, display_name, iter, lookup, lookup_name, register, register_alias, register_typedef,
+        attach, cast, construct, conversions_from, convert, converter, register_into, facets, register_cast, implementors_of, implements, register_impl, register_clone, register_debug, register_eq, merge, metadata, register_default, register_factory, set_display_name, snapshot, try_register_typedef, CollisionKind, RegistrySnapshot,
+    };
+    #[cfg(not(feature = "id-only"))]
+    use super::{export_json, query};
+    use super::{global, Query, Registry};
+    use {NamedType, TypeDef};
+
+    #[cfg(feature = "ctor")]
+    struct Startup;
+
+    #[cfg(feature = "ctor")]
+    register_type!(Startup, Vec<Startup>);
+
+    #[test]
+    #[cfg(feature = "ctor")]
+    fn should_register_types_at_startup() {
+        assert!(lookup(TypeId::of::<Startup>()).is_some());
+        assert!(lookup(TypeId::of::<Vec<Startup>>()).is_some());
+    }
+
+    #[test]
+    fn should_look_up_registered_types() {
+        struct Registered;
+        struct NotRegistered;
+
+        let typedef = register::<Registered>();
+        assert_eq!(lookup(TypeId::of::<Registered>()), Some(typedef));
+        assert_eq!(lookup(TypeId::of::<NotRegistered>()), None);
+
+        let value: &dyn Any = &Registered;
+        assert!(TypeDef::from_id(value.type_id()).unwrap().is::<Registered>());
+    }
+
+    #[test]
+    fn should_register_and_look_up_from_many_threads() {
+        use std::thread;
+
+        struct Shared<T>(T);
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                thread::spawn(|| {
+                    register::<Shared<u8>>();
+                    register::<Shared<u16>>();
+                    register::<Shared<u32>>();
+                    (0..100).all(|_| lookup(TypeId::of::<Shared<u16>>()).is_some())
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            assert!(thread.join().unwrap());
+        }
+        assert_eq!(iter().filter(|typedef| typedef.is::<Shared<u32>>()).count(), 1);
+    }
+
+    #[test]
+    fn should_keep_types_of_registry_instances_apart() {
+        struct Local;
+        struct Other;
+
+        let first = Registry::new();
+        let second = Registry::default();
+        let typedef = first.register::<Local>();
+        second.register::<Other>();
+        first.register_default::<Vec<u8>>();
+
+        assert_eq!(first.lookup(TypeId::of::<Local>()), Some(typedef));
+        assert_eq!(second.lookup(TypeId::of::<Local>()), None);
+        assert_eq!(lookup(TypeId::of::<Local>()), None);
+        assert_eq!(first.iter().count(), 2);
+        assert_eq!(Query::in_registry(&second).iter().collect::<Vec<_>>(), [TypeDef::of::<Other>()]);
+        assert!(first.construct(&TypeDef::name_of::<Vec<u8>>()).is_some());
+        assert!(second.construct(&TypeDef::name_of::<Vec<u8>>()).is_none());
+        assert!(global().lookup(TypeId::of::<Other>()).is_none());
+    }
+
+    #[test]
+    fn should_count_types_of_registry() {
+        mod shapes {
+            pub struct Circle;
+        }
+
+        struct Root;
+
+        let registry = Registry::new();
+        registry.register::<shapes::Circle>();
+        registry.register::<Root>();
+        registry.register::<u8>();
+        registry.register_alias::<u8>("byte");
+        let stats = registry.stats();
+
+        assert_eq!((stats.types, stats.aliases, stats.collisions), (3, 1, 0));
+        #[cfg(not(feature = "id-only"))]
+        {
+            let root = format!("typedef::{}", TypeDef::of::<Root>().module_path().unwrap());
+            let module = format!("{}::shapes", root);
+            assert_eq!(stats.crates["typedef"], 2);
+            assert_eq!(stats.modules.get(&module), Some(&1));
+            assert_eq!(stats.modules.get(&root), Some(&1));
+            assert_eq!(
+                stats.to_string(),
+                format!("types: 3, aliases: 1, collisions: 0\n  typedef: 2\n    {}: 1\n    {}: 1", root, module)
+            );
+        }
+        #[cfg(feature = "id-only")]
+        assert_eq!(stats.to_string(), "types: 3, aliases: 1, collisions: 0");
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_rank_types_found_by_search() {
+        struct Order;
+        struct OrderLine;
+        struct BackOrder;
+        struct Other;
+
+        let registry = Registry::new();
+        registry.register::<OrderLine>();
+        registry.register::<BackOrder>();
+        registry.register::<Order>();
+        registry.register::<Other>();
+
+        let found: Vec<TypeId> = registry.search("ORDER").iter().map(TypeDef::id).collect();
+        assert_eq!(found, [TypeId::of::<Order>(), TypeId::of::<OrderLine>(), TypeId::of::<BackOrder>()]);
+
+        let found: Vec<TypeId> = registry.search("oline").iter().map(TypeDef::id).collect();
+        assert_eq!(found, [TypeId::of::<OrderLine>()]);
+
+        assert_eq!(registry.search("registry::test").len(), 4);
+        assert_eq!(registry.search("").len(), 4);
+        assert!(registry.search("orderz").is_empty());
+    }
+
+    #[test]
+    fn should_search_display_names_of_the_registry() {
+        struct Local;
+        struct Global;
+
+        let registry = Registry::new();
+        registry.set_display_name::<Local>("Sale");
+        registry.register::<Global>();
+        set_display_name::<Global>("Purchase");
+
+        assert_eq!(registry.search("sale").iter().map(TypeDef::id).collect::<Vec<_>>(), [TypeId::of::<Local>()]);
+        assert!(registry.search("purchase").is_empty());
+        assert!(super::search("sale").is_empty());
+    }
+
+    #[test]
+    fn should_iterate_over_snapshot_of_registered_types() {
+        struct First;
+        struct Second;
+
+        register::<First>();
+        let types = iter();
+        register::<Second>();
+        let types: Vec<_> = types.collect();
+
+        assert!(types.iter().any(|typedef| typedef.is::<First>()));
+        assert!(!types.iter().any(|typedef| typedef.is::<Second>()));
+        assert!(iter().any(|typedef| typedef.is::<Second>()));
+    }
+
+    #[cfg(not(feature = "id-only"))]
+    mod plugins {
+        pub mod image {
+            pub struct Resize;
+        }
+        pub struct Crop;
+    }
+    #[cfg(not(feature = "id-only"))]
+    mod plugins_extra {
+        pub struct Blur;
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_query_registered_types() {
+        register::<plugins::image::Resize>();
+        register::<plugins::Crop>();
+        register::<plugins_extra::Blur>();
+        register::<Option<plugins::Crop>>();
+
+        let names = |query: Query| {
+            let mut names: Vec<_> = query.iter().map(|typedef| typedef.ident().unwrap()).collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(query().module("typedef::registry::test::plugins")), ["Crop", "Resize"]);
+        assert_eq!(names(query().module("typedef::registry::test::plugins::image")), ["Resize"]);
+        assert_eq!(names(query().module("typedef::registry::test::plugins_extra")), ["Blur"]);
+        assert_eq!(names(query().crate_name("typedef").pattern("Crop")), ["Crop"]);
+        assert_eq!(names(query().pattern("Option<_>").glob("*::plugins::*")), ["Option"]);
+        assert_eq!(
+            names(query().glob("*::plugins*").filter(|typedef| typedef.ident() != Some("Crop"))),
+            ["Blur", "Option", "Resize"]
+        );
+        #[cfg(feature = "regex")]
+        {
+            let regex = ::TypeRegex::new(r"::plugins(_extra)?::(image::)?[BR]\w+$").unwrap();
+            assert_eq!(names(query().regex(&regex)), ["Blur", "Resize"]);
+        }
+    }
+
+    #[test]
+    fn should_look_up_exact_names_first() {
+        struct Compact;
+        struct Spaced;
+
+        impl NamedType for Compact {
+            const NAME: &'static str = "shop::Pair<u8,u8>";
+        }
+
+        impl NamedType for Spaced {
+            const NAME: &'static str = "shop::Pair<u8, u8>";
+        }
+
+        let registry = Registry::new();
+        registry.register_typedef(TypeDef::of_named::<Compact>());
+        registry.register_typedef(TypeDef::of_named::<Spaced>());
+
+        assert_eq!(registry.collisions().len(), 1);
+        assert!(registry.lookup_name("shop::Pair<u8,u8>").unwrap().is::<Compact>());
+        assert!(registry.lookup_name("shop::Pair<u8, u8>").unwrap().is::<Spaced>());
+        assert!(registry.lookup_name("shop::Pair<u8,  u8>").unwrap().is::<Compact>());
+
+        #[cfg(not(feature = "id-only"))]
+        {
+            let tagged = TypeDef::of_tagged::<u64, Compact>();
+            registry.register_typedef(tagged);
+            assert_eq!(registry.lookup_name(&format!("u64 @ {}", tagged.tag_name().unwrap())), Some(tagged));
+        }
+    }
+
+    #[test]
+    fn should_look_up_aliases() {
+        struct Current;
+        struct Other;
+
+        impl NamedType for Current {
+            const NAME: &'static str = "my_crate::Current";
+        }
+
+        register_alias::<Current>("old_crate::Old<&'static  str>");
+        assert!(lookup_name("old_crate::Old<&str>").unwrap().is::<Current>());
+
+        register_typedef(TypeDef::of_named::<Current>());
+        assert_eq!(lookup_name("old_crate::Old<&str>").unwrap().name(), "my_crate::Current");
+
+        register::<Other>();
+        register_alias::<Other>("my_crate::Current");
+        assert!(lookup_name("my_crate::Current").unwrap().is::<Current>());
+    }
+
+    #[test]
+    fn should_display_display_names() {
+        struct Shown;
+        struct NotShown;
+
+        set_display_name::<Shown>("Shown Type");
+
+        assert_eq!(display_name(TypeId::of::<Shown>()), Some("Shown Type"));
+        assert_eq!(display_name(TypeId::of::<NotShown>()), None);
+        assert_eq!(format!("{:>12}", TypeDef::of::<Shown>()), "  Shown Type");
+        assert_eq!(format!("{:#}", TypeDef::of::<Shown>()), "Shown Type");
+        assert_eq!(format!("{:#}", TypeDef::of::<NotShown>()), TypeDef::of::<NotShown>().short_name());
+        assert!(lookup(TypeId::of::<Shown>()).is_some());
+        assert_eq!(lookup_name("Shown Type"), None);
+    }
+
+    #[test]
+    fn should_report_name_collisions() {
+        struct First;
+        struct Second;
+
+        impl NamedType for First {
+            const NAME: &'static str = "my_crate::Collision";
+        }
+
+        impl NamedType for Second {
+            const NAME: &'static str = "my_crate::Collision";
+        }
+
+        register_typedef(TypeDef::of_named::<First>());
+        assert!(try_register_typedef(TypeDef::of_named::<First>()).is_ok());
+        let error = try_register_typedef(TypeDef::of_named::<Second>()).unwrap_err();
+        assert_eq!(error.kind(), CollisionKind::Name);
+        assert!(lookup(TypeId::of::<Second>()).is_none());
+
+        register_typedef(TypeDef::of_named::<Second>());
+        assert!(lookup_name("my_crate::Collision").unwrap().is::<First>());
+        assert!(lookup(TypeId::of::<Second>()).is_some());
+
+        let collision = collisions().into_iter().find(|collision| collision.rejected().is::<Second>()).unwrap();
+        assert!(collision.registered().is::<First>());
+        assert!(collision.to_string().starts_with("type name `my_crate::Collision` registered at an unknown location"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_report_stable_hash_collisions() {
+        // Both types are printed as `...::should_report_stable_hash_collisions::Same`.
+        let first = {
+            struct Same;
+            impl NamedType for Same {
+                const NAME: &'static str = "my_crate::FirstSame";
+            }
+            TypeDef::of_named::<Same>()
+        };
+        let second = {
+            struct Same;
+            impl NamedType for Same {
+                const NAME: &'static str = "my_crate::SecondSame";
+            }
+            TypeDef::of_named::<Same>()
+        };
+
+        assert!(try_register_typedef(first).is_ok());
+        let collision = try_register_typedef(second).unwrap_err();
+        assert_eq!(collision.kind(), CollisionKind::StableHash);
+        assert_eq!(collision.registered(), first);
+        assert!(collision.to_string().ends_with("with the name `my_crate::FirstSame`"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_look_up_tagged_names() {
+        struct UserId;
+
+        register::<u32>();
+        let tagged = TypeDef::of_tagged::<u32, UserId>();
+        assert!(try_register_typedef(tagged).is_ok());
+
+        let name = format!("u32 @ {}", TypeDef::name_of::<UserId>());
+        assert_eq!(lookup_name(&name), Some(tagged));
+        assert_eq!(lookup_name("u32"), Some(TypeDef::of::<u32>()));
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_export_display_names_and_locations() {
+        struct Exported;
+
+        let typedef = register::<Exported>();
+        set_display_name::<Exported>("Exported \"type\"");
+
+        let line = export_json().lines().find(|line| line.contains("::Exported\"")).unwrap().to_string();
+        let location = typedef.location().unwrap();
+        assert!(line.contains(", \"display_name\": \"Exported \\\"type\\\"\""));
+        assert!(line.contains(&format!(", \"location\": \"{}:{}:{}\"}}", location.file(), location.line(), location.column())));
+    }
+
+    #[test]
+    fn should_read_snapshots_that_were_written() {
+        struct Written;
+
+        register::<Written>();
+        register_alias::<Written>("old::Written");
+
+        let live = snapshot();
+        let read = RegistrySnapshot::from_json(&live.to_json()).unwrap();
+        let written = read.get("old::Written").unwrap();
+
+        assert!(written.location.is_some());
+        assert_eq!(read.types().len(), live.types().len());
+        assert!(read.types().iter().all(|ty| live.get(&ty.name) == Some(ty)));
+    }
+
+    #[test]
+    fn should_merge_and_compare_snapshots() {
+        let snapshot = |json| RegistrySnapshot::from_json(json).unwrap();
+        let mut left = snapshot(r#"{"types": [
+            {"name": "a::Same", "stable_hash": "1"},
+            {"name": "a::Changed", "stable_hash": "2", "aliases": ["old::Changed"]},
+            {"name": "a::Removed", "display_name": null}
+        ]}"#);
+        let right = snapshot(r#"{"types": [
+            {"name": "a::Same", "stable_hash": "1"},
+            {"name": "a::Changed", "stable_hash": "3", "aliases": ["older::Changed"]},
+            {"name": "a::Added", "extra": [1, {"nested": true}]}
+        ]}"#);
+
+        let diff = left.compare(&right);
+        assert_eq!(diff.added.iter().map(|ty| &*ty.name).collect::<Vec<_>>(), ["a::Added"]);
+        assert_eq!(diff.removed.iter().map(|ty| &*ty.name).collect::<Vec<_>>(), ["a::Removed"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!((diff.changed[0].0.stable_hash, diff.changed[0].1.stable_hash), (Some(2), Some(3)));
+        assert!(left.compare(&left).is_empty());
+
+        left.merge(&right);
+        assert_eq!(left.types().iter().map(|ty| &*ty.name).collect::<Vec<_>>(), ["a::Added", "a::Changed", "a::Removed", "a::Same"]);
+        assert_eq!(left.get("a::Changed").unwrap().aliases, ["old::Changed", "older::Changed"]);
+        assert_eq!(left.get("a::Changed").unwrap().stable_hash, Some(2));
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_not_change_stable_hashes_of_snapshots_with_the_compiler() {
+        use std::sync::Mutex;
+
+        register::<Mutex<u8>>();
+        let hash = ::fnv1a64(::FNV_OFFSET_BASIS, ::format::canonicalize("std::sync::mutex::Mutex<u8>").as_bytes());
+        let previous = RegistrySnapshot::from_json(&format!(
+            r#"{{"types": [{{"name": "std::sync::mutex::Mutex<u8>", "stable_hash": "{:016x}"}}]}}"#,
+            hash
+        ))
+        .unwrap();
+
+        let live = snapshot();
+        let diff = previous.compare(&live);
+        assert_eq!(live.get("std::sync::Mutex<u8>").unwrap().stable_hash, Some(hash));
+        assert!(diff.removed.is_empty());
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn should_reject_invalid_snapshots() {
+        let error = |json| RegistrySnapshot::from_json(json).unwrap_err().to_string();
+
+        assert_eq!(error("[]"), "invalid registry snapshot at byte 0: expected an object with a `types` array");
+        assert_eq!(error("{\"types\": [1"), "invalid registry snapshot at byte 12: expected `,` or `]`");
+        assert_eq!(error("{\"types\": {}}"), "invalid registry snapshot at byte 10: expected a `types` array");
+        assert_eq!(
+            error(r#"{"types": [{"name": "a", "stable_hash": "xyz"}]}"#),
+            "invalid registry snapshot at byte 40: expected a hexadecimal `stable_hash`"
+        );
+        assert_eq!(error(r#"{"types": [{"name": 1}]}"#), "invalid registry snapshot at byte 20: expected a string");
+        assert_eq!(
+            error(r#"{"types": [{"name": "[a]"}, {"name": "b", "aliases": ["c", 2]}]}"#),
+            "invalid registry snapshot at byte 59: expected a string"
+        );
+    }
+
+    #[test]
+    fn should_merge_snapshot_into_registry() {
+        struct Merged;
+
+        impl NamedType for Merged {
+            const NAME: &'static str = "my_crate::Merged";
+        }
+
+        register_typedef(TypeDef::of_named::<Merged>());
+        let imported = RegistrySnapshot::from_json(r#"{"types": [
+            {"name": "old_crate::Merged", "aliases": ["my_crate::Merged", "older_crate::Merged"]},
+            {"name": "old_crate::Unknown"}
+        ]}"#).unwrap();
+
+        assert_eq!(merge(&imported), 1);
+        assert!(lookup_name("old_crate::Merged").unwrap().is::<Merged>());
+        assert!(lookup_name("older_crate::Merged").unwrap().is::<Merged>());
+        assert_eq!(lookup_name("old_crate::Unknown"), None);
+    }
+
+    #[test]
+    fn should_attach_metadata_by_type() {
+        struct Annotated;
+        #[derive(Debug, PartialEq)]
+        struct Schema(u32);
+
+        attach::<Annotated, _>(Schema(1));
+        attach::<Annotated, _>(Schema(2));
+        attach::<Annotated, _>("label");
+
+        let typedef = TypeDef::of::<Annotated>();
+        assert!(lookup(typedef.id()).is_some());
+        assert_eq!(metadata::<Schema>(&typedef).as_deref(), Some(&Schema(2)));
+        assert_eq!(metadata::<&str>(&typedef).as_deref(), Some(&"label"));
+        assert_eq!(metadata::<u8>(&typedef), None);
+        assert_eq!(metadata::<Schema>(&TypeDef::of_tagged::<Annotated, Schema>()), None);
+    }
+
+    #[test]
+    fn should_construct_values_by_name() {
+        #[derive(Default, Debug, PartialEq)]
+        struct Defaulted(u8);
+        #[derive(Debug, PartialEq)]
+        struct Nested(u8);
+        struct NoFactory;
+
+        impl NamedType for Nested {
+            const NAME: &'static str = "my_crate::Nested";
+        }
+
+        register_default::<Defaulted>();
+        assert_eq!(construct(&TypeDef::name_of::<Defaulted>()).unwrap().downcast_ref(), Some(&Defaulted(0)));
+
+        register_typedef(TypeDef::of_named::<Nested>());
+        register_factory(|| {
+            let inner = construct(&TypeDef::name_of::<Defaulted>()).unwrap();
+            Nested(inner.downcast_ref::<Defaulted>().unwrap().0 + 2)
+        });
+        assert_eq!(construct("my_crate::Nested").unwrap().downcast_ref(), Some(&Nested(2)));
+        assert_eq!(lookup(TypeId::of::<Nested>()).unwrap().name(), "my_crate::Nested");
+
+        register::<NoFactory>();
+        assert!(construct(&TypeDef::name_of::<NoFactory>()).is_none());
+        assert!(construct("my_crate::Unknown").is_none());
+    }
+
+    #[test]
+    fn should_use_registered_facets() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Value(u8);
+        struct Opaque;
+
+        register_clone::<Value>();
+        register_eq::<Value>();
+        let typedef = register_debug::<Value>();
+
+        let facets = facets(&typedef);
+        let value: &dyn Any = &Value(1);
+        let other: &dyn Any = &Value(2);
+        let wrong: &dyn Any = &1u8;
+        let copy = facets.clone_value(value).unwrap();
+
+        assert_eq!(copy.downcast_ref(), Some(&Value(1)));
+        assert_eq!(facets.eq(value, &*copy), Some(true));
+        assert_eq!(facets.eq(value, other), Some(false));
+        assert_eq!(facets.eq(value, wrong), None);
+        assert!(facets.clone_value(wrong).is_none());
+        assert_eq!(format!("{:?}", facets.debug(other).unwrap()), "Value(2)");
+        assert!(facets.debug(wrong).is_none());
+        assert_eq!(format!("{:?}", facets), "Facets { clone: true, debug: true, eq: true }");
+
+        let facets = super::facets(&register::<Opaque>());
+        assert!(!facets.has_clone() && !facets.has_debug() && !facets.has_eq());
+        assert!(facets.clone_value(&Opaque).is_none());
+        assert!(facets.debug(&Opaque).is_none());
+    }
+
+    #[test]
+    fn should_record_trait_implementations() {
+        trait Marker {}
+        trait Other {}
+        struct First;
+        struct Second;
+
+        register_impl::<First, dyn Marker>();
+        let second = register_impl::<Second, dyn Marker>();
+        register_impl::<Second, dyn Other>();
+
+        assert!(implements::<dyn Marker>(&TypeDef::of::<First>()));
+        assert!(!implements::<dyn Other>(&TypeDef::of::<First>()));
+        assert!(second.implements::<dyn Other>());
+
+        let mut implementors = implementors_of::<dyn Marker>();
+        implementors.sort();
+        let mut expected = vec![TypeDef::of::<First>(), second];
+        expected.sort();
+        assert_eq!(implementors, expected);
+        assert_eq!(implementors_of::<dyn Other>(), [second]);
+    }
+
+    #[test]
+    fn should_cast_values_to_registered_traits() {
+        trait Named {
+            fn name(&self) -> String;
+        }
+        struct Cast(u8);
+        struct NotCast;
+
+        impl Named for Cast {
+            fn name(&self) -> String {
+                format!("cast {}", self.0)
+            }
+        }
+
+        impl Named for NotCast {
+            fn name(&self) -> String {
+                "not cast".into()
+            }
+        }
+
+        let typedef = register_cast::<Cast, dyn Named>(|value| value);
+        let value: Box<dyn Any> = Box::new(Cast(3));
+
+        assert!(typedef.implements::<dyn Named>());
+        assert_eq!(cast::<dyn Named>(&*value).map(|named| named.name()), Some("cast 3".into()));
+        assert!(cast::<dyn Named>(&NotCast).is_none());
+        assert!(cast::<dyn Send>(&*value).is_none());
+
+        register_cast!(NotCast => dyn Named, dyn Sync);
+        assert_eq!(cast::<dyn Named>(&NotCast).map(|named| named.name()), Some("not cast".into()));
+        assert!(cast::<dyn Sync>(&NotCast).is_some());
+    }
+
+    #[test]
+    fn should_convert_values_with_registered_converters() {
+        #[derive(Debug, PartialEq)]
+        struct Meters(u32);
+        #[derive(Debug, PartialEq)]
+        struct Centimeters(u32);
+
+        impl From<Meters> for Centimeters {
+            fn from(meters: Meters) -> Centimeters {
+                Centimeters(meters.0 * 100)
+            }
+        }
+
+        register_into::<Meters, Centimeters>();
+        let meters = TypeDef::of::<Meters>();
+        let centimeters = TypeDef::of::<Centimeters>();
+
+        assert!(converter(&meters, &centimeters).is_some());
+        assert!(converter(&centimeters, &meters).is_none());
+        assert_eq!(conversions_from(&meters), [centimeters]);
+        assert!(lookup(centimeters.id()).is_some());
+
+        let converted = convert(Box::new(Meters(2)), &centimeters).ok().unwrap();
+        assert_eq!(converted.downcast_ref(), Some(&Centimeters(200)));
+        let same = convert(Box::new(Meters(2)), &meters).ok().unwrap();
+        assert_eq!(same.downcast_ref(), Some(&Meters(2)));
+        let back = convert(Box::new(Centimeters(1)), &meters).err().unwrap();
+        assert_eq!(back.downcast_ref(), Some(&Centimeters(1)));
+
+        let converter = converter(&meters, &centimeters).unwrap();
+        assert_eq!(converter(Box::new(1u8)).downcast_ref(), Some(&1u8));
+    }
+
+    #[test]
+    fn should_replace_typedef_registered_again() {
+        struct Tag;
+
+        register::<Tag>();
+        register_typedef(TypeDef::of_traced::<Tag>());
+
+        assert!(lookup(TypeId::of::<Tag>()).unwrap().location().is_some());
+    }
+
+    #[test]
+    #[cfg(not(feature = "id-only"))]
+    fn should_look_up_registered_names() {
+        struct Registered<T>(T);
+
+        let typedef = register::<Registered<&'static str>>();
+        let name = TypeDef::name_of::<Registered<&'static str>>();
+
+        assert_eq!(lookup_name(&name), Some(typedef));
+        assert_eq!(lookup_name(&name.replace("&str", "&'static  str")), Some(typedef));
+        assert_eq!(lookup_name(&name.replace("&str", "&u8")), None);
+        assert_eq!(lookup_name("Registered<&str>"), None);
+    }
+
+    #[test]
+    fn should_forget_name_of_replaced_typedef() {
+        struct Renamed;
+
+        impl NamedType for Renamed {
+            const NAME: &'static str = "my_crate::Renamed";
+        }
+
+        register::<Renamed>();
+        register_typedef(TypeDef::of_named::<Renamed>());
+
+        assert_eq!(lookup_name("my_crate::Renamed").map(|typedef| typedef.id()), Some(TypeId::of::<Renamed>()));
+        assert_eq!(lookup_name(&TypeDef::name_of::<Renamed>()), None);
+    }
+}
@@ -0,0 +1,1217 @@
+//! Map that holds at most one value of each type, keyed by its typedef.
+//!
+//! ```
+//! use typedef::typemap::TypeMap;
+//!
+//! struct Port(u16);
+//!
+//! let mut map = TypeMap::new();
+//! map.insert(Port(8080));
+//! map.insert(String::from("localhost"));
+//!
+//! assert_eq!(map.get::<Port>().unwrap().0, 8080);
+//! assert_eq!(map.get::<String>().unwrap(), "localhost");
+//! assert!(!map.contains::<u8>());
+//! ```
+//!
+//! A `TypeMap<dyn Any + Send>` only holds values that implement `Send`, so
+//! the map itself can be sent to another thread, and a
+//! `TypeMap<dyn Any + Send + Sync>` can also be shared between threads:
+//!
+//! ```
+//! use std::any::Any;
+//! use std::thread;
+//! use typedef::typemap::TypeMap;
+//!
+//! let mut map = TypeMap::<dyn Any + Send>::default();
+//! map.insert(String::from("localhost"));
+//!
+//! let host = thread::spawn(move || map.remove::<String>()).join().unwrap();
+//!
+//! assert_eq!(host.unwrap(), "localhost");
+//! ```
+//!
+//! `SyncTypeMap` can be shared between threads and changed from them, for
+//! example to keep global state of an application by type. `TypeSet` holds
+//! types without values. Both are not available without the `std` feature.
+//!
+//! This module is not available if this crate is compiled without the `alloc` feature.
+
+use alloc::boxed::Box;
+use alloc::collections::btree_map::{self, BTreeMap};
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+use core::any::Any;
+#[cfg(feature = "std")]
+use core::any::TypeId;
+use core::fmt;
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::collections::hash_set::{self, HashSet};
+#[cfg(feature = "std")]
+use std::iter::FromIterator;
+#[cfg(feature = "std")]
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+#[cfg(feature = "std")]
+use registry;
+use TypeDef;
+
+/// Map of values of different types, with at most one value of each type.
+///
+/// Values are stored as `Box<A>`, where `A` is `dyn Any`, `dyn Any + Send` or
+/// `dyn Any + Send + Sync`, and the map can only hold values that can be
+/// stored so.
+///
+/// ```compile_fail
+/// use std::any::Any;
+/// use std::rc::Rc;
+/// use typedef::typemap::TypeMap;
+///
+/// let mut map = TypeMap::<dyn Any + Send>::default();
+/// map.insert(Rc::new(1));
+/// ```
+pub struct TypeMap<A: ?Sized + ValueBound = dyn Any> {
+    values: BTreeMap<TypeDef, Box<A>>,
+}
+
+/// Trait object that a `TypeMap` stores its values as.
+pub trait ValueBound: Any {
+    /// Get the value as `dyn Any`.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Get the value as `dyn Any`, for changing it.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Get the boxed value as `dyn Any`.
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+/// Value that can be stored in a `TypeMap` as the trait object `A`.
+pub trait IntoBoxed<A: ?Sized + ValueBound>: Any {
+    /// Box the value as the trait object.
+    fn into_boxed(self) -> Box<A>;
+}
+
+macro_rules! value_bound {
+    ($($bound:path),*) => {
+        impl ValueBound for dyn Any $(+ $bound)* {
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn Any {
+                self
+            }
+
+            fn into_any(self: Box<Self>) -> Box<dyn Any> {
+                self
+            }
+        }
+
+        impl<T: Any $(+ $bound)*> IntoBoxed<dyn Any $(+ $bound)*> for T {
+            fn into_boxed(self) -> Box<dyn Any $(+ $bound)*> {
+                Box::new(self)
+            }
+        }
+    };
+}
+
+value_bound!();
+value_bound!(Send);
+value_bound!(Send, Sync);
+
+impl TypeMap {
+    /// Create an empty map of any values. Maps of values that implement
+    /// `Send` or `Sync` are created with `TypeMap::default`.
+    pub const fn new() -> TypeMap {
+        TypeMap { values: BTreeMap::new() }
+    }
+}
+
+impl<A: ?Sized + ValueBound> TypeMap<A> {
+    /// Insert the value, and return the value of the same type that it
+    /// replaced.
+    pub fn insert<T: IntoBoxed<A>>(&mut self, value: T) -> Option<T> {
+        let previous = self.values.insert(TypeDef::of::<T>(), value.into_boxed())?;
+        previous.into_any().downcast().ok().map(|previous| *previous)
+    }
+
+    /// Get the entry of the type, to get its value or to insert the value if
+    /// there is none, with one lookup.
+    ///
+    /// ```
+    /// use typedef::typemap::TypeMap;
+    ///
+    /// let mut map = TypeMap::new();
+    ///
+    /// map.entry::<Vec<u8>>().or_default().push(1);
+    /// map.entry::<Vec<u8>>().and_modify(|bytes| bytes.push(2)).or_insert_with(Vec::new);
+    ///
+    /// assert_eq!(map.get::<Vec<u8>>().unwrap(), &[1, 2]);
+    /// ```
+    pub fn entry<T: IntoBoxed<A>>(&mut self) -> Entry<'_, T, A> {
+        Entry {
+            entry: self.values.entry(TypeDef::of::<T>()),
+            marker: PhantomData,
+        }
+    }
+
+    /// Get the value of the type.
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.values.get(&TypeDef::of::<T>())?.as_any().downcast_ref()
+    }
+
+    /// Get the value of the type for changing it.
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.values.get_mut(&TypeDef::of::<T>())?.as_any_mut().downcast_mut()
+    }
+
+    /// Remove the value of the type and return it.
+    pub fn remove<T: Any>(&mut self) -> Option<T> {
+        let value = self.values.remove(&TypeDef::of::<T>())?;
+        value.into_any().downcast().ok().map(|value| *value)
+    }
+
+    /// Check if the map has a value of the type.
+    pub fn contains<T: Any>(&self) -> bool {
+        self.values.contains_key(&TypeDef::of::<T>())
+    }
+
+    /// Get the number of values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Check if the map has no values.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Remove all values.
+    pub fn clear(&mut self) {
+        self.values.clear()
+    }
+
+    /// Iterate over the types of the values, in the order of `TypeDef`.
+    pub fn types(&self) -> Types<'_, A> {
+        Types {
+            keys: self.values.keys(),
+        }
+    }
+
+    /// Iterate over the values with their types, in the order of `TypeDef`.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "id-only"))] {
+    /// use typedef::typemap::TypeMap;
+    ///
+    /// let mut map = TypeMap::new();
+    /// map.insert(8080u16);
+    /// map.insert(String::from("localhost"));
+    ///
+    /// for (typedef, value) in map.iter() {
+    ///     if let Some(port) = value.downcast_ref::<u16>() {
+    ///         assert_eq!(typedef.name(), "u16");
+    ///         assert_eq!(*port, 8080);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn iter(&self) -> Iter<'_, A> {
+        Iter {
+            values: self.values.iter(),
+        }
+    }
+
+    /// Iterate over the values with their types for changing the values, in
+    /// the order of `TypeDef`.
+    pub fn iter_mut(&mut self) -> IterMut<'_, A> {
+        IterMut {
+            values: self.values.iter_mut(),
+        }
+    }
+}
+
+impl<A: ?Sized + ValueBound> Default for TypeMap<A> {
+    fn default() -> TypeMap<A> {
+        TypeMap { values: BTreeMap::new() }
+    }
+}
+
+/// Writes the names of the types with their values. Values are not required
+/// to implement `Debug`: they are written with the function registered with
+/// `registry::register_debug` for the type, or as `..` if there is none.
+/// Without the `std` feature, all values are written as `..`.
+///
+/// ```
+/// # #[cfg(all(feature = "std", not(feature = "id-only")))] {
+/// use typedef::registry;
+/// use typedef::typemap::TypeMap;
+///
+/// #[derive(Debug)]
+/// struct Port(u16);
+///
+/// struct Secret;
+///
+/// registry::register_debug::<Port>();
+///
+/// let mut map = TypeMap::new();
+/// map.insert(Port(8080));
+/// map.insert(Secret);
+///
+/// let debug = format!("{:?}", map);
+///
+/// assert!(debug.contains("::Port: Port(8080)"));
+/// assert!(debug.contains("::Secret: .."));
+/// # }
+/// ```
+impl<A: ?Sized + ValueBound> fmt::Debug for TypeMap<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut map = f.debug_map();
+        for (typedef, value) in self.iter() {
+            map.entry(&format_args!("{}", typedef), &DebugEntry { typedef, value });
+        }
+        map.finish()
+    }
+}
+
+/// Value of a map written with the `Debug` function registered for its type.
+struct DebugEntry<'a> {
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    typedef: TypeDef,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    value: &'a dyn Any,
+}
+
+impl<'a> fmt::Debug for DebugEntry<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        #[cfg(feature = "std")]
+        {
+            if let Some(value) = registry::facets(&self.typedef).debug(self.value) {
+                return fmt::Debug::fmt(&value, f);
+            }
+        }
+        f.write_str("..")
+    }
+}
+
+/// Value of a type in a `TypeMap`, which may not be inserted yet, created by
+/// `TypeMap::entry`.
+pub struct Entry<'a, T, A: ?Sized + ValueBound = dyn Any> {
+    entry: btree_map::Entry<'a, TypeDef, Box<A>>,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T: IntoBoxed<A>, A: ?Sized + ValueBound> Entry<'a, T, A> {
+    /// Get the typedef of the type.
+    pub fn key(&self) -> TypeDef {
+        *self.entry.key()
+    }
+
+    /// Check if the map has a value of the type.
+    pub fn is_occupied(&self) -> bool {
+        match self.entry {
+            btree_map::Entry::Occupied(_) => true,
+            btree_map::Entry::Vacant(_) => false,
+        }
+    }
+
+    /// Insert the value if the map has no value of the type, and get the
+    /// value of the type.
+    pub fn or_insert(self, value: T) -> &'a mut T {
+        self.or_insert_with(|| value)
+    }
+
+    /// Insert the value returned by the function if the map has no value of
+    /// the type, and get the value of the type. The function is not called
+    /// otherwise.
+    pub fn or_insert_with<F: FnOnce() -> T>(self, default: F) -> &'a mut T {
+        downcast_value(self.entry.or_insert_with(|| default().into_boxed()))
+    }
+
+    /// Insert the default value if the map has no value of the type, and get
+    /// the value of the type.
+    pub fn or_default(self) -> &'a mut T
+    where
+        T: Default,
+    {
+        self.or_insert_with(T::default)
+    }
+
+    /// Change the value of the type with the function, if the map has it.
+    pub fn and_modify<F: FnOnce(&mut T)>(mut self, modify: F) -> Entry<'a, T, A> {
+        if let btree_map::Entry::Occupied(ref mut entry) = self.entry {
+            modify(downcast_value(entry.get_mut()));
+        }
+        self
+    }
+}
+
+impl<'a, T, A: ?Sized + ValueBound> fmt::Debug for Entry<'a, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Entry").field(self.entry.key()).finish()
+    }
+}
+
+fn downcast_value<T: Any, A: ?Sized + ValueBound>(value: &mut Box<A>) -> &mut T {
+    match (**value).as_any_mut().downcast_mut() {
+        Some(value) => value,
+        None => unreachable!("values are stored with the typedef of their type"),
+    }
+}
+
+/// Iterator over the types of the values of a `TypeMap`, created by
+/// `TypeMap::types`.
+pub struct Types<'a, A: ?Sized + ValueBound = dyn Any> {
+    keys: btree_map::Keys<'a, TypeDef, Box<A>>,
+}
+
+impl<'a, A: ?Sized + ValueBound> Clone for Types<'a, A> {
+    fn clone(&self) -> Types<'a, A> {
+        Types { keys: self.keys.clone() }
+    }
+}
+
+impl<'a, A: ?Sized + ValueBound> fmt::Debug for Types<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+impl<'a, A: ?Sized + ValueBound> Iterator for Types<'a, A> {
+    type Item = TypeDef;
+
+    fn next(&mut self) -> Option<TypeDef> {
+        self.keys.next().cloned()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl<'a, A: ?Sized + ValueBound> ExactSizeIterator for Types<'a, A> {}
+
+/// Iterator over the values of a `TypeMap` with their types, created by
+/// `TypeMap::iter`.
+pub struct Iter<'a, A: ?Sized + ValueBound = dyn Any> {
+    values: btree_map::Iter<'a, TypeDef, Box<A>>,
+}
+
+impl<'a, A: ?Sized + ValueBound> Clone for Iter<'a, A> {
+    fn clone(&self) -> Iter<'a, A> {
+        Iter {
+            values: self.values.clone(),
+        }
+    }
+}
+
+impl<'a, A: ?Sized + ValueBound> fmt::Debug for Iter<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.clone().map(|entry| entry.0)).finish()
+    }
+}
+
+impl<'a, A: ?Sized + ValueBound> Iterator for Iter<'a, A> {
+    type Item = (TypeDef, &'a dyn Any);
+
+    fn next(&mut self) -> Option<(TypeDef, &'a dyn Any)> {
+        self.values.next().map(|(typedef, value)| (*typedef, (**value).as_any()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<'a, A: ?Sized + ValueBound> ExactSizeIterator for Iter<'a, A> {}
+
+/// Iterator over the values of a `TypeMap` with their types for changing the
+/// values, created by `TypeMap::iter_mut`.
+pub struct IterMut<'a, A: ?Sized + ValueBound = dyn Any> {
+    values: btree_map::IterMut<'a, TypeDef, Box<A>>,
+}
+
+impl<'a, A: ?Sized + ValueBound> fmt::Debug for IterMut<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IterMut").field("len", &self.values.len()).finish()
+    }
+}
+
+impl<'a, A: ?Sized + ValueBound> Iterator for IterMut<'a, A> {
+    type Item = (TypeDef, &'a mut dyn Any);
+
+    fn next(&mut self) -> Option<(TypeDef, &'a mut dyn Any)> {
+        self.values.next().map(|(typedef, value)| (*typedef, (**value).as_any_mut()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<'a, A: ?Sized + ValueBound> ExactSizeIterator for IterMut<'a, A> {}
+
+impl<'a, A: ?Sized + ValueBound> IntoIterator for &'a TypeMap<A> {
+    type Item = (TypeDef, &'a dyn Any);
+    type IntoIter = Iter<'a, A>;
+
+    fn into_iter(self) -> Iter<'a, A> {
+        self.iter()
+    }
+}
+
+impl<'a, A: ?Sized + ValueBound> IntoIterator for &'a mut TypeMap<A> {
+    type Item = (TypeDef, &'a mut dyn Any);
+    type IntoIter = IterMut<'a, A>;
+
+    fn into_iter(self) -> IterMut<'a, A> {
+        self.iter_mut()
+    }
+}
+
+/// `TypeMap` that looks up the values it does not have in its parent, as in
+/// a chain of scopes where a request is in a connection, which is in the
+/// application.
+///
+/// Values are inserted into and removed from this map only; the parents are
+/// not changed.
+///
+/// ```
+/// use typedef::typemap::ScopedTypeMap;
+///
+/// struct Config(&'static str);
+/// struct User(&'static str);
+///
+/// let mut application = ScopedTypeMap::new();
+/// application.insert(Config("production"));
+///
+/// let mut request = ScopedTypeMap::with_parent(&application);
+/// request.insert(User("alice"));
+///
+/// assert_eq!(request.get::<Config>().unwrap().0, "production");
+/// assert_eq!(request.get::<User>().unwrap().0, "alice");
+/// assert!(application.get::<User>().is_none());
+/// ```
+pub struct ScopedTypeMap<'a, A: ?Sized + ValueBound = dyn Any> {
+    map: TypeMap<A>,
+    parent: Option<&'a ScopedTypeMap<'a, A>>,
+}
+
+impl<'a> ScopedTypeMap<'a> {
+    /// Create an empty map of any values without a parent. Maps of values
+    /// that implement `Send` or `Sync` are created with
+    /// `ScopedTypeMap::default`.
+    pub const fn new() -> ScopedTypeMap<'a> {
+        ScopedTypeMap {
+            map: TypeMap::new(),
+            parent: None,
+        }
+    }
+}
+
+impl<'a, A: ?Sized + ValueBound> ScopedTypeMap<'a, A> {
+    /// Create an empty map that looks up values in the parent.
+    pub fn with_parent(parent: &'a ScopedTypeMap<'a, A>) -> ScopedTypeMap<'a, A> {
+        ScopedTypeMap {
+            map: TypeMap::default(),
+            parent: Some(parent),
+        }
+    }
+
+    /// Get the parent of this map.
+    pub fn parent(&self) -> Option<&'a ScopedTypeMap<'a, A>> {
+        self.parent
+    }
+
+    /// Get the values of this map, without the values of the parents.
+    pub fn local(&self) -> &TypeMap<A> {
+        &self.map
+    }
+
+    /// Get the values of this map for changing them, without the values of
+    /// the parents.
+    pub fn local_mut(&mut self) -> &mut TypeMap<A> {
+        &mut self.map
+    }
+
+    /// Insert the value into this map, and return the value of the same type
+    /// that it replaced in this map.
+    pub fn insert<T: IntoBoxed<A>>(&mut self, value: T) -> Option<T> {
+        self.map.insert(value)
+    }
+
+    /// Get the value of the type from this map, or from the closest parent
+    /// that has it.
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        let mut scope = Some(self);
+        while let Some(map) = scope {
+            if let Some(value) = map.map.get::<T>() {
+                return Some(value);
+            }
+            scope = map.parent;
+        }
+        None
+    }
+
+    /// Get the value of the type in this map for changing it. Values of the
+    /// parents are not returned.
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.map.get_mut()
+    }
+
+    /// Remove the value of the type from this map and return it. The parents
+    /// keep their values, which `get` finds afterwards.
+    pub fn remove<T: Any>(&mut self) -> Option<T> {
+        self.map.remove()
+    }
+
+    /// Check if this map or one of its parents has a value of the type.
+    pub fn contains<T: Any>(&self) -> bool {
+        self.get::<T>().is_some()
+    }
+}
+
+impl<'a, A: ?Sized + ValueBound> Default for ScopedTypeMap<'a, A> {
+    fn default() -> ScopedTypeMap<'a, A> {
+        ScopedTypeMap {
+            map: TypeMap::default(),
+            parent: None,
+        }
+    }
+}
+
+impl<'a, A: ?Sized + ValueBound> fmt::Debug for ScopedTypeMap<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ScopedTypeMap")
+            .field("values", &self.map)
+            .field("parent", &self.parent)
+            .finish()
+    }
+}
+
+/// Map of values of different types, with at most one value of each type,
+/// that can be used from many threads at once.
+///
+/// Values are shared with `Arc`, since a value can be removed by one thread
+/// while another uses it. Types are split into shards that each have a lock,
+/// so threads that use different types seldom wait for each other.
+///
+/// ```
+/// use std::sync::atomic::{ AtomicUsize, Ordering };
+/// use std::thread;
+/// use typedef::typemap::SyncTypeMap;
+///
+/// static STATE: SyncTypeMap = SyncTypeMap::new();
+///
+/// struct Requests(AtomicUsize);
+///
+/// let threads: Vec<_> = (0..4)
+///     .map(|_| thread::spawn(|| {
+///         let requests = STATE.get_or_insert_with(|| Requests(AtomicUsize::new(0)));
+///         requests.0.fetch_add(1, Ordering::Relaxed);
+///     }))
+///     .collect();
+/// for thread in threads {
+///     thread.join().unwrap();
+/// }
+///
+/// assert_eq!(STATE.get::<Requests>().unwrap().0.load(Ordering::Relaxed), 4);
+/// ```
+///
+/// This is not available if this crate is compiled without the `std` feature.
+#[cfg(feature = "std")]
+pub struct SyncTypeMap {
+    shards: [RwLock<BTreeMap<TypeDef, SyncValue>>; SHARD_COUNT],
+}
+
+#[cfg(feature = "std")]
+type SyncValue = Arc<dyn Any + Send + Sync>;
+
+#[cfg(feature = "std")]
+const SHARD_COUNT: usize = 16;
+
+#[cfg(feature = "std")]
+impl SyncTypeMap {
+    /// Create an empty map.
+    pub const fn new() -> SyncTypeMap {
+        SyncTypeMap {
+            shards: [const { RwLock::new(BTreeMap::new()) }; SHARD_COUNT],
+        }
+    }
+
+    /// Insert the value, and return the value of the same type that it
+    /// replaced.
+    pub fn insert<T: Any + Send + Sync>(&self, value: T) -> Option<Arc<T>> {
+        let previous = self.shard_mut::<T>().insert(TypeDef::of::<T>(), Arc::new(value))?;
+        previous.downcast().ok()
+    }
+
+    /// Get the value of the type.
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        let value = self.shard::<T>().get(&TypeDef::of::<T>())?.clone();
+        value.downcast().ok()
+    }
+
+    /// Get the value of the type, or insert the value returned by the
+    /// function if there is none.
+    ///
+    /// When threads call this for a type that has no value at once, the
+    /// function is called by one of them and the others get its value. The
+    /// function is called while the map is locked for other types too, so it
+    /// must not use the map.
+    pub fn get_or_insert_with<T: Any + Send + Sync, F: FnOnce() -> T>(&self, default: F) -> Arc<T> {
+        if let Some(value) = self.get::<T>() {
+            return value;
+        }
+        let value = self
+            .shard_mut::<T>()
+            .entry(TypeDef::of::<T>())
+            .or_insert_with(|| Arc::new(default()))
+            .clone();
+        match value.downcast() {
+            Ok(value) => value,
+            Err(_) => unreachable!("values are stored with the typedef of their type"),
+        }
+    }
+
+    /// Remove the value of the type and return it.
+    pub fn remove<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        let value = self.shard_mut::<T>().remove(&TypeDef::of::<T>())?;
+        value.downcast().ok()
+    }
+
+    /// Check if the map has a value of the type.
+    pub fn contains<T: Any + Send + Sync>(&self) -> bool {
+        self.shard::<T>().contains_key(&TypeDef::of::<T>())
+    }
+
+    /// Get the number of values.
+    pub fn len(&self) -> usize {
+        (0..SHARD_COUNT).map(|index| self.shard_at(index).len()).sum()
+    }
+
+    /// Check if the map has no values.
+    pub fn is_empty(&self) -> bool {
+        (0..SHARD_COUNT).all(|index| self.shard_at(index).is_empty())
+    }
+
+    /// Remove all values.
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            shard.write().unwrap_or_else(|error| error.into_inner()).clear();
+        }
+    }
+
+    /// Get the types of the values, in no specified order.
+    pub fn types(&self) -> Vec<TypeDef> {
+        let mut types = Vec::new();
+        for index in 0..SHARD_COUNT {
+            types.extend(self.shard_at(index).keys().cloned());
+        }
+        types
+    }
+
+    fn shard<T: Any>(&self) -> RwLockReadGuard<'_, BTreeMap<TypeDef, SyncValue>> {
+        self.shard_at(shard_index(TypeId::of::<T>()))
+    }
+
+    // The maps are never left half-updated, so a panic while a lock is held
+    // does not make their contents invalid.
+    fn shard_at(&self, index: usize) -> RwLockReadGuard<'_, BTreeMap<TypeDef, SyncValue>> {
+        self.shards[index].read().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn shard_mut<T: Any>(&self) -> RwLockWriteGuard<'_, BTreeMap<TypeDef, SyncValue>> {
+        self.shards[shard_index(TypeId::of::<T>())].write().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for SyncTypeMap {
+    fn default() -> SyncTypeMap {
+        SyncTypeMap::new()
+    }
+}
+
+/// Writes the names of the types with their values, as `Debug` of `TypeMap`
+/// does.
+#[cfg(feature = "std")]
+impl fmt::Debug for SyncTypeMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut values: Vec<(TypeDef, SyncValue)> = Vec::new();
+        for index in 0..SHARD_COUNT {
+            values.extend(self.shard_at(index).iter().map(|(typedef, value)| (*typedef, value.clone())));
+        }
+        values.sort_by_key(|entry| entry.0);
+        let mut map = f.debug_map();
+        for (typedef, value) in &values {
+            let value: &dyn Any = &**value;
+            map.entry(&format_args!("{}", typedef), &DebugEntry { typedef: *typedef, value });
+        }
+        map.finish()
+    }
+}
+
+#[cfg(feature = "std")]
+fn shard_index(id: TypeId) -> usize {
+    (::raw_id(&id) % SHARD_COUNT as u64) as usize
+}
+
+/// Set of types, as the capabilities of a plugin or the types seen while
+/// walking values.
+///
+/// ```
+/// use typedef::TypeDef;
+/// use typedef::typemap::TypeSet;
+///
+/// struct Read;
+/// struct Write;
+///
+/// let mut granted = TypeSet::new();
+/// granted.insert::<Read>();
+///
+/// let required: TypeSet = vec![TypeDef::of::<Read>(), TypeDef::of::<Write>()].into_iter().collect();
+///
+/// assert!(granted.contains::<Read>());
+/// assert!(!granted.contains_def(TypeDef::of::<Write>()));
+/// assert_eq!(required.intersection(&granted), granted);
+/// assert_eq!(required.union(&granted).len(), 2);
+/// ```
+///
+/// This is not available if this crate is compiled without the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct TypeSet {
+    types: HashSet<TypeDef>,
+}
+
+#[cfg(feature = "std")]
+impl TypeSet {
+    /// Create an empty set.
+    pub fn new() -> TypeSet {
+        TypeSet { types: HashSet::new() }
+    }
+
+    /// Add the type, and return `false` if the set already had it.
+    pub fn insert<T: ?Sized + Any>(&mut self) -> bool {
+        self.insert_def(TypeDef::of::<T>())
+    }
+
+    /// Add the type of the typedef, and return `false` if the set already had
+    /// it.
+    pub fn insert_def(&mut self, typedef: TypeDef) -> bool {
+        self.types.insert(typedef)
+    }
+
+    /// Remove the type, and return `false` if the set did not have it.
+    pub fn remove<T: ?Sized + Any>(&mut self) -> bool {
+        self.remove_def(TypeDef::of::<T>())
+    }
+
+    /// Remove the type of the typedef, and return `false` if the set did not
+    /// have it.
+    pub fn remove_def(&mut self, typedef: TypeDef) -> bool {
+        self.types.remove(&typedef)
+    }
+
+    /// Check if the set has the type.
+    pub fn contains<T: ?Sized + Any>(&self) -> bool {
+        self.contains_def(TypeDef::of::<T>())
+    }
+
+    /// Check if the set has the type of the typedef.
+    pub fn contains_def(&self, typedef: TypeDef) -> bool {
+        self.types.contains(&typedef)
+    }
+
+    /// Get the number of types.
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    /// Check if the set has no types.
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Remove all types.
+    pub fn clear(&mut self) {
+        self.types.clear()
+    }
+
+    /// Get the set of the types that are in this set or in the other one.
+    pub fn union(&self, other: &TypeSet) -> TypeSet {
+        self.types.union(&other.types).cloned().collect()
+    }
+
+    /// Get the set of the types that are in both this set and the other one.
+    pub fn intersection(&self, other: &TypeSet) -> TypeSet {
+        self.types.intersection(&other.types).cloned().collect()
+    }
+
+    /// Get the set of the types that are in this set but not in the other
+    /// one.
+    pub fn difference(&self, other: &TypeSet) -> TypeSet {
+        self.types.difference(&other.types).cloned().collect()
+    }
+
+    /// Check if all types of this set are in the other one.
+    pub fn is_subset(&self, other: &TypeSet) -> bool {
+        self.types.is_subset(&other.types)
+    }
+
+    /// Iterate over the types, in no specified order.
+    pub fn iter(&self) -> TypeSetIter<'_> {
+        TypeSetIter {
+            types: self.types.iter(),
+        }
+    }
+}
+
+/// Writes the names of the types, sorted as `TypeDef`.
+#[cfg(feature = "std")]
+impl fmt::Debug for TypeSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut types: Vec<TypeDef> = self.iter().collect();
+        types.sort();
+        let mut set = f.debug_set();
+        for typedef in &types {
+            set.entry(&format_args!("{}", typedef));
+        }
+        set.finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromIterator<TypeDef> for TypeSet {
+    fn from_iter<I: IntoIterator<Item = TypeDef>>(types: I) -> TypeSet {
+        TypeSet {
+            types: types.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Extend<TypeDef> for TypeSet {
+    fn extend<I: IntoIterator<Item = TypeDef>>(&mut self, types: I) {
+        self.types.extend(types)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> IntoIterator for &'a TypeSet {
+    type Item = TypeDef;
+    type IntoIter = TypeSetIter<'a>;
+
+    fn into_iter(self) -> TypeSetIter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the types of a `TypeSet`, created by `TypeSet::iter`.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct TypeSetIter<'a> {
+    types: hash_set::Iter<'a, TypeDef>,
+}
+
+#[cfg(feature = "std")]
+impl<'a> Iterator for TypeSetIter<'a> {
+    type Item = TypeDef;
+
+    fn next(&mut self) -> Option<TypeDef> {
+        self.types.next().cloned()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.types.size_hint()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> ExactSizeIterator for TypeSetIter<'a> {}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "std")]
+    use super::{SyncTypeMap, TypeSet};
+    use super::TypeMap;
+    use TypeDef;
+
+    #[derive(Debug, PartialEq)]
+    struct Port(u16);
+
+    #[test]
+    fn should_keep_one_value_of_each_type() {
+        let mut map = TypeMap::new();
+
+        assert_eq!(map.insert(Port(80)), None);
+        assert_eq!(map.insert(Port(8080)), Some(Port(80)));
+        assert_eq!(map.insert(80u16), None);
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get::<Port>(), Some(&Port(8080)));
+        assert_eq!(map.get::<u16>(), Some(&80));
+        assert_eq!(map.get::<u32>(), None);
+    }
+
+    #[test]
+    fn should_change_and_remove_values() {
+        let mut map = TypeMap::new();
+        map.insert(Port(80));
+
+        map.get_mut::<Port>().unwrap().0 += 1;
+        assert!(map.get_mut::<u8>().is_none());
+
+        assert!(map.contains::<Port>());
+        assert_eq!(map.remove::<Port>(), Some(Port(81)));
+        assert_eq!(map.remove::<Port>(), None);
+        assert!(!map.contains::<Port>());
+        assert!(map.is_empty());
+
+        map.insert(1u8);
+        map.clear();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn should_insert_value_of_vacant_entry_only() {
+        let mut map = TypeMap::new();
+
+        assert!(!map.entry::<Port>().is_occupied());
+        assert_eq!(map.entry::<Port>().key(), TypeDef::of::<Port>());
+        assert_eq!(map.entry().or_insert(Port(80)), &mut Port(80));
+        assert_eq!(map.entry().or_insert(Port(8080)), &mut Port(80));
+        assert_eq!(map.entry::<Port>().or_insert_with(|| unreachable!()), &mut Port(80));
+        assert!(map.entry::<Port>().is_occupied());
+
+        *map.entry::<u32>().or_default() += 5;
+        assert_eq!(map.get::<u32>(), Some(&5));
+    }
+
+    #[test]
+    fn should_modify_value_of_occupied_entry_only() {
+        let mut map = TypeMap::new();
+
+        map.entry::<Vec<u8>>().and_modify(|bytes| bytes.push(1)).or_default();
+        assert_eq!(map.get::<Vec<u8>>(), Some(&Vec::new()));
+
+        map.entry::<Vec<u8>>().and_modify(|bytes| bytes.push(2)).or_default();
+        assert_eq!(map.get::<Vec<u8>>(), Some(&vec![2]));
+    }
+
+    #[test]
+    fn should_list_types_of_values() {
+        let mut map: TypeMap = TypeMap::default();
+        map.insert(1u8);
+        map.insert(Port(80));
+
+        let mut types: Vec<TypeDef> = map.types().collect();
+        types.sort_by_key(TypeDef::id);
+        let mut expected = vec![TypeDef::of::<u8>(), TypeDef::of::<Port>()];
+        expected.sort_by_key(TypeDef::id);
+
+        assert_eq!(types, expected);
+        assert_eq!(map.types().len(), 2);
+        assert_eq!(format!("{:?}", TypeMap::new()), "{}");
+    }
+
+    #[test]
+    fn should_keep_values_of_send_and_sync_maps() {
+        use core::any::Any;
+        use std::rc::Rc;
+
+        fn assert_send<T: Send>(_: &T) {}
+        fn assert_sync<T: Sync>(_: &T) {}
+
+        let mut any = TypeMap::new();
+        any.insert(Rc::new(1u8));
+        assert_eq!(any.get::<Rc<u8>>().map(|value| **value), Some(1));
+
+        let mut send = TypeMap::<dyn Any + Send>::default();
+        assert_eq!(send.insert(Port(80)), None);
+        *send.entry::<u8>().or_default() += 1;
+        assert_send(&send);
+        assert_eq!(send.remove::<Port>(), Some(Port(80)));
+
+        let mut sync = TypeMap::<dyn Any + Send + Sync>::default();
+        sync.insert(Port(8080));
+        sync.get_mut::<Port>().unwrap().0 += 1;
+        assert_send(&sync);
+        assert_sync(&sync);
+        assert_eq!(sync.get::<Port>(), Some(&Port(8081)));
+        assert_eq!(sync.types().collect::<Vec<_>>(), [TypeDef::of::<Port>()]);
+    }
+
+    #[test]
+    fn should_iterate_over_values_with_their_types() {
+        let mut map = TypeMap::new();
+        map.insert(Port(80));
+        map.insert(5u8);
+
+        let mut found: Vec<(TypeDef, bool)> = map.iter().map(|(typedef, value)| (typedef, value.is::<u8>())).collect();
+        found.sort_by_key(|found| found.0.id());
+        let mut expected = vec![(TypeDef::of::<Port>(), false), (TypeDef::of::<u8>(), true)];
+        expected.sort_by_key(|expected| expected.0.id());
+        assert_eq!(found, expected);
+        assert_eq!(map.iter().len(), 2);
+
+        for (typedef, value) in &mut map {
+            if typedef.is::<Port>() {
+                value.downcast_mut::<Port>().unwrap().0 += 1;
+            }
+        }
+        assert_eq!(map.get::<Port>(), Some(&Port(81)));
+        assert_eq!((&map).into_iter().count(), 2);
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", not(feature = "id-only")))]
+    fn should_debug_names_and_registered_values() {
+        use registry;
+
+        struct Hidden;
+
+        registry::register_debug::<Port>();
+
+        let mut map = TypeMap::new();
+        map.insert(Port(80));
+        map.insert(Hidden);
+
+        let port = TypeDef::of::<Port>();
+        let hidden = TypeDef::of::<Hidden>();
+        let (first, second) = if port < hidden {
+            (format!("{}: Port(80)", port), format!("{}: ..", hidden))
+        } else {
+            (format!("{}: ..", hidden), format!("{}: Port(80)", port))
+        };
+        assert_eq!(format!("{:?}", map), format!("{{{}, {}}}", first, second));
+
+        let sync = SyncTypeMap::new();
+        sync.insert(Port(80));
+        assert_eq!(format!("{:?}", sync), format!("{{{}: Port(80)}}", port));
+    }
+
+    #[test]
+    fn should_look_up_values_of_parent_scopes() {
+        use super::ScopedTypeMap;
+
+        let mut application = ScopedTypeMap::new();
+        application.insert(Port(80));
+        application.insert(1u8);
+
+        let mut connection = ScopedTypeMap::with_parent(&application);
+        connection.insert(2u8);
+
+        let mut request = ScopedTypeMap::with_parent(&connection);
+        assert_eq!(request.get::<u8>(), Some(&2));
+        assert_eq!(request.get::<Port>(), Some(&Port(80)));
+        assert!(request.contains::<Port>());
+        assert!(!request.contains::<u16>());
+
+        assert_eq!(request.insert(3u8), None);
+        assert_eq!(request.get::<u8>(), Some(&3));
+        assert!(request.get_mut::<Port>().is_none());
+        assert_eq!(request.remove::<u8>(), Some(3));
+        assert_eq!(request.remove::<Port>(), None);
+        assert_eq!(request.get::<u8>(), Some(&2));
+
+        assert!(request.local().is_empty());
+        assert!(request.parent().unwrap().parent().unwrap().parent().is_none());
+        assert_eq!(connection.get::<u8>(), Some(&2));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn should_share_values_of_sync_map() {
+        use std::sync::Arc;
+
+        let map = SyncTypeMap::new();
+
+        assert_eq!(map.insert(Port(80)), None);
+        assert_eq!(map.insert(Port(8080)), Some(Arc::new(Port(80))));
+        assert_eq!(map.get::<Port>(), Some(Arc::new(Port(8080))));
+        assert!(map.get::<u8>().is_none());
+        assert!(map.contains::<Port>());
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.types(), [TypeDef::of::<Port>()]);
+
+        assert_eq!(map.remove::<Port>(), Some(Arc::new(Port(8080))));
+        assert!(map.is_empty());
+
+        map.insert(1u8);
+        map.insert(2u16);
+        map.clear();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn should_insert_one_value_from_many_threads() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::thread;
+
+        static MAP: SyncTypeMap = SyncTypeMap::new();
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        let threads: Vec<_> = (0..8)
+            .map(|thread| {
+                thread::spawn(move || {
+                    MAP.get_or_insert_with(|| {
+                        CALLS.fetch_add(1, Ordering::SeqCst);
+                        Port(thread)
+                    })
+                    .0
+                })
+            })
+            .collect();
+        let ports: Vec<u16> = threads.into_iter().map(|thread| thread.join().unwrap()).collect();
+
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+        assert!(ports.iter().all(|port| *port == ports[0]));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn should_add_and_remove_types_of_set() {
+        let mut set = TypeSet::new();
+
+        assert!(set.insert::<Port>());
+        assert!(!set.insert_def(TypeDef::of::<Port>()));
+        assert!(set.insert::<str>());
+        assert!(set.contains::<str>());
+        assert!(set.contains_def(TypeDef::of::<Port>()));
+        assert!(!set.contains::<String>());
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.iter().len(), 2);
+
+        assert!(set.remove::<str>());
+        assert!(!set.remove_def(TypeDef::of::<str>()));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [TypeDef::of::<Port>()]);
+
+        set.clear();
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn should_combine_type_sets() {
+        let left: TypeSet = vec![TypeDef::of::<u8>(), TypeDef::of::<u16>()].into_iter().collect();
+        let mut right = TypeSet::new();
+        right.extend(vec![TypeDef::of::<u16>(), TypeDef::of::<u32>()]);
+
+        let union = left.union(&right);
+        assert_eq!(union.len(), 3);
+        assert!(left.is_subset(&union));
+        assert!(!left.is_subset(&right));
+
+        let intersection = left.intersection(&right);
+        assert_eq!(intersection.iter().collect::<Vec<_>>(), [TypeDef::of::<u16>()]);
+        assert_eq!(left.difference(&right).iter().collect::<Vec<_>>(), [TypeDef::of::<u8>()]);
+        assert_eq!((&union).into_iter().count(), 3);
+        #[cfg(not(feature = "id-only"))]
+        assert_eq!(format!("{:?}", intersection), "{u16}");
+    }
+}
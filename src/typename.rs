@@ -0,0 +1,1569 @@
+//! Parsing of type names into a tree.
+//!
+//! The tree keeps paths, generic arguments, references, pointers, slices,
+//! arrays, tuples, function pointers and trait bounds, so names can be
+//! inspected and rewritten without string splitting that breaks inside
+//! generic arguments. Lifetimes are kept, except for `for<'a>` binders.
+//! Parsed names, also after they were changed, are written back with
+//! `Display`, where the alternate flag (`{:#}`) removes module paths, or with
+//! `TypeName::format_with` and the options of `NameFormat`.
+//!
+//! ```
+//! use typedef::typename::{ parse, GenericArg, TypeName };
+//!
+//! let name = parse("alloc::vec::Vec<&'static str>").unwrap();
+//!
+//! match name {
+//!     TypeName::Path { path, ref args, .. } => {
+//!         assert_eq!(path, "alloc::vec::Vec");
+//!         assert!(matches!(args[0], GenericArg::Type(TypeName::Ref { mutable: false, .. })));
+//!     },
+//!     _ => unreachable!(),
+//! }
+//! assert_eq!(format!("{:#}", name), "Vec<&'static str>");
+//! ```
+//!
+//! This module is not available if this crate is compiled without the `alloc` feature.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use docs;
+use format::{self, Token, Tokens};
+use NameFormat;
+
+/// Structural category of a type, returned by `TypeDef::kind`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TypeKind {
+    /// Primitive type, as `u8`, `str` or the never type `!`.
+    Primitive,
+    /// Tuple, including the unit type `()`.
+    Tuple,
+    /// Reference, `&T` or `&mut T`.
+    Reference,
+    /// Raw pointer, `*const T` or `*mut T`.
+    RawPointer,
+    /// Slice, `[T]`.
+    Slice,
+    /// Array, `[T; N]`.
+    Array,
+    /// Function pointer, as `fn(u8) -> u8`.
+    FnPointer,
+    /// Trait object, as `dyn Send`.
+    TraitObject,
+    /// Type named by a path, as `alloc::vec::Vec<u8>`, including qualified
+    /// paths `<T as Trait>::Out`.
+    Path,
+    /// Closure type, as `my_crate::main::{{closure}}`. The futures of async
+    /// blocks and `async fn` are named the same way as closures, so they are
+    /// classified as closures too.
+    Closure,
+    /// `impl Trait` type or other compiler-generated type, as
+    /// `my_crate::f::{{opaque}}`.
+    Opaque,
+}
+
+/// Parsed type name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeName<'a> {
+    /// Named type, as `alloc::vec::Vec<u8>`, or a trait with the `Fn(u8) -> u8`
+    /// signature.
+    Path {
+        path: &'a str,
+        args: Vec<GenericArg<'a>>,
+        signature: Option<Signature<'a>>,
+    },
+    /// Reference, `&T` or `&mut T`, with the lifetime if it is written, as
+    /// `'static` in `&'static T`.
+    Ref { mutable: bool, lifetime: Option<&'a str>, inner: Box<TypeName<'a>> },
+    /// Raw pointer, `*const T` or `*mut T`.
+    Ptr { mutable: bool, inner: Box<TypeName<'a>> },
+    /// Slice, `[T]`.
+    Slice(Box<TypeName<'a>>),
+    /// Array, `[T; N]`, with the length as it is written.
+    Array(Box<TypeName<'a>>, &'a str),
+    /// Tuple, including the unit type `()`.
+    Tuple(Vec<TypeName<'a>>),
+    /// Function pointer, as `fn(u8) -> u8`.
+    Fn(Signature<'a>),
+    /// Trait object with its bounds, as `dyn Send + Sync`.
+    Dyn(Bounds<'a>),
+    /// Opaque type with its bounds, as `impl Send`.
+    Impl(Bounds<'a>),
+    /// Never type, `!`.
+    Never,
+    /// Any other type kept as it is written, such as closures and qualified
+    /// paths `<T as Trait>::Out`.
+    Other(&'a str),
+}
+
+/// Generic argument of a parsed type name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GenericArg<'a> {
+    /// Type argument.
+    Type(TypeName<'a>),
+    /// Associated type binding, as `Output = u8`.
+    Binding(&'a str, TypeName<'a>),
+    /// Lifetime argument, including the leading `'`, as `'_`.
+    Lifetime(&'a str),
+    /// Const argument as it is written, as `3`, `-1` or `true`.
+    Const(&'a str),
+}
+
+/// Trait and lifetime bounds of a trait object or an `impl Trait` type, as
+/// `Error`, `Send` and `'static` in `dyn Error + Send + 'static`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bounds<'a> {
+    /// Traits, in the order they are written.
+    pub traits: Vec<TypeName<'a>>,
+    /// Lifetimes, including the leading `'`.
+    pub lifetimes: Vec<&'a str>,
+}
+
+/// Auto traits of the standard library, which can be added to any trait
+/// object.
+/// Deepest nesting of types that names are parsed with, which keeps user
+/// patterns from exhausting the stack.
+const MAX_DEPTH: usize = 128;
+
+const AUTO_TRAITS: &[&str] = &["Send", "Sync", "Unpin", "UnwindSafe", "RefUnwindSafe"];
+
+impl<'a> Bounds<'a> {
+    /// Get the trait that is not an auto trait, as `Error` in
+    /// `dyn Error + Send + Sync`, or `None` if all traits are auto traits.
+    pub fn primary_trait(&self) -> Option<&TypeName<'a>> {
+        self.traits.iter().find(|bound| !is_auto_trait(bound))
+    }
+
+    /// Get the auto traits, as `Send` and `Sync` in `dyn Error + Send + Sync`.
+    pub fn auto_traits(&self) -> Vec<&TypeName<'a>> {
+        self.traits.iter().filter(|bound| is_auto_trait(bound)).collect()
+    }
+
+    /// Write the bounds separated with `+`, with module paths removed if
+    /// `short` is set.
+    pub fn write(&self, short: bool, f: &mut dyn fmt::Write) -> fmt::Result {
+        for (i, bound) in self.traits.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" + ")?;
+            }
+            bound.write(short, f)?;
+        }
+        for (i, lifetime) in self.lifetimes.iter().enumerate() {
+            if i > 0 || !self.traits.is_empty() {
+                f.write_str(" + ")?;
+            }
+            f.write_str(lifetime)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_auto_trait(bound: &TypeName) -> bool {
+    match *bound {
+        TypeName::Path { path, ref args, signature: None } if args.is_empty() => {
+            AUTO_TRAITS.contains(&path.rsplit("::").next().unwrap_or(path))
+        },
+        _ => false,
+    }
+}
+
+/// Arguments and return type of a function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature<'a> {
+    /// Types of the arguments.
+    pub args: Vec<TypeName<'a>>,
+    /// Return type, or `None` if the function returns `()`.
+    pub ret: Option<Box<TypeName<'a>>>,
+    /// Function pointer is `unsafe`.
+    pub unsafety: bool,
+    /// ABI of `extern` function pointers, `C` if the ABI is not written.
+    pub abi: Option<&'a str>,
+    /// Arguments end with `...`, as in `unsafe extern "C" fn(i32, ...)`.
+    pub variadic: bool,
+}
+
+impl<'a> TypeName<'a> {
+    /// Check if the type is a reference, `&T` or `&mut T`.
+    pub fn is_reference(&self) -> bool {
+        matches!(*self, TypeName::Ref { .. })
+    }
+
+    /// Get the elements of a tuple, or `None` if the type is not a tuple.
+    pub fn tuple_elements(&self) -> Option<&[TypeName<'a>]> {
+        match *self {
+            TypeName::Tuple(ref items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Get the element type of a slice or an array, as `u8` for `[u8]` or
+    /// `[u8; 16]`.
+    pub fn element_type(&self) -> Option<&TypeName<'a>> {
+        match *self {
+            TypeName::Slice(ref inner) | TypeName::Array(ref inner, _) => Some(inner),
+            _ => None,
+        }
+    }
+
+    /// Get the length of an array, as `16` for `[u8; 16]`. Returns `None` for
+    /// other types and for lengths that are not written as a number.
+    pub fn array_len(&self) -> Option<usize> {
+        match *self {
+            TypeName::Array(_, len) => len.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Get the signature of a function pointer, or `None` if the type is not a
+    /// function pointer.
+    pub fn fn_signature(&self) -> Option<&Signature<'a>> {
+        match *self {
+            TypeName::Fn(ref signature) => Some(signature),
+            _ => None,
+        }
+    }
+
+    /// Get the type behind a reference or a raw pointer, as `u8` for
+    /// `&mut u8` or `*const u8`.
+    pub fn pointee(&self) -> Option<&TypeName<'a>> {
+        match *self {
+            TypeName::Ref { ref inner, .. } | TypeName::Ptr { ref inner, .. } => Some(inner),
+            _ => None,
+        }
+    }
+
+    /// Get the generic arguments of a named type, as `String` and `Vec<u8>`
+    /// for `HashMap<String, Vec<u8>>`. Other types have no generic
+    /// arguments.
+    pub fn generic_args(&self) -> &[GenericArg<'a>] {
+        match *self {
+            TypeName::Path { ref args, .. } => args,
+            _ => &[],
+        }
+    }
+
+    /// Check if both types are named by paths that end with the same
+    /// identifier, as `Vec` in `alloc::vec::Vec<u8>` and `std::vec::Vec<i8>`.
+    /// Generic arguments are not compared.
+    pub fn same_ident(&self, other: &TypeName) -> bool {
+        match (self, other) {
+            (TypeName::Path { path: left, .. }, TypeName::Path { path: right, .. }) => last_segment(left) == last_segment(right),
+            _ => false,
+        }
+    }
+
+    /// Compare the structure of both types, with paths compared only by their
+    /// last segment, so that types named through different re-exports are
+    /// equal.
+    ///
+    /// ```
+    /// use typedef::typename::parse;
+    ///
+    /// let left = parse("core::option::Option<&alloc::string::String>").unwrap();
+    /// let right = parse("std::option::Option<&std::string::String>").unwrap();
+    /// assert!(left.eq_modulo_paths(&right));
+    ///
+    /// let other = parse("std::option::Option<&std::string::String, u8>").unwrap();
+    /// assert!(!left.eq_modulo_paths(&other));
+    /// ```
+    pub fn eq_modulo_paths(&self, other: &TypeName) -> bool {
+        match (self, other) {
+            (
+                TypeName::Path { path: left, args: left_args, signature: left_signature },
+                TypeName::Path { path: right, args: right_args, signature: right_signature },
+            ) => {
+                last_segment(left) == last_segment(right)
+                    && left_args.len() == right_args.len()
+                    && left_args.iter().zip(right_args).all(|(left, right)| left.eq_modulo_paths(right))
+                    && match (left_signature, right_signature) {
+                        (Some(left), Some(right)) => left.eq_modulo_paths(right),
+                        (None, None) => true,
+                        _ => false,
+                    }
+            }
+            (
+                TypeName::Ref { mutable: left_mutable, lifetime: left_lifetime, inner: left },
+                TypeName::Ref { mutable: right_mutable, lifetime: right_lifetime, inner: right },
+            ) => left_mutable == right_mutable && left_lifetime == right_lifetime && left.eq_modulo_paths(right),
+            (
+                TypeName::Ptr { mutable: left_mutable, inner: left },
+                TypeName::Ptr { mutable: right_mutable, inner: right },
+            ) => left_mutable == right_mutable && left.eq_modulo_paths(right),
+            (TypeName::Slice(left), TypeName::Slice(right)) => left.eq_modulo_paths(right),
+            (TypeName::Array(left, left_len), TypeName::Array(right, right_len)) => {
+                left_len == right_len && left.eq_modulo_paths(right)
+            }
+            (TypeName::Tuple(left), TypeName::Tuple(right)) => list_eq_modulo_paths(left, right),
+            (TypeName::Fn(left), TypeName::Fn(right)) => left.eq_modulo_paths(right),
+            (TypeName::Dyn(left), TypeName::Dyn(right)) | (TypeName::Impl(left), TypeName::Impl(right)) => {
+                left.eq_modulo_paths(right)
+            }
+            (TypeName::Never, TypeName::Never) => true,
+            (TypeName::Other(left), TypeName::Other(right)) => left == right,
+            _ => false,
+        }
+    }
+}
+
+impl GenericArg<'_> {
+    /// Compare generic arguments with `TypeName::eq_modulo_paths`.
+    pub fn eq_modulo_paths(&self, other: &GenericArg) -> bool {
+        match (self, other) {
+            (GenericArg::Type(left), GenericArg::Type(right)) => left.eq_modulo_paths(right),
+            (GenericArg::Binding(left_name, left), GenericArg::Binding(right_name, right)) => {
+                left_name == right_name && left.eq_modulo_paths(right)
+            }
+            (GenericArg::Lifetime(left), GenericArg::Lifetime(right)) | (GenericArg::Const(left), GenericArg::Const(right)) => {
+                left == right
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Signature<'_> {
+    /// Compare signatures with `TypeName::eq_modulo_paths`.
+    pub fn eq_modulo_paths(&self, other: &Signature) -> bool {
+        self.unsafety == other.unsafety
+            && self.abi == other.abi
+            && self.variadic == other.variadic
+            && list_eq_modulo_paths(&self.args, &other.args)
+            && match (&self.ret, &other.ret) {
+                (Some(left), Some(right)) => left.eq_modulo_paths(right),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+impl Bounds<'_> {
+    /// Compare bounds with `TypeName::eq_modulo_paths`, in the order they are
+    /// written.
+    pub fn eq_modulo_paths(&self, other: &Bounds) -> bool {
+        self.lifetimes == other.lifetimes && list_eq_modulo_paths(&self.traits, &other.traits)
+    }
+}
+
+fn list_eq_modulo_paths(left: &[TypeName], right: &[TypeName]) -> bool {
+    left.len() == right.len() && left.iter().zip(right).all(|(left, right)| left.eq_modulo_paths(right))
+}
+
+fn last_segment(path: &str) -> &str {
+    let segment = match path.rfind("::") {
+        Some(pos) => &path[pos + 2..],
+        None => path,
+    };
+    format::strip_disambiguator(segment)
+}
+
+impl TypeName<'_> {
+    /// Write the type name, with module paths removed if `short` is set.
+    pub fn write(&self, short: bool, f: &mut dyn fmt::Write) -> fmt::Result {
+        match *self {
+            TypeName::Path { path, ref args, ref signature } => {
+                if short {
+                    f.write_str(path.rsplit("::").next().unwrap_or(path))?;
+                } else {
+                    f.write_str(path)?;
+                }
+                if !args.is_empty() {
+                    f.write_str("<")?;
+                    for (i, arg) in args.iter().enumerate() {
+                        if i > 0 {
+                            f.write_str(", ")?;
+                        }
+                        arg.write(short, f)?;
+                    }
+                    f.write_str(">")?;
+                }
+                if let Some(ref signature) = *signature {
+                    signature.write(short, f)?;
+                }
+                Ok(())
+            },
+            TypeName::Ref { mutable, lifetime, ref inner } => {
+                f.write_str("&")?;
+                if let Some(lifetime) = lifetime {
+                    write!(f, "{} ", lifetime)?;
+                }
+                if mutable {
+                    f.write_str("mut ")?;
+                }
+                inner.write(short, f)
+            },
+            TypeName::Ptr { mutable, ref inner } => {
+                f.write_str(if mutable { "*mut " } else { "*const " })?;
+                inner.write(short, f)
+            },
+            TypeName::Slice(ref inner) => {
+                f.write_str("[")?;
+                inner.write(short, f)?;
+                f.write_str("]")
+            },
+            TypeName::Array(ref inner, len) => {
+                f.write_str("[")?;
+                inner.write(short, f)?;
+                write!(f, "; {}]", len)
+            },
+            TypeName::Tuple(ref items) => {
+                f.write_str("(")?;
+                write_list(items, short, f)?;
+                if items.len() == 1 {
+                    f.write_str(",")?;
+                }
+                f.write_str(")")
+            },
+            TypeName::Fn(ref signature) => {
+                if signature.unsafety {
+                    f.write_str("unsafe ")?;
+                }
+                if let Some(abi) = signature.abi {
+                    write!(f, "extern \"{}\" ", abi)?;
+                }
+                f.write_str("fn")?;
+                signature.write(short, f)
+            },
+            TypeName::Dyn(ref bounds) => {
+                f.write_str("dyn ")?;
+                bounds.write(short, f)
+            },
+            TypeName::Impl(ref bounds) => {
+                f.write_str("impl ")?;
+                bounds.write(short, f)
+            },
+            TypeName::Never => f.write_str("!"),
+            TypeName::Other(text) if short => write!(f, "{}", format::strip_paths(text)),
+            TypeName::Other(text) => f.write_str(text),
+        }
+    }
+}
+
+impl<'a> TypeName<'a> {
+    /// Render the type name with custom options, as `TypeDef::format_with`.
+    ///
+    /// ```
+    /// use typedef::{ NameFormat, PathStyle };
+    /// use typedef::typename::{ parse, GenericArg, TypeName };
+    ///
+    /// let mut name = parse("core::result::Result<alloc::string::String, my_crate::Error>").unwrap();
+    /// if let TypeName::Path { ref mut args, .. } = name {
+    ///     args[1] = GenericArg::Type(parse("std::io::error::Error").unwrap());
+    /// }
+    ///
+    /// let format = NameFormat::new().paths(PathStyle::StripStd);
+    ///
+    /// assert_eq!(name.format_with(&format).to_string(), "Result<String, Error>");
+    /// ```
+    pub fn format_with<'t>(&'t self, format: &NameFormat) -> FormattedTypeName<'t, 'a> {
+        FormattedTypeName {
+            name: self,
+            format: *format,
+        }
+    }
+}
+
+impl fmt::Display for TypeName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f.alternate(), f)
+    }
+}
+
+/// Parsed type name rendered with custom options, created by
+/// `TypeName::format_with`.
+#[derive(Clone, Copy, Debug)]
+pub struct FormattedTypeName<'t, 'a: 't> {
+    name: &'t TypeName<'a>,
+    format: NameFormat,
+}
+
+impl<'t, 'a> fmt::Display for FormattedTypeName<'t, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut name = String::new();
+        self.name.write(false, &mut name)?;
+        format::pad(f, |out| write!(out, "{}", format::render(&name, self.format)))
+    }
+}
+
+impl GenericArg<'_> {
+    /// Write the argument, with module paths removed if `short` is set.
+    pub fn write(&self, short: bool, f: &mut dyn fmt::Write) -> fmt::Result {
+        match *self {
+            GenericArg::Type(ref ty) => ty.write(short, f),
+            GenericArg::Binding(name, ref ty) => {
+                write!(f, "{} = ", name)?;
+                ty.write(short, f)
+            },
+            GenericArg::Lifetime(lifetime) => f.write_str(lifetime),
+            GenericArg::Const(value) => f.write_str(value),
+        }
+    }
+}
+
+impl<'a> Signature<'a> {
+    /// Get the types of the arguments, without the `...` of variadic
+    /// functions.
+    pub fn args(&self) -> &[TypeName<'a>] {
+        &self.args
+    }
+
+    /// Get the return type, or `None` if the function returns `()`.
+    pub fn ret(&self) -> Option<&TypeName<'a>> {
+        self.ret.as_deref()
+    }
+
+    /// Get the ABI, as `C` for `extern "C" fn()` and for `extern fn()`, or
+    /// `None` for the Rust ABI.
+    pub fn abi(&self) -> Option<&'a str> {
+        self.abi
+    }
+
+    /// Check if the function is `unsafe`.
+    pub fn is_unsafe(&self) -> bool {
+        self.unsafety
+    }
+
+    /// Check if the function is variadic, as `unsafe extern "C" fn(i32, ...)`.
+    pub fn is_variadic(&self) -> bool {
+        self.variadic
+    }
+
+    /// Write the arguments and the return type.
+    pub fn write(&self, short: bool, f: &mut dyn fmt::Write) -> fmt::Result {
+        f.write_str("(")?;
+        write_list(&self.args, short, f)?;
+        if self.variadic {
+            f.write_str(if self.args.is_empty() { "..." } else { ", ..." })?;
+        }
+        f.write_str(")")?;
+        if let Some(ref ret) = self.ret {
+            f.write_str(" -> ")?;
+            ret.write(short, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GenericArg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f.alternate(), f)
+    }
+}
+
+fn write_list(items: &[TypeName], short: bool, f: &mut dyn fmt::Write) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        item.write(short, f)?;
+    }
+    Ok(())
+}
+
+/// Parse a type name, returning `None` if it is not a valid type or nests
+/// types more than 128 levels deep.
+pub fn parse(name: &str) -> Option<TypeName<'_>> {
+    parse_with(name, false)
+}
+
+/// Parse a type pattern, where `..` in lists is kept as `TypeName::Other("..")`.
+pub(crate) fn parse_pattern(pattern: &str) -> Option<TypeName<'_>> {
+    parse_with(pattern, true)
+}
+
+fn parse_with(name: &str, patterns: bool) -> Option<TypeName<'_>> {
+    let mut parser = Parser {
+        name,
+        tokens: Tokens::new(name),
+        start: 0,
+        patterns,
+        depth: 0,
+    };
+    let ty = parser.ty()?;
+    match parser.next() {
+        None => Some(ty),
+        Some(_) => None,
+    }
+}
+
+/// Visitor of the parts of a parsed type name, used with `walk`.
+///
+/// Every method has a default implementation that visits the nested parts,
+/// so a visitor only implements the methods for the parts it needs. A
+/// method that is implemented continues into the nested parts by calling
+/// `walk_path`, `walk_generic_arg`, `walk_signature` or `walk_bounds`, or
+/// `visit_type_name` for the inner types of references, pointers, slices,
+/// arrays and tuples.
+///
+/// ```
+/// use typedef::typename::{ parse, walk, GenericArg, Signature, TypeNameVisitor };
+///
+/// #[derive(Default)]
+/// struct Paths<'a>(Vec<&'a str>);
+///
+/// impl<'a> TypeNameVisitor<'a> for Paths<'a> {
+///     fn visit_path(&mut self, path: &'a str, args: &[GenericArg<'a>], signature: Option<&Signature<'a>>) {
+///         self.0.push(path);
+///         typedef::typename::walk_path(self, path, args, signature);
+///     }
+/// }
+///
+/// let name = parse("core::option::Option<&[alloc::string::String]>").unwrap();
+/// let mut paths = Paths::default();
+/// walk(&mut paths, &name);
+///
+/// assert_eq!(paths.0, ["core::option::Option", "alloc::string::String"]);
+/// ```
+pub trait TypeNameVisitor<'a> {
+    /// Visit any type. Dispatches to the other methods by default.
+    fn visit_type_name(&mut self, name: &TypeName<'a>) {
+        walk(self, name);
+    }
+
+    /// Visit a named type, as `alloc::vec::Vec<u8>`, or a trait bound.
+    fn visit_path(&mut self, path: &'a str, args: &[GenericArg<'a>], signature: Option<&Signature<'a>>) {
+        walk_path(self, path, args, signature);
+    }
+
+    /// Visit a generic argument.
+    fn visit_generic_arg(&mut self, arg: &GenericArg<'a>) {
+        walk_generic_arg(self, arg);
+    }
+
+    /// Visit a reference, `&T` or `&mut T`.
+    fn visit_reference(&mut self, mutable: bool, lifetime: Option<&'a str>, inner: &TypeName<'a>) {
+        let _ = mutable;
+        if let Some(lifetime) = lifetime {
+            self.visit_lifetime(lifetime);
+        }
+        self.visit_type_name(inner);
+    }
+
+    /// Visit a raw pointer, `*const T` or `*mut T`.
+    fn visit_pointer(&mut self, mutable: bool, inner: &TypeName<'a>) {
+        let _ = mutable;
+        self.visit_type_name(inner);
+    }
+
+    /// Visit a slice, `[T]`.
+    fn visit_slice(&mut self, inner: &TypeName<'a>) {
+        self.visit_type_name(inner);
+    }
+
+    /// Visit an array, `[T; N]`.
+    fn visit_array(&mut self, inner: &TypeName<'a>, len: &'a str) {
+        let _ = len;
+        self.visit_type_name(inner);
+    }
+
+    /// Visit a tuple, including the unit type `()`.
+    fn visit_tuple(&mut self, items: &[TypeName<'a>]) {
+        for item in items {
+            self.visit_type_name(item);
+        }
+    }
+
+    /// Visit a function pointer.
+    fn visit_fn(&mut self, signature: &Signature<'a>) {
+        walk_signature(self, signature);
+    }
+
+    /// Visit a trait object, as `dyn Send`.
+    fn visit_trait_object(&mut self, bounds: &Bounds<'a>) {
+        walk_bounds(self, bounds);
+    }
+
+    /// Visit an `impl Trait` type.
+    fn visit_impl_trait(&mut self, bounds: &Bounds<'a>) {
+        walk_bounds(self, bounds);
+    }
+
+    /// Visit a lifetime, including the leading `'`.
+    fn visit_lifetime(&mut self, lifetime: &'a str) {
+        let _ = lifetime;
+    }
+
+    /// Visit a const argument.
+    fn visit_const(&mut self, value: &'a str) {
+        let _ = value;
+    }
+
+    /// Visit the never type, `!`.
+    fn visit_never(&mut self) {}
+
+    /// Visit a type that is kept as it is written, as a closure.
+    fn visit_other(&mut self, text: &'a str) {
+        let _ = text;
+    }
+}
+
+/// Visit a type name with the visitor method for its kind.
+pub fn walk<'a, V: TypeNameVisitor<'a> + ?Sized>(visitor: &mut V, name: &TypeName<'a>) {
+    match *name {
+        TypeName::Path { path, ref args, ref signature } => visitor.visit_path(path, args, signature.as_ref()),
+        TypeName::Ref { mutable, lifetime, ref inner } => visitor.visit_reference(mutable, lifetime, inner),
+        TypeName::Ptr { mutable, ref inner } => visitor.visit_pointer(mutable, inner),
+        TypeName::Slice(ref inner) => visitor.visit_slice(inner),
+        TypeName::Array(ref inner, len) => visitor.visit_array(inner, len),
+        TypeName::Tuple(ref items) => visitor.visit_tuple(items),
+        TypeName::Fn(ref signature) => visitor.visit_fn(signature),
+        TypeName::Dyn(ref bounds) => visitor.visit_trait_object(bounds),
+        TypeName::Impl(ref bounds) => visitor.visit_impl_trait(bounds),
+        TypeName::Never => visitor.visit_never(),
+        TypeName::Other(text) => visitor.visit_other(text),
+    }
+}
+
+/// Visit the generic arguments and the `Fn` signature of a path.
+pub fn walk_path<'a, V: TypeNameVisitor<'a> + ?Sized>(
+    visitor: &mut V,
+    path: &'a str,
+    args: &[GenericArg<'a>],
+    signature: Option<&Signature<'a>>,
+) {
+    let _ = path;
+    for arg in args {
+        visitor.visit_generic_arg(arg);
+    }
+    if let Some(signature) = signature {
+        walk_signature(visitor, signature);
+    }
+}
+
+/// Visit the type, the lifetime or the constant of a generic argument.
+pub fn walk_generic_arg<'a, V: TypeNameVisitor<'a> + ?Sized>(visitor: &mut V, arg: &GenericArg<'a>) {
+    match *arg {
+        GenericArg::Type(ref ty) | GenericArg::Binding(_, ref ty) => visitor.visit_type_name(ty),
+        GenericArg::Lifetime(lifetime) => visitor.visit_lifetime(lifetime),
+        GenericArg::Const(value) => visitor.visit_const(value),
+    }
+}
+
+/// Visit the argument types and the return type of a function.
+pub fn walk_signature<'a, V: TypeNameVisitor<'a> + ?Sized>(visitor: &mut V, signature: &Signature<'a>) {
+    for arg in &signature.args {
+        visitor.visit_type_name(arg);
+    }
+    if let Some(ref ret) = signature.ret {
+        visitor.visit_type_name(ret);
+    }
+}
+
+/// Visit the traits and the lifetimes of bounds.
+pub fn walk_bounds<'a, V: TypeNameVisitor<'a> + ?Sized>(visitor: &mut V, bounds: &Bounds<'a>) {
+    for bound in &bounds.traits {
+        visitor.visit_type_name(bound);
+    }
+    for lifetime in &bounds.lifetimes {
+        visitor.visit_lifetime(lifetime);
+    }
+}
+
+/// Classify a type name, as `TypeDef::kind`. Names that can not be parsed are
+/// paths.
+pub fn kind(name: &str) -> TypeKind {
+    if format::is_closure(name) {
+        return TypeKind::Closure;
+    }
+    if format::generated_origin(name).is_some() {
+        return TypeKind::Opaque;
+    }
+    match parse(name) {
+        Some(TypeName::Path { path, .. }) if docs::PRIMITIVES.contains(&path) => TypeKind::Primitive,
+        Some(TypeName::Never) => TypeKind::Primitive,
+        Some(TypeName::Tuple(_)) => TypeKind::Tuple,
+        Some(TypeName::Ref { .. }) => TypeKind::Reference,
+        Some(TypeName::Ptr { .. }) => TypeKind::RawPointer,
+        Some(TypeName::Slice(_)) => TypeKind::Slice,
+        Some(TypeName::Array(..)) => TypeKind::Array,
+        Some(TypeName::Fn(_)) => TypeKind::FnPointer,
+        Some(TypeName::Dyn(_)) => TypeKind::TraitObject,
+        Some(TypeName::Impl(_)) => TypeKind::Opaque,
+        Some(TypeName::Path { .. }) | Some(TypeName::Other(_)) | None => TypeKind::Path,
+    }
+}
+
+#[derive(Clone)]
+struct Parser<'a> {
+    name: &'a str,
+    tokens: Tokens<'a>,
+    /// Position of the last consumed token.
+    start: usize,
+    /// Accept `..` in lists of types and bounds.
+    patterns: bool,
+    /// Number of types being parsed that contain the current one.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Option<Token<'a>> {
+        loop {
+            self.start = self.tokens.position();
+            match self.tokens.next()? {
+                Token::Punct(" ") => continue,
+                token => return Some(token),
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.clone().next()
+    }
+
+    fn eat(&mut self, token: Token) -> bool {
+        if self.peek() == Some(token) {
+            self.next();
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, token: Token) -> Option<()> {
+        if self.eat(token) { Some(()) } else { None }
+    }
+
+    fn lifetime(&mut self) -> Option<&'a str> {
+        match self.peek() {
+            Some(Token::Lifetime(lifetime)) => {
+                self.next();
+                Some(lifetime)
+            },
+            _ => None,
+        }
+    }
+
+    fn ty(&mut self) -> Option<TypeName<'a>> {
+        if self.depth >= MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let ty = self.nested_ty();
+        self.depth -= 1;
+        ty
+    }
+
+    fn nested_ty(&mut self) -> Option<TypeName<'a>> {
+        let token = self.next()?;
+        let start = self.start;
+        Some(match token {
+            Token::Punct("&") => {
+                let lifetime = self.lifetime();
+                let mutable = self.eat(Token::Ident("mut"));
+                TypeName::Ref { mutable, lifetime, inner: Box::new(self.ty()?) }
+            },
+            Token::Punct("*") => {
+                let mutable = match self.next()? {
+                    Token::Ident("mut") => true,
+                    Token::Ident("const") => false,
+                    _ => return None,
+                };
+                TypeName::Ptr { mutable, inner: Box::new(self.ty()?) }
+            },
+            Token::Punct("[") => {
+                let inner = Box::new(self.ty()?);
+                if self.eat(Token::Punct(";")) {
+                    let len_start = self.tokens.position();
+                    self.skip_to_close("[", "]")?;
+                    TypeName::Array(inner, self.name[len_start..self.start].trim())
+                } else {
+                    self.expect(Token::Punct("]"))?;
+                    TypeName::Slice(inner)
+                }
+            },
+            Token::Punct("(") => TypeName::Tuple(self.list(")")?),
+            Token::Punct("!") => TypeName::Never,
+            Token::Punct("<") => {
+                self.skip_to_close("<", ">")?;
+                self.skip_path_continuation();
+                TypeName::Other(&self.name[start..self.tokens.position()])
+            },
+            Token::Ident("dyn") => TypeName::Dyn(self.bounds()?),
+            Token::Ident("impl") => TypeName::Impl(self.bounds()?),
+            Token::Ident("for") if self.eat(Token::Punct("<")) => {
+                self.skip_to_close("<", ">")?;
+                return self.ty();
+            },
+            Token::Ident("unsafe") | Token::Ident("extern") => {
+                let mut unsafety = false;
+                let mut abi = None;
+                let mut qualifier = token;
+                loop {
+                    match qualifier {
+                        Token::Ident("fn") => break,
+                        Token::Ident("unsafe") => unsafety = true,
+                        Token::Ident("extern") => abi = Some("C"),
+                        Token::Punct(literal) if abi.is_some() && literal.starts_with('"') => {
+                            abi = Some(literal.trim_matches('"'));
+                        },
+                        _ => return None,
+                    }
+                    qualifier = self.next()?;
+                }
+                TypeName::Fn(Signature { unsafety, abi, ..self.signature()? })
+            },
+            Token::Ident("fn") => TypeName::Fn(self.signature()?),
+            Token::Ident(first) => {
+                let path = self.tokens.take_path(first);
+                let mut args = Vec::new();
+                let mut signature = None;
+                if self.eat(Token::Punct("<")) {
+                    args = self.generic_args()?;
+                } else if self.peek() == Some(Token::Punct("(")) {
+                    signature = Some(self.signature()?);
+                }
+                if self.skip_path_continuation() || path.contains("{{") {
+                    return Some(TypeName::Other(&self.name[start..self.tokens.position()]));
+                }
+                TypeName::Path { path, args, signature }
+            },
+            _ => return None,
+        })
+    }
+
+    /// Parse types separated with `,` up to and including `close`.
+    fn list(&mut self, close: &str) -> Option<Vec<TypeName<'a>>> {
+        let mut items = Vec::new();
+        while !self.eat(Token::Punct(close)) {
+            if self.rest() {
+                items.push(TypeName::Other(".."));
+            } else {
+                items.push(self.ty()?);
+            }
+            if !self.eat(Token::Punct(",")) {
+                self.expect(Token::Punct(close))?;
+                break;
+            }
+        }
+        Some(items)
+    }
+
+    fn signature(&mut self) -> Option<Signature<'a>> {
+        self.expect(Token::Punct("("))?;
+        let mut args = Vec::new();
+        let mut variadic = false;
+        while !self.eat(Token::Punct(")")) {
+            if self.rest() {
+                args.push(TypeName::Other(".."));
+            } else if self.eat(Token::Punct(".")) {
+                self.expect(Token::Punct("."))?;
+                self.expect(Token::Punct("."))?;
+                variadic = true;
+                self.expect(Token::Punct(")"))?;
+                break;
+            } else {
+                args.push(self.ty()?);
+            }
+            if !self.eat(Token::Punct(",")) {
+                self.expect(Token::Punct(")"))?;
+                break;
+            }
+        }
+        let ret = match self.eat(Token::Punct("->")) {
+            true => Some(Box::new(self.ty()?)),
+            false => None,
+        };
+        Some(Signature {
+            args,
+            ret,
+            unsafety: false,
+            abi: None,
+            variadic,
+        })
+    }
+
+    fn generic_args(&mut self) -> Option<Vec<GenericArg<'a>>> {
+        let mut args = Vec::new();
+        while !self.eat(Token::Punct(">")) {
+            if self.rest() {
+                args.push(GenericArg::Type(TypeName::Other("..")));
+            } else if let Some(lifetime) = self.lifetime() {
+                args.push(GenericArg::Lifetime(lifetime));
+            } else if let Some(value) = self.const_arg() {
+                args.push(GenericArg::Const(value));
+            } else {
+                let mut binding = self.clone();
+                match (binding.next(), binding.next()) {
+                    (Some(Token::Ident(name)), Some(Token::Punct("="))) => {
+                        *self = binding;
+                        args.push(GenericArg::Binding(name, self.ty()?));
+                    },
+                    _ => args.push(GenericArg::Type(self.ty()?)),
+                }
+            }
+            if !self.eat(Token::Punct(",")) {
+                self.expect(Token::Punct(">"))?;
+                break;
+            }
+        }
+        Some(args)
+    }
+
+    /// Consume `..` if patterns are accepted.
+    fn rest(&mut self) -> bool {
+        let mut parser = self.clone();
+        if !self.patterns || parser.next() != Some(Token::Punct(".")) || parser.next() != Some(Token::Punct(".")) {
+            return false;
+        }
+        if parser.peek() == Some(Token::Punct(".")) {
+            return false;
+        }
+        *self = parser;
+        true
+    }
+
+    /// Consume a const argument: a number, a negative number or a `bool`.
+    fn const_arg(&mut self) -> Option<&'a str> {
+        let mut parser = self.clone();
+        let value = match parser.next()? {
+            Token::Ident(value) if value.starts_with(|c: char| c.is_ascii_digit()) || value == "true" || value == "false" => value,
+            Token::Punct("-") => {
+                let start = parser.start;
+                match parser.next()? {
+                    Token::Ident(_) => &self.name[start..parser.tokens.position()],
+                    _ => return None,
+                }
+            },
+            _ => return None,
+        };
+        *self = parser;
+        Some(value)
+    }
+
+    fn bounds(&mut self) -> Option<Bounds<'a>> {
+        let mut bounds = Bounds { traits: Vec::new(), lifetimes: Vec::new() };
+        loop {
+            if self.rest() {
+                bounds.traits.push(TypeName::Other(".."));
+            } else if let Some(lifetime) = self.lifetime() {
+                bounds.lifetimes.push(lifetime);
+            } else {
+                bounds.traits.push(self.ty()?);
+            }
+            if !self.eat(Token::Punct("+")) {
+                return Some(bounds);
+            }
+        }
+    }
+
+    /// Consume tokens up to and including `close` that closes already
+    /// consumed `open`.
+    fn skip_to_close(&mut self, open: &str, close: &str) -> Option<()> {
+        let mut depth = 0;
+        loop {
+            match self.next()? {
+                Token::Punct(token) if token == close && depth == 0 => return Some(()),
+                Token::Punct(token) if token == close => depth -= 1,
+                Token::Punct(token) if token == open => depth += 1,
+                _ => {},
+            }
+        }
+    }
+
+    /// Consume path segments and generic arguments that continue a path
+    /// after generic arguments, as in `my_crate::f<u8>::{{closure}}`, and the
+    /// closure index, as in `{{closure}}#0`.
+    fn skip_path_continuation(&mut self) -> bool {
+        let mut skipped = false;
+        loop {
+            if self.tokens.skip_if(&[Token::PathSep]) {
+                match self.next() {
+                    Some(Token::Punct("<")) => {
+                        let _ = self.skip_to_close("<", ">");
+                    },
+                    Some(Token::Ident(ident)) => {
+                        self.tokens.take_path(ident);
+                    },
+                    _ => {},
+                }
+            } else if self.tokens.skip_if(&[Token::Punct("<")]) {
+                let _ = self.skip_to_close("<", ">");
+            } else if self.tokens.skip_if(&[Token::Punct("#")]) {
+                self.tokens.next();
+            } else {
+                return skipped;
+            }
+            skipped = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::boxed::Box;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use super::{kind, parse, walk, walk_path, Bounds, GenericArg, Signature, TypeKind, TypeName, TypeNameVisitor};
+    use {NameFormat, PathStyle};
+
+    fn path<'a>(path: &'a str, args: Vec<TypeName<'a>>) -> TypeName<'a> {
+        TypeName::Path {
+            path,
+            args: args.into_iter().map(GenericArg::Type).collect(),
+            signature: None,
+        }
+    }
+
+    fn bounds<'a>(traits: Vec<TypeName<'a>>, lifetimes: Vec<&'a str>) -> Bounds<'a> {
+        Bounds { traits, lifetimes }
+    }
+
+    #[test]
+    fn should_parse_generic_types() {
+        assert_eq!(
+            parse("std::collections::hash::map::HashMap<alloc::string::String, alloc::vec::Vec<u8>>").unwrap(),
+            path("std::collections::hash::map::HashMap", vec![
+                path("alloc::string::String", vec![]),
+                path("alloc::vec::Vec", vec![path("u8", vec![])]),
+            ])
+        );
+        assert_eq!(
+            parse("my_crate::Foo<'_, 3>").unwrap(),
+            TypeName::Path {
+                path: "my_crate::Foo",
+                args: vec![GenericArg::Lifetime("'_"), GenericArg::Const("3")],
+                signature: None,
+            }
+        );
+    }
+
+    #[test]
+    fn should_parse_types_without_names() {
+        let u8 = || Box::new(path("u8", vec![]));
+
+        assert_eq!(
+            parse("&'static mut [u8]").unwrap(),
+            TypeName::Ref { mutable: true, lifetime: Some("'static"), inner: Box::new(TypeName::Slice(u8())) }
+        );
+        assert_eq!(parse("*const [u8; 4]").unwrap(), TypeName::Ptr { mutable: false, inner: Box::new(TypeName::Array(u8(), "4")) });
+        assert_eq!(parse("()").unwrap(), TypeName::Tuple(vec![]));
+        assert_eq!(parse("(u8,)").unwrap(), TypeName::Tuple(vec![*u8()]));
+        assert_eq!(parse("!").unwrap(), TypeName::Never);
+        assert_eq!(
+            parse("extern \"system\" fn()").unwrap(),
+            TypeName::Fn(Signature { args: vec![], ret: None, unsafety: false, abi: Some("system"), variadic: false })
+        );
+        assert_eq!(
+            parse("for<'a> unsafe extern \"C\" fn(&'a u8) -> u8").unwrap(),
+            TypeName::Fn(Signature {
+                args: vec![TypeName::Ref { mutable: false, lifetime: Some("'a"), inner: u8() }],
+                ret: Some(u8()),
+                unsafety: true,
+                abi: Some("C"),
+                variadic: false,
+            })
+        );
+    }
+
+    #[test]
+    fn should_parse_traits() {
+        assert_eq!(
+            parse("dyn core::ops::function::Fn(u8) -> u8 + core::marker::Send + 'static").unwrap(),
+            TypeName::Dyn(bounds(vec![
+                TypeName::Path {
+                    path: "core::ops::function::Fn",
+                    args: vec![],
+                    signature: Some(Signature {
+                        args: vec![path("u8", vec![])],
+                        ret: Some(Box::new(path("u8", vec![]))),
+                        unsafety: false,
+                        abi: None,
+                        variadic: false,
+                    }),
+                },
+                path("core::marker::Send", vec![]),
+            ], vec!["'static"]))
+        );
+        assert_eq!(
+            parse("impl core::future::future::Future<Output = u8>").unwrap(),
+            TypeName::Impl(bounds(vec![TypeName::Path {
+                path: "core::future::future::Future",
+                args: vec![GenericArg::Binding("Output", path("u8", vec![]))],
+                signature: None,
+            }], vec![]))
+        );
+    }
+
+    #[test]
+    fn should_keep_other_types_as_written() {
+        assert_eq!(parse("my_crate::main::{{closure}}").unwrap(), TypeName::Other("my_crate::main::{{closure}}"));
+        assert_eq!(parse("my_crate::f<u8>::{{closure}}").unwrap(), TypeName::Other("my_crate::f<u8>::{{closure}}"));
+        assert_eq!(parse("my_crate::main::{{closure}}#0").unwrap(), TypeName::Other("my_crate::main::{{closure}}#0"));
+        assert_eq!(parse("<my_crate::Foo as my_crate::Tr>::Out").unwrap(), TypeName::Other("<my_crate::Foo as my_crate::Tr>::Out"));
+    }
+
+    #[test]
+    fn should_write_parsed_names() {
+        let names = [
+            "std::collections::hash::map::HashMap<alloc::string::String, alloc::vec::Vec<u8>>",
+            "&mut [*const (u8,); 4]",
+            "unsafe extern \"C\" fn(u8, ()) -> !",
+            "alloc::boxed::Box<dyn core::ops::function::Fn(&str) -> u8 + core::marker::Send>",
+            "impl core::future::future::Future<Output = u8>",
+            "my_crate::Foo<my_crate::main::{{closure}}>",
+        ];
+
+        for name in &names {
+            let mut written = String::new();
+            parse(name).unwrap().write(false, &mut written).unwrap();
+            assert_eq!(written, *name);
+        }
+
+        let mut short = String::new();
+        parse(names[0]).unwrap().write(true, &mut short).unwrap();
+        assert_eq!(short, "HashMap<String, Vec<u8>>");
+        assert_eq!(format!("{:#}", parse(names[3]).unwrap()), "Box<dyn Fn(&str) -> u8 + Send>");
+        assert_eq!(format!("{}", parse(names[1]).unwrap()), names[1]);
+    }
+
+    #[test]
+    fn should_parse_nested_types() {
+        let u8 = || path("u8", vec![]);
+
+        assert_eq!(
+            parse("core::option::Option<&[(u8, [u8; 2])]>").unwrap(),
+            path("core::option::Option", vec![TypeName::Ref {
+                mutable: false,
+                lifetime: None,
+                inner: Box::new(TypeName::Slice(Box::new(TypeName::Tuple(vec![
+                    u8(),
+                    TypeName::Array(Box::new(u8()), "2"),
+                ])))),
+            }])
+        );
+        assert_eq!(
+            parse("*mut *const u8").unwrap(),
+            TypeName::Ptr { mutable: true, inner: Box::new(TypeName::Ptr { mutable: false, inner: Box::new(u8()) }) }
+        );
+        assert_eq!(
+            parse("[[u8; 2]; 3]").unwrap(),
+            TypeName::Array(Box::new(TypeName::Array(Box::new(u8()), "2")), "3")
+        );
+        assert_eq!(
+            parse("((), (u8,))").unwrap(),
+            TypeName::Tuple(vec![TypeName::Tuple(vec![]), TypeName::Tuple(vec![u8()])])
+        );
+        assert_eq!(
+            parse("fn(fn(u8) -> u8) -> fn()").unwrap(),
+            TypeName::Fn(Signature {
+                args: vec![TypeName::Fn(Signature { args: vec![u8()], ret: Some(Box::new(u8())), unsafety: false, abi: None, variadic: false })],
+                ret: Some(Box::new(TypeName::Fn(Signature { args: vec![], ret: None, unsafety: false, abi: None, variadic: false }))),
+                unsafety: false,
+                abi: None,
+                variadic: false,
+            })
+        );
+    }
+
+    #[test]
+    fn should_parse_names_with_irregular_spacing() {
+        let expected = parse("alloc::vec::Vec<(u8, &mut [u8; 4])>").unwrap();
+
+        assert_eq!(parse("alloc::vec::Vec< ( u8 ,&mut[u8;4] ) >").unwrap(), expected);
+        assert_eq!(parse("alloc::vec::Vec<(u8,&mut  [u8 ; 4])>").unwrap(), expected);
+        assert_eq!(parse(" u8 ").unwrap(), path("u8", vec![]));
+    }
+
+    #[test]
+    fn should_parse_array_lengths_as_written() {
+        assert_eq!(parse("[u8; N]").unwrap(), TypeName::Array(Box::new(path("u8", vec![])), "N"));
+        assert_eq!(parse("[u8; {N + 1}]").unwrap(), TypeName::Array(Box::new(path("u8", vec![])), "{N + 1}"));
+    }
+
+    #[test]
+    fn should_parse_trait_objects_in_generic_arguments() {
+        assert_eq!(
+            parse("alloc::boxed::Box<dyn core::error::Error + core::marker::Send + core::marker::Sync>").unwrap(),
+            path("alloc::boxed::Box", vec![TypeName::Dyn(bounds(vec![
+                path("core::error::Error", vec![]),
+                path("core::marker::Send", vec![]),
+                path("core::marker::Sync", vec![]),
+            ], vec![]))])
+        );
+        assert_eq!(
+            parse("&dyn core::iter::traits::iterator::Iterator<Item = &u8>").unwrap(),
+            TypeName::Ref {
+                mutable: false,
+                lifetime: None,
+                inner: Box::new(TypeName::Dyn(bounds(vec![TypeName::Path {
+                    path: "core::iter::traits::iterator::Iterator",
+                    args: vec![GenericArg::Binding("Item", TypeName::Ref { mutable: false, lifetime: None, inner: Box::new(path("u8", vec![])) })],
+                    signature: None,
+                }], vec![]))),
+            }
+        );
+    }
+
+    #[test]
+    fn should_get_primary_trait_and_additional_bounds() {
+        let name = parse("dyn core::error::Error + core::marker::Send + core::marker::Sync + 'static").unwrap();
+        let bounds = match name {
+            TypeName::Dyn(ref bounds) => bounds,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(bounds.primary_trait(), Some(&path("core::error::Error", vec![])));
+        assert_eq!(bounds.auto_traits(), [&path("core::marker::Send", vec![]), &path("core::marker::Sync", vec![])]);
+        assert_eq!(bounds.lifetimes, ["'static"]);
+        assert_eq!(format!("{}", name), "dyn core::error::Error + core::marker::Send + core::marker::Sync + 'static");
+        assert_eq!(format!("{:#}", name), "dyn Error + Send + Sync + 'static");
+
+        match parse("dyn core::marker::Send + core::panic::unwind_safe::UnwindSafe").unwrap() {
+            TypeName::Dyn(bounds) => {
+                assert_eq!(bounds.primary_trait(), None);
+                assert_eq!(bounds.auto_traits().len(), 2);
+            },
+            _ => unreachable!(),
+        }
+        assert_eq!(format!("{}", parse("impl 'static + core::marker::Send").unwrap()), "impl core::marker::Send + 'static");
+    }
+
+    #[test]
+    fn should_render_changed_names_with_format() {
+        let mut name = parse("alloc::vec::Vec<my_crate::inner::Foo<&'static str>>").unwrap();
+        if let TypeName::Path { ref mut args, .. } = name {
+            if let GenericArg::Type(TypeName::Path { ref mut path, .. }) = args[0] {
+                *path = path.strip_prefix("my_crate::inner::").map_or(*path, |rest| rest);
+            }
+            args.push(GenericArg::Type(TypeName::Tuple(vec![])));
+        }
+
+        assert_eq!(format!("{}", name), "alloc::vec::Vec<Foo<&'static str>, ()>");
+        assert_eq!(name.format_with(&NameFormat::new()).to_string(), "alloc::vec::Vec<Foo<&'static str>, ()>");
+        assert_eq!(
+            name.format_with(&NameFormat::new().paths(PathStyle::Short).lifetimes(false)).to_string(),
+            "Vec<Foo<&str>, ()>"
+        );
+        assert_eq!(name.format_with(&NameFormat::new().max_depth(1)).to_string(), "alloc::vec::Vec<Foo<…>, ()>");
+        assert_eq!(format!("[{:>10}]", parse("u8").unwrap().format_with(&NameFormat::new())), "[        u8]");
+    }
+
+    #[derive(Default)]
+    struct Counts<'a> {
+        paths: Vec<&'a str>,
+        references: usize,
+        lifetimes: Vec<&'a str>,
+        consts: Vec<&'a str>,
+        others: usize,
+    }
+
+    impl<'a> TypeNameVisitor<'a> for Counts<'a> {
+        fn visit_path(&mut self, path: &'a str, args: &[GenericArg<'a>], signature: Option<&Signature<'a>>) {
+            self.paths.push(path);
+            walk_path(self, path, args, signature);
+        }
+
+        fn visit_reference(&mut self, _mutable: bool, lifetime: Option<&'a str>, inner: &TypeName<'a>) {
+            self.references += 1;
+            if let Some(lifetime) = lifetime {
+                self.visit_lifetime(lifetime);
+            }
+            self.visit_type_name(inner);
+        }
+
+        fn visit_lifetime(&mut self, lifetime: &'a str) {
+            self.lifetimes.push(lifetime);
+        }
+
+        fn visit_const(&mut self, value: &'a str) {
+            self.consts.push(value);
+        }
+
+        fn visit_other(&mut self, _text: &'a str) {
+            self.others += 1;
+        }
+    }
+
+    #[test]
+    fn should_walk_all_parts_of_names() {
+        let name = parse(
+            "my_crate::Foo<'a, 3, (&'a u8, [*const my_crate::Bar; 2]), \
+             fn(&str) -> alloc::boxed::Box<dyn core::ops::function::Fn(u16) -> u32 + 'static>, my_crate::main::{{closure}}>"
+        ).unwrap();
+        let mut counts = Counts::default();
+        walk(&mut counts, &name);
+
+        assert_eq!(counts.paths, [
+            "my_crate::Foo", "u8", "my_crate::Bar", "str", "alloc::boxed::Box", "core::ops::function::Fn", "u16", "u32",
+        ]);
+        assert_eq!(counts.references, 2);
+        assert_eq!(counts.lifetimes, ["'a", "'a", "'static"]);
+        assert_eq!(counts.consts, ["3"]);
+        assert_eq!(counts.others, 1);
+    }
+
+    #[test]
+    fn should_stop_walking_where_visitor_does_not_continue() {
+        struct Outer<'a>(Vec<&'a str>);
+
+        impl<'a> TypeNameVisitor<'a> for Outer<'a> {
+            fn visit_path(&mut self, path: &'a str, _args: &[GenericArg<'a>], _signature: Option<&Signature<'a>>) {
+                self.0.push(path);
+            }
+        }
+
+        let mut outer = Outer(Vec::new());
+        walk(&mut outer, &parse("(alloc::vec::Vec<u8>, &core::option::Option<u8>)").unwrap());
+
+        assert_eq!(outer.0, ["alloc::vec::Vec", "core::option::Option"]);
+    }
+
+    #[test]
+    fn should_compare_names_without_paths() {
+        let same = |left: &str, right: &str| parse(left).unwrap().eq_modulo_paths(&parse(right).unwrap());
+
+        assert!(same("alloc::vec::Vec<u8>", "std::vec::Vec<u8>"));
+        assert!(same("my_crate[1a2b]::Foo", "Foo"));
+        assert!(same("&'a mut [a::Foo; 3]", "&'a mut [b::Foo; 3]"));
+        assert!(same("fn(a::Foo) -> a::Bar", "fn(b::Foo) -> b::Bar"));
+        assert!(same("dyn a::Tr<Output = a::Foo> + Send", "dyn b::Tr<Output = b::Foo> + Send"));
+        assert!(same("a::F<3, 'a>", "b::F<3, 'a>"));
+
+        assert!(!same("alloc::vec::Vec<u8>", "alloc::vec::Vec<i8>"));
+        assert!(!same("a::Foo", "a::Foo<u8>"));
+        assert!(!same("&a::Foo", "&mut a::Foo"));
+        assert!(!same("[a::Foo; 3]", "[a::Foo; 4]"));
+        assert!(!same("fn(a::Foo)", "unsafe fn(a::Foo)"));
+        assert!(!same("dyn Send + Sync", "dyn Sync + Send"));
+        assert!(!same("a::Foo", "&a::Foo"));
+    }
+
+    #[test]
+    fn should_compare_identifiers() {
+        let same = |left: &str, right: &str| parse(left).unwrap().same_ident(&parse(right).unwrap());
+
+        assert!(same("alloc::vec::Vec<u8>", "my_crate::Vec"));
+        assert!(same("a[1a2b]::Foo", "b::Foo<i8>"));
+        assert!(!same("a::Foo", "a::Bar"));
+        assert!(!same("&a::Foo", "a::Foo"));
+    }
+
+    #[test]
+    fn should_parse_tuples_arrays_and_consts() {
+        let name = parse("(u8, [u16; 16], [u32])").unwrap();
+        let elements = name.tuple_elements().unwrap();
+
+        assert_eq!(elements.len(), 3);
+        assert_eq!(elements[0].tuple_elements(), None);
+        assert_eq!(elements[1].array_len(), Some(16));
+        assert_eq!(elements[1].element_type(), Some(&path("u16", vec![])));
+        assert_eq!(elements[2].array_len(), None);
+        assert_eq!(elements[2].element_type(), Some(&path("u32", vec![])));
+        assert_eq!(parse("()").unwrap().tuple_elements(), Some(&[][..]));
+        assert_eq!(parse("[u8; N]").unwrap().array_len(), None);
+
+        let name = parse("my_crate::Foo<u8, 3, -1, true>").unwrap();
+
+        assert_eq!(name.generic_args(), &[
+            GenericArg::Type(path("u8", vec![])),
+            GenericArg::Const("3"),
+            GenericArg::Const("-1"),
+            GenericArg::Const("true"),
+        ]);
+        assert_eq!(format!("{}", name), "my_crate::Foo<u8, 3, -1, true>");
+        assert_eq!(parse("-1"), None);
+    }
+
+    #[test]
+    fn should_parse_function_pointers() {
+        let name = parse("unsafe extern \"C\" fn(i32, *const u8, ...) -> isize").unwrap();
+        let signature = name.fn_signature().unwrap();
+
+        assert_eq!(signature.args(), &[path("i32", vec![]), TypeName::Ptr { mutable: false, inner: Box::new(path("u8", vec![])) }]);
+        assert_eq!(signature.ret(), Some(&path("isize", vec![])));
+        assert_eq!(signature.abi(), Some("C"));
+        assert!(signature.is_unsafe());
+        assert!(signature.is_variadic());
+        assert_eq!(format!("{}", name), "unsafe extern \"C\" fn(i32, *const u8, ...) -> isize");
+
+        let signature = parse("fn(u8)").unwrap().fn_signature().cloned().unwrap();
+
+        assert_eq!(signature.ret(), None);
+        assert_eq!(signature.abi(), None);
+        assert!(!signature.is_unsafe());
+        assert!(!signature.is_variadic());
+        assert_eq!(parse("extern fn()").unwrap().fn_signature().unwrap().abi(), Some("C"));
+        assert_eq!(format!("{}", parse("unsafe extern \"C\" fn(...)").unwrap()), "unsafe extern \"C\" fn(...)");
+        assert_eq!(parse("u8").unwrap().fn_signature(), None);
+        assert_eq!(parse("fn(..., u8)"), None);
+        assert_eq!(parse("fn(..)"), None);
+    }
+
+    #[test]
+    fn should_parse_references_and_pointers() {
+        let name = parse("&'a mut *const u8").unwrap();
+
+        assert!(name.is_reference());
+        assert_eq!(name, TypeName::Ref {
+            mutable: true,
+            lifetime: Some("'a"),
+            inner: Box::new(TypeName::Ptr { mutable: false, inner: Box::new(path("u8", vec![])) }),
+        });
+        assert!(!name.pointee().unwrap().is_reference());
+        assert_eq!(name.pointee().unwrap().pointee(), Some(&path("u8", vec![])));
+        assert_eq!(parse("u8").unwrap().pointee(), None);
+        assert_ne!(parse("&u8"), parse("&mut u8"));
+        assert_ne!(parse("*const u8"), parse("*mut u8"));
+        assert_eq!(format!("{}", parse("&'static  mut u8").unwrap()), "&'static mut u8");
+        assert_eq!(format!("{}", parse("my_crate::Foo<'a,u8>").unwrap()), "my_crate::Foo<'a, u8>");
+    }
+
+    #[test]
+    fn should_get_generic_arguments() {
+        let name = parse("alloc::vec::Vec<core::option::Option<u8>>").unwrap();
+
+        assert_eq!(name.generic_args(), &[GenericArg::Type(path("core::option::Option", vec![path("u8", vec![])]))]);
+        assert_eq!(parse("&alloc::vec::Vec<u8>").unwrap().generic_args(), &[]);
+        assert_eq!(parse("alloc::string::String").unwrap().generic_args(), &[]);
+    }
+
+    #[test]
+    fn should_keep_disambiguators_in_paths() {
+        assert_eq!(parse("my_crate[4f2a]::Foo<u8>").unwrap(), path("my_crate[4f2a]::Foo", vec![path("u8", vec![])]));
+    }
+
+    #[test]
+    fn should_classify_names() {
+        assert_eq!(kind("u8"), TypeKind::Primitive);
+        assert_eq!(kind("!"), TypeKind::Primitive);
+        assert_eq!(kind("()"), TypeKind::Tuple);
+        assert_eq!(kind("(u8, alloc::string::String)"), TypeKind::Tuple);
+        assert_eq!(kind("&mut [u8]"), TypeKind::Reference);
+        assert_eq!(kind("*const u8"), TypeKind::RawPointer);
+        assert_eq!(kind("[u8]"), TypeKind::Slice);
+        assert_eq!(kind("[u8; 4]"), TypeKind::Array);
+        assert_eq!(kind("unsafe extern \"C\" fn(u8)"), TypeKind::FnPointer);
+        assert_eq!(kind("dyn core::any::Any + core::marker::Send"), TypeKind::TraitObject);
+        assert_eq!(kind("impl core::marker::Send"), TypeKind::Opaque);
+        assert_eq!(kind("alloc::vec::Vec<u8>"), TypeKind::Path);
+        assert_eq!(kind("my_crate::u8"), TypeKind::Path);
+        assert_eq!(kind("<my_crate::Foo as my_crate::Tr>::Out"), TypeKind::Path);
+        assert_eq!(kind("my_crate::main::{{closure}}"), TypeKind::Closure);
+        assert_eq!(kind("my_crate::f::{{opaque}}#0"), TypeKind::Opaque);
+        assert_eq!(kind("Vec<u8"), TypeKind::Path);
+    }
+
+    #[test]
+    fn should_reject_invalid_names() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("Vec<u8"), None);
+        assert_eq!(parse("u8 u8"), None);
+        assert_eq!(parse("(u8"), None);
+        assert_eq!(parse("&"), None);
+        assert_eq!(parse("*u8"), None);
+        assert_eq!(parse("[u8"), None);
+        assert_eq!(parse("u8>"), None);
+        assert_eq!(parse("fn(u8"), None);
+        assert_eq!(parse("alloc::vec::Vec<u8,,>"), None);
+    }
+
+    #[test]
+    fn should_reject_deeply_nested_names() {
+        assert!(parse(&format!("{}u8", "&".repeat(127))).is_some());
+        assert_eq!(parse(&format!("{}u8", "&".repeat(128))), None);
+        assert_eq!(parse(&format!("{}u8", "&".repeat(1_000_000))), None);
+        assert_eq!(parse(&format!("{}u8{}", "Vec<".repeat(200), ">".repeat(200))), None);
+    }
+}
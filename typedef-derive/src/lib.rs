@@ -0,0 +1,91 @@
+//! Derive macro for `typedef`, which registers a type in the typedef registry
+//! at startup.
+//!
+//! Use it through the `derive` feature of `typedef`, which re-exports
+//! `RegisterType`.
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+/// Register the type in the typedef registry at startup, so that it is found
+/// by `TypeDef::from_id` and `TypeDef::from_name`.
+///
+/// With `#[typedef(rename = "...")]` the type also implements `NamedType`
+/// with this name, and is registered and found by it.
+#[proc_macro_derive(RegisterType, attributes(typedef))]
+pub fn register_type(input: TokenStream) -> TokenStream {
+    let output = match expand(input) {
+        Ok(output) => output,
+        Err(message) => format!("compile_error!({:?});", message),
+    };
+    output.parse().expect("generated code is valid")
+}
+
+fn expand(input: TokenStream) -> Result<String, String> {
+    let mut rename = None;
+    let mut tokens = input.into_iter();
+    let ident = loop {
+        match tokens.next() {
+            Some(TokenTree::Group(ref group)) if group.delimiter() == Delimiter::Bracket => {
+                if let Some(name) = attribute_rename(group.stream())? {
+                    rename = Some(name);
+                }
+            }
+            Some(TokenTree::Ident(ref keyword)) if ["struct", "enum", "union"].contains(&&*keyword.to_string()) => {
+                match tokens.next() {
+                    Some(TokenTree::Ident(ident)) => break ident.to_string(),
+                    _ => return Err("expected the name of the type".into()),
+                }
+            }
+            Some(_) => {}
+            None => return Err("`RegisterType` can only be derived for structs, enums and unions".into()),
+        }
+    };
+    if let Some(TokenTree::Punct(ref punct)) = tokens.next() {
+        if punct.as_char() == '<' {
+            return Err("`RegisterType` can not be derived for generic types, use `register_type!` with the concrete types".into());
+        }
+    }
+
+    Ok(match rename {
+        Some(name) => format!(
+            "impl ::typedef::NamedType for {ident} {{ const NAME: &'static str = {name:?}; }} \
+             ::typedef::register_type!(@typedefs ::typedef::TypeDef::of_named::<{ident}>());",
+            ident = ident,
+            name = name,
+        ),
+        None => format!("::typedef::register_type!({});", ident),
+    })
+}
+
+/// Get the name of `#[typedef(rename = "...")]`, or `None` for other
+/// attributes.
+fn attribute_rename(attribute: TokenStream) -> Result<Option<String>, String> {
+    let mut tokens = attribute.into_iter();
+    match tokens.next() {
+        Some(TokenTree::Ident(ref ident)) if ident.to_string() == "typedef" => {}
+        _ => return Ok(None),
+    }
+    let args = match tokens.next() {
+        Some(TokenTree::Group(ref group)) if group.delimiter() == Delimiter::Parenthesis => group.stream(),
+        _ => return Err("expected `#[typedef(rename = \"...\")]`".into()),
+    };
+
+    let args: Vec<TokenTree> = args.into_iter().collect();
+    match args.as_slice() {
+        [TokenTree::Ident(key), TokenTree::Punct(eq), TokenTree::Literal(value)] if key.to_string() == "rename" && eq.as_char() == '=' => {
+            string_literal(&value.to_string()).map(Some)
+        }
+        _ => Err("expected `#[typedef(rename = \"...\")]`".into()),
+    }
+}
+
+/// Get the value of a string literal without escapes.
+fn string_literal(literal: &str) -> Result<String, String> {
+    if literal.len() >= 2 && literal.starts_with('"') && literal.ends_with('"') && !literal.contains('\\') {
+        Ok(literal[1..literal.len() - 1].into())
+    } else {
+        Err("the name in `#[typedef(rename = \"...\")]` must be a string without escapes".into())
+    }
+}